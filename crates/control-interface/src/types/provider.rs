@@ -16,6 +16,9 @@ pub struct ProviderDescription {
     /// Image reference for this provider, if applicable
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) image_ref: Option<String>,
+    /// Manifest digest of the provider image, if it was fetched from an OCI registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) digest: Option<String>,
     /// Name of the provider, if one exists
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) name: Option<String>,
@@ -39,6 +42,11 @@ impl ProviderDescription {
         self.image_ref.as_deref()
     }
 
+    /// Get the manifest digest of the provider image
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
     /// Get the name of the provider
     pub fn name(&self) -> Option<&str> {
         self.name.as_deref()
@@ -66,6 +74,7 @@ impl ProviderDescription {
 pub struct ProviderDescriptionBuilder {
     id: Option<ComponentId>,
     image_ref: Option<String>,
+    digest: Option<String>,
     name: Option<String>,
     revision: Option<i32>,
     annotations: Option<BTreeMap<String, String>>,
@@ -86,6 +95,13 @@ impl ProviderDescriptionBuilder {
         self
     }
 
+    /// Manifest digest of the provider image, if it was fetched from an OCI registry
+    #[must_use]
+    pub fn digest(mut self, v: &str) -> Self {
+        self.digest = Some(v.into());
+        self
+    }
+
     /// Name of the provider, if one exists
     #[must_use]
     pub fn name(mut self, v: &str) -> Self {
@@ -113,6 +129,7 @@ impl ProviderDescriptionBuilder {
         Ok(ProviderDescription {
             id: self.id.ok_or_else(|| "id is required".to_string())?,
             image_ref: self.image_ref,
+            digest: self.digest,
             name: self.name,
             revision: self.revision.unwrap_or_default(),
             annotations: self.annotations,
@@ -132,6 +149,7 @@ mod tests {
             ProviderDescription {
                 id: "id".into(),
                 image_ref: Some("ref".into()),
+                digest: Some("sha256:abc".into()),
                 name: Some("name".into()),
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 revision: 0,
@@ -139,6 +157,7 @@ mod tests {
             ProviderDescription::builder()
                 .id("id")
                 .image_ref("ref")
+                .digest("sha256:abc")
                 .name("name")
                 .annotations(BTreeMap::from([("a".into(), "b".into())]))
                 .revision(0)
@@ -1,15 +1,32 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::env::temp_dir;
+use std::ffi::OsString;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 
-use anyhow::{bail, Context as _};
+use anyhow::{bail, ensure, Context as _};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::{BoxFuture, Shared};
+use futures::{stream, FutureExt as _, StreamExt as _};
 use oci_client::client::ClientProtocol;
-use oci_client::client::ImageData;
+use oci_client::errors::{OciDistributionError, OciErrorCode};
+use oci_client::manifest::{OciDescriptor, OciImageManifest, OciManifest};
 use oci_client::Reference;
 use oci_wasm::WASM_LAYER_MEDIA_TYPE;
-use oci_wasm::WASM_MANIFEST_MEDIA_TYPE;
+use oci_wasm::WASM_MANIFEST_CONFIG_MEDIA_TYPE;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, debug_span, field, instrument, warn, Instrument as _, Span};
 use wascap::jwt;
 
 use crate::RegistryConfig;
@@ -18,6 +35,62 @@ use crate::{tls, UseParFileCache};
 const PROVIDER_ARCHIVE_MEDIA_TYPE: &str = "application/vnd.wasmcloud.provider.archive.layer.v1+par";
 const WASM_MEDIA_TYPE: &str = "application/vnd.module.wasm.content.layer.v1+wasm";
 const OCI_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar";
+const COMPONENT_ARTIFACT_TYPE: &str = "application/vnd.wasmcloud.component.v1+wasm";
+const PROVIDER_ARCHIVE_ARTIFACT_TYPE: &str = "application/vnd.wasmcloud.provider.archive.v1+par";
+
+/// Maximum number of references fetched concurrently by [`OciFetcher::warm`]
+const WARM_CONCURRENCY: usize = 4;
+
+/// User-Agent sent to registries unless overridden with [`OciFetcher::with_user_agent`]
+const DEFAULT_USER_AGENT: &str = concat!("wasmcloud/", env!("CARGO_PKG_VERSION"));
+
+/// User-Agent strings set with [`OciFetcher::with_user_agent`]. The OCI client only accepts
+/// `'static` strings, so each distinct value is leaked exactly once and reused afterwards
+static USER_AGENTS: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(Mutex::default);
+
+/// Number of [`FetchEvent`]s buffered for subscribers before the oldest are dropped
+const FETCH_EVENTS_CAPACITY: usize = 256;
+
+/// Version of the [`CacheMetadata`] format written to the OCI cache
+const CACHE_METADATA_VERSION: u32 = 1;
+
+/// Default number of times a rate-limited registry request is retried
+const DEFAULT_RATE_LIMIT_RETRIES: u32 = 3;
+
+/// Default upper bound on how long to wait before retrying a rate-limited registry request
+const DEFAULT_MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Default time allowed for establishing a connection to a registry. Kept short, so that an
+/// unreachable registry fails the fetch quickly
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time allowed between reads of a registry response. This bounds how long a stalled
+/// connection is kept open, rather than the total duration of a download, so large artifacts
+/// still download as long as data keeps arriving
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Default number of times resolving the manifest to revalidate a cached artifact against is
+/// retried after failing transiently
+const DEFAULT_MANIFEST_RETRIES: u32 = 2;
+
+/// Default initial delay before retrying to resolve the manifest to revalidate a cached artifact
+/// against. Doubled on every attempt
+const DEFAULT_MANIFEST_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Initial delay before retrying a rate-limited request when the registry does not send a
+/// `Retry-After` header. Doubled on every attempt
+const RATE_LIMIT_FALLBACK_DELAY: Duration = Duration::from_secs(1);
+
+/// Username sent along with tokens read from the file set with [`OciFetcher::with_token_file`].
+/// Registries accepting tokens as Basic credentials ignore it, or expect this placeholder
+const TOKEN_FILE_USERNAME: &str = "<token>";
+
+/// Number of times a token file is read before giving up if it does not exist, e.g. while it is
+/// being replaced by a rotation
+const TOKEN_FILE_READ_ATTEMPTS: u32 = 5;
+
+/// Delay between attempts to read a token file that does not exist
+const TOKEN_FILE_RETRY_DELAY: Duration = Duration::from_millis(100);
 
 /// Whether to update an OCI artifact cache
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -28,24 +101,702 @@ pub enum OciArtifactCacheUpdate {
     Ignore,
     /// Update the cache
     Update,
+    /// Always pull from the registry, even if the cached artifact is up to date, and then update
+    /// the cache
+    ForceRefresh,
+}
+
+/// When an [`OciFetcher`] contacts the registry for artifacts that may already be cached.
+///
+/// Fetching references tagged `latest` must still be allowed explicitly, regardless of the
+/// policy. As a `latest` tag is expected to move, [`PullPolicy::IfNotPresent`] and
+/// [`PullPolicy::Never`] will keep serving the copy that was cached first, so prefer
+/// [`PullPolicy::Revalidate`] or [`PullPolicy::Always`] when allowing `latest`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullPolicy {
+    /// Use the cached copy of an artifact if the registry still resolves its reference to the
+    /// same digest, otherwise pull it
+    #[default]
+    Revalidate,
+    /// Always pull artifacts from the registry, replacing any cached copy
+    Always,
+    /// Use any cached copy of an artifact without contacting the registry, and only pull
+    /// artifacts that are not cached
+    IfNotPresent,
+    /// Never contact the registry, only fetching artifacts that are cached
+    Never,
+}
+
+impl FromStr for PullPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "revalidate" => Ok(Self::Revalidate),
+            "always" => Ok(Self::Always),
+            "ifnotpresent" => Ok(Self::IfNotPresent),
+            "never" => Ok(Self::Never),
+            policy => bail!(
+                "unsupported pull policy: {policy:?}, did you mean 'revalidate', 'always', 'if-not-present' or 'never'?"
+            ),
+        }
+    }
+}
+
+/// Errors that can occur while fetching OCI artifacts
+///
+/// The fetch methods on [`OciFetcher`] return [`anyhow::Error`]s with this type attached as the
+/// underlying cause, which can be retrieved using [`OciFetchError::find`].
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum OciFetchError {
+    /// The reference is tagged `latest`, which is not allowed by the fetcher configuration
+    #[error("fetching images tagged 'latest' is currently prohibited in this host. This option can be overridden with WASMCLOUD_OCI_ALLOW_LATEST")]
+    LatestDisallowed,
+    /// The reference could not be parsed
+    #[error("invalid OCI reference: {0}")]
+    InvalidReference(#[from] oci_client::ParseError),
+    /// The registry rejected the provided credentials
+    #[error("not authorized to fetch artifact: {0}")]
+    Unauthorized(#[source] OciDistributionError),
+    /// The artifact does not exist in the registry
+    #[error("artifact not found: {0}")]
+    NotFound(#[source] OciDistributionError),
+    /// The digest of the artifact is not on the configured list of allowed digests
+    #[error("artifact digest `{digest}` is not allowed")]
+    DigestNotAllowed {
+        /// The digest of the artifact's manifest, which is empty if it is unknown
+        digest: String,
+    },
+    /// The fetch was cancelled
+    #[error("fetch was cancelled")]
+    Cancelled,
+    /// The artifact is not cached, and the pull policy does not allow pulling it
+    #[error("artifact is not cached, and the pull policy prohibits pulling it")]
+    NotCached,
+    /// The artifact carries no wascap claims, but signed artifacts are required
+    #[error("artifact is not signed, but signed artifacts are required")]
+    Unsigned,
+    /// The artifact is signed by an issuer that is not on the configured list of required issuers
+    #[error("artifact is signed by `{issuer}`, which is not a trusted issuer")]
+    UntrustedIssuer {
+        /// The public key of the issuer of the artifact's claims
+        issuer: String,
+    },
+    /// The manifest declares an OCI 1.1 `artifactType` that is not one of the expected kinds of
+    /// artifact
+    #[error("unsupported artifact type `{artifact_type}`, expected one of {expected:?}")]
+    UnsupportedArtifactType {
+        /// The artifact type declared by the manifest
+        artifact_type: String,
+        /// The artifact types that would have been accepted
+        expected: Vec<String>,
+    },
+    /// The artifact is not a valid wasmCloud artifact
+    #[error("Found invalid OCI wasm artifact, expected single wasm layer, found {layers} layers")]
+    InvalidArtifact {
+        /// The number of wasm layers found in the artifact
+        layers: usize,
+    },
+    /// The artifact is larger than the configured maximum size
+    #[error("artifact size of {size} bytes exceeds the maximum of {max} bytes")]
+    TooLarge {
+        /// The size of the artifact
+        size: u64,
+        /// The maximum allowed size
+        max: u64,
+    },
+    /// None of the artifact's layers has an accepted media type
+    #[error("artifact has no layer with an accepted media type, found {media_types:?}")]
+    NoMatchingLayer {
+        /// The media types of the layers found in the artifact
+        media_types: Vec<String>,
+    },
+    /// The reference resolves to an image index without a manifest for the platform being fetched
+    /// for
+    #[error("image index has no manifest for platform `{platform}`, found {available:?}")]
+    NoMatchingPlatform {
+        /// The platform being fetched for
+        platform: String,
+        /// The platforms of the manifests in the image index
+        available: Vec<String>,
+    },
+    /// The registry could not be reached
+    #[error("network error: {0}")]
+    Network(#[source] OciDistributionError),
+    /// The registry kept rate-limiting requests after the configured number of retries
+    #[error("rate limited by registry (retry after: {retry_after:?})")]
+    RateLimited {
+        /// How long the registry asked to wait before retrying, if it said so
+        retry_after: Option<Duration>,
+    },
+    /// The registry returned an error
+    #[error("registry error: {0}")]
+    Registry(#[source] OciDistributionError),
+    /// Reading from or writing to the OCI cache failed
+    #[error("OCI cache error: {0}")]
+    Cache(#[source] std::io::Error),
+}
+
+impl OciFetchError {
+    /// Returns the [`OciFetchError`] that caused `err`, if any
+    #[must_use]
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.chain().find_map(|e| e.downcast_ref::<Self>())
+    }
+
+    /// Whether retrying the fetch might succeed
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Network(_)
+                | Self::RateLimited { .. }
+                | Self::Registry(OciDistributionError::ServerError { .. })
+        )
+    }
+}
+
+impl From<OciDistributionError> for OciFetchError {
+    fn from(err: OciDistributionError) -> Self {
+        if is_rate_limited(&err) {
+            return Self::RateLimited { retry_after: None };
+        }
+        let has_code = |codes: &[OciErrorCode]| match &err {
+            OciDistributionError::RegistryError { envelope, .. } => {
+                envelope.errors.iter().any(|e| codes.contains(&e.code))
+            }
+            _ => false,
+        };
+        if has_code(&[OciErrorCode::Unauthorized, OciErrorCode::Denied]) {
+            return Self::Unauthorized(err);
+        }
+        if has_code(&[
+            OciErrorCode::ManifestUnknown,
+            OciErrorCode::BlobUnknown,
+            OciErrorCode::NameUnknown,
+        ]) {
+            return Self::NotFound(err);
+        }
+        match err {
+            OciDistributionError::UnauthorizedError { .. }
+            | OciDistributionError::AuthenticationFailure(_) => Self::Unauthorized(err),
+            OciDistributionError::ImageManifestNotFoundError(_) => Self::NotFound(err),
+            OciDistributionError::RequestError(_) | OciDistributionError::IoError(_) => {
+                Self::Network(err)
+            }
+            _ => Self::Registry(err),
+        }
+    }
+}
+
+/// Whether the registry rejected a request because of rate limiting (HTTP 429)
+fn is_rate_limited(err: &OciDistributionError) -> bool {
+    match err {
+        OciDistributionError::RegistryError { envelope, .. } => envelope
+            .errors
+            .iter()
+            .any(|e| e.code == OciErrorCode::Toomanyrequests),
+        OciDistributionError::RequestError(e) => {
+            e.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+        }
+        OciDistributionError::ServerError { code, .. } => *code == 429,
+        _ => false,
+    }
+}
+
+/// Parses the value of a `Retry-After` header, which is either a number of seconds or an
+/// HTTP-date
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// The kind of wasmCloud artifact being fetched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ArtifactKind {
+    /// A WebAssembly component
+    Component,
+    /// A capability provider archive
+    Provider,
+}
+
+impl ArtifactKind {
+    /// Layer media types accepted for this kind of artifact out of the box
+    #[must_use]
+    pub fn default_media_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::Component => &[WASM_MEDIA_TYPE, OCI_MEDIA_TYPE, WASM_LAYER_MEDIA_TYPE],
+            Self::Provider => &[PROVIDER_ARCHIVE_MEDIA_TYPE, OCI_MEDIA_TYPE],
+        }
+    }
+
+    /// OCI 1.1 manifest `artifactType`s identifying this kind of artifact
+    #[must_use]
+    pub fn artifact_types(&self) -> &'static [&'static str] {
+        match self {
+            Self::Component => &[WASM_MANIFEST_CONFIG_MEDIA_TYPE, COMPONENT_ARTIFACT_TYPE],
+            Self::Provider => &[PROVIDER_ARCHIVE_ARTIFACT_TYPE],
+        }
+    }
+}
+
+/// Platform whose manifest is selected when a reference resolves to an OCI image index, named
+/// like the `os` and `architecture` of image index entries, i.e. using Go's `GOOS` and `GOARCH`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+    /// The operating system, e.g. `linux`
+    pub os: String,
+    /// The CPU architecture, e.g. `amd64`
+    pub architecture: String,
+}
+
+impl Platform {
+    /// Creates a platform from the `os` and `architecture` used in image index entries
+    pub fn new(os: impl Into<String>, architecture: impl Into<String>) -> Self {
+        Self {
+            os: os.into(),
+            architecture: architecture.into(),
+        }
+    }
+
+    /// Returns the platform the host was compiled for
+    #[must_use]
+    pub fn current() -> Self {
+        let os = match std::env::consts::OS {
+            "macos" => "darwin",
+            os => os,
+        };
+        let architecture = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "x86" => "386",
+            "aarch64" => "arm64",
+            "powerpc64" if cfg!(target_endian = "little") => "ppc64le",
+            "powerpc64" => "ppc64",
+            "loongarch64" => "loong64",
+            arch => arch,
+        };
+        Self::new(os, architecture)
+    }
+
+    fn matches(&self, platform: &oci_client::manifest::Platform) -> bool {
+        platform.os == self.os && platform.architecture == self.architecture
+    }
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self::current()
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.os, self.architecture)
+    }
+}
+
+/// Outcome of an OCI fetch, as reported to [`FetchMetrics`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FetchOutcome {
+    /// The artifact was served from the cache
+    Hit,
+    /// The artifact was downloaded from the registry
+    Miss,
+    /// The fetch failed
+    Error,
+}
+
+impl FetchOutcome {
+    /// Returns the outcome as a metric label value
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Miss => "miss",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Receiver of metrics about fetches performed by an [`OciFetcher`], e.g. to export pull counts,
+/// cache hit ratios, downloaded bytes and fetch latencies per registry
+pub trait FetchMetrics: Send + Sync {
+    /// Called once per fetch with the registry host, the outcome, the number of bytes downloaded
+    /// from the registry and the total time the fetch took
+    fn record_fetch(
+        &self,
+        registry: &str,
+        outcome: FetchOutcome,
+        downloaded: u64,
+        elapsed: Duration,
+    );
+
+    /// Called with the usage of the on-disk OCI cache whenever artifacts are written to or
+    /// removed from it, e.g. to export gauges of how full the cache is
+    fn record_cache_usage(&self, _usage: CacheUsage) {}
+}
+
+/// Lifecycle event of a fetch performed by an [`OciFetcher`], as delivered to subscribers of
+/// [`OciFetcher::subscribe`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FetchEvent {
+    /// Normalized reference being fetched
+    pub reference: String,
+    /// What happened
+    pub kind: FetchEventKind,
+}
+
+/// Kind of a [`FetchEvent`].
+///
+/// Every fetch starts with [`FetchEventKind::FetchStarted`] and ends with either
+/// [`FetchEventKind::FetchCompleted`] or [`FetchEventKind::FetchFailed`]. In between,
+/// [`FetchEventKind::ManifestResolved`] is sent once the manifest digest is known, followed by
+/// [`FetchEventKind::LayerProgress`] for every downloaded layer on a cache miss, or by
+/// [`FetchEventKind::CacheHit`] right before completing on a cache hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FetchEventKind {
+    /// The fetch started
+    FetchStarted,
+    /// The reference was resolved to the manifest with `digest`
+    ManifestResolved {
+        /// Digest of the manifest
+        digest: String,
+    },
+    /// The artifact is served from the cache
+    CacheHit,
+    /// A layer of the artifact was downloaded
+    LayerProgress {
+        /// Digest of the downloaded layer
+        digest: String,
+        /// Number of bytes of the artifact downloaded so far
+        downloaded: u64,
+        /// Total size of the artifact's layers, as declared by the manifest
+        total: u64,
+    },
+    /// The fetch completed
+    FetchCompleted {
+        /// Size of the artifact in bytes
+        bytes: u64,
+        /// Number of bytes downloaded from the registry, which is zero for cache hits
+        downloaded: u64,
+        /// How long the fetch took
+        duration: Duration,
+    },
+    /// The fetch failed
+    FetchFailed {
+        /// The error the fetch failed with
+        error: String,
+    },
+}
+
+/// Usage of the on-disk OCI cache, as reported to [`FetchMetrics`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheUsage {
+    /// Total size of the cached blobs, counting blobs shared by several references only once
+    pub bytes: u64,
+    /// Number of cached references
+    pub entries: usize,
+    /// Size the cache is pruned to, if set with [`OciFetcher::with_max_cache_bytes`]
+    pub limit: Option<u64>,
+}
+
+/// Cache an evicted entry was removed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionSource {
+    /// The in-memory cache enabled with [`OciFetcher::with_memory_cache`], which evicts the least
+    /// recently used components once full
+    Memory,
+    /// The on-disk OCI cache, pruned with [`OciFetcher::prune_cache`]
+    Disk,
+}
+
+/// An entry removed from a cache of an [`OciFetcher`], as reported to the callback set with
+/// [`OciFetcher::with_eviction_callback`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvictedEntry {
+    /// Cache the entry was removed from
+    pub source: EvictionSource,
+    /// Key of the entry, which is the manifest digest for in-memory entries and the cache key
+    /// derived from the reference for on-disk entries
+    pub key: String,
+    /// Manifest digest of the evicted artifact, if known
+    pub digest: Option<String>,
+    /// Number of bytes reclaimed. On-disk entries sharing their content with remaining entries
+    /// reclaim nothing
+    pub bytes: u64,
+}
+
+/// Callback invoked with every entry evicted from a cache of an [`OciFetcher`]
+pub type EvictionCallback = Arc<dyn Fn(EvictedEntry) + Send + Sync>;
+
+#[derive(Clone)]
+struct EvictionHook(EvictionCallback);
+
+impl std::fmt::Debug for EvictionHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvictionHook").finish_non_exhaustive()
+    }
+}
+
+/// Registry clients built by an [`OciFetcher`], keyed by the registries they may access over
+/// plain HTTP. Reusing clients across fetches keeps connections and TLS sessions alive
+#[derive(Clone, Default)]
+struct ClientCache(Arc<Mutex<HashMap<Vec<String>, RegistryClient>>>);
+
+/// Error of a fetch shared by several callers. The original error is exposed as its source, so
+/// that [`OciFetchError::find`] still finds the cause
+#[derive(Debug, Clone)]
+struct SharedError(Arc<anyhow::Error>);
+
+impl std::fmt::Display for SharedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("failed to fetch component")
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        let err: &(dyn std::error::Error + Send + Sync + 'static) = self.0.as_ref().as_ref();
+        Some(err)
+    }
+}
+
+/// A component fetch that can be awaited by several callers
+type SharedFetch = Shared<BoxFuture<'static, Result<Bytes, SharedError>>>;
+
+/// Component fetches currently in flight, keyed by reference
+#[derive(Clone, Default)]
+struct FetchGroup(Arc<Mutex<HashMap<String, SharedFetch>>>);
+
+impl std::fmt::Debug for FetchGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FetchGroup").finish_non_exhaustive()
+    }
+}
+
+/// A file holding a registry token, e.g. a projected Kubernetes service account token
+#[derive(Clone, Debug)]
+struct TokenFile {
+    path: PathBuf,
+    /// The token last read from the file
+    current: Arc<Mutex<Option<String>>>,
+}
+
+impl TokenFile {
+    /// Reads the token, retrying for a while if the file does not exist. Returns the token along
+    /// with whether it replaced a different token read previously
+    async fn read(&self) -> anyhow::Result<(String, bool)> {
+        let mut attempt = 1;
+        let token = loop {
+            match fs::read_to_string(&self.path).await {
+                Ok(token) => break token.trim().to_string(),
+                Err(e)
+                    if e.kind() == std::io::ErrorKind::NotFound
+                        && attempt < TOKEN_FILE_READ_ATTEMPTS =>
+                {
+                    attempt += 1;
+                    tokio::time::sleep(TOKEN_FILE_RETRY_DELAY).await;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!(
+                            "failed to read registry token from `{}`",
+                            self.path.display()
+                        )
+                    })
+                }
+            }
+        };
+        ensure!(
+            !token.is_empty(),
+            "registry token file `{}` is empty",
+            self.path.display()
+        );
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        if current.as_deref() == Some(&token) {
+            return Ok((token, false));
+        }
+        let rotated = current.replace(token.clone()).is_some();
+        Ok((token, rotated))
+    }
+}
+
+/// A registry client along with the root certificates it trusts
+type RegistryClient = (oci_client::Client, Arc<[oci_client::client::Certificate]>);
+
+/// Running totals of the on-disk OCI cache, shared by all clones of a fetcher. The cache
+/// directory is only scanned the first time usage is needed and after bulk changes, such as
+/// prunes, while writes by fetches update the totals directly.
+///
+/// Blobs and entries are tracked by path and key rather than counted, so that concurrent fetches
+/// writing the same content are accounted for once
+#[derive(Clone, Default)]
+struct CacheUsageTracker(Arc<tokio::sync::Mutex<Option<CacheUsageIndex>>>);
+
+#[derive(Debug)]
+struct CacheUsageIndex {
+    dir: PathBuf,
+    blobs: HashMap<PathBuf, u64>,
+    entries: HashSet<String>,
+    bytes: u64,
+}
+
+impl CacheUsageIndex {
+    /// Builds the index of the OCI cache under `dir` from its contents
+    async fn scan(dir: &Path) -> anyhow::Result<Self> {
+        let mut index = Self {
+            dir: dir.to_path_buf(),
+            blobs: HashMap::default(),
+            entries: HashSet::default(),
+            bytes: 0,
+        };
+        for entry in stored_entries(dir).await? {
+            index.insert(entry.index.key, entry.blob, entry.size);
+        }
+        Ok(index)
+    }
+
+    fn insert(&mut self, key: String, blob: PathBuf, size: u64) {
+        self.entries.insert(key);
+        if self.blobs.insert(blob, size).is_none() {
+            self.bytes = self.bytes.saturating_add(size);
+        }
+    }
+
+    fn usage(&self, limit: Option<u64>) -> CacheUsage {
+        CacheUsage {
+            bytes: self.bytes,
+            entries: self.entries.len(),
+            limit,
+        }
+    }
+}
+
+impl std::fmt::Debug for CacheUsageTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheUsageTracker").finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for ClientCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCache").finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+struct MetricsHook(Arc<dyn FetchMetrics>);
+
+#[derive(Clone)]
+struct CacheStoreHook(Arc<dyn CacheStore>);
+
+impl std::fmt::Debug for CacheStoreHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CacheStoreHook").finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MetricsHook").finish_non_exhaustive()
+    }
 }
 
 /// OCI artifact fetcher
+///
+/// The default fetcher authenticates with the credentials read by [`crate::RegistryAuth::from_env`]
 #[derive(Clone, Debug)]
 pub struct OciFetcher {
     additional_ca_paths: Vec<PathBuf>,
+    additional_ca_pems: Vec<Vec<u8>>,
     allow_latest: bool,
     allow_insecure: bool,
+    insecure_registries: HashSet<String>,
     auth: oci_client::secrets::RegistryAuth,
+    registry_auths: HashMap<String, oci_client::secrets::RegistryAuth>,
+    media_types: HashMap<ArtifactKind, Vec<String>>,
+    rate_limit_retries: u32,
+    max_retry_after: Duration,
+    manifest_retries: u32,
+    manifest_retry_delay: Duration,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    user_agent: &'static str,
+    require_signed_components: bool,
+    memory_cache: Option<Arc<Mutex<MemoryCache>>>,
+    metrics: Option<MetricsHook>,
+    download_tmp_dir: Option<PathBuf>,
+    clients: ClientCache,
+    fetches: FetchGroup,
+    manifest_ttl: Option<Duration>,
+    cache_store: Option<CacheStoreHook>,
+    max_artifact_bytes: Option<u64>,
+    pull_policy: PullPolicy,
+    allowed_digests: Option<HashSet<String>>,
+    required_provider_issuers: Option<HashSet<String>>,
+    cache_dir: Option<PathBuf>,
+    eviction_callback: Option<EvictionHook>,
+    verify_extracted_providers: bool,
+    anonymous_fallback: bool,
+    token_file: Option<TokenFile>,
+    max_cache_bytes: Option<u64>,
+    cache_usage: CacheUsageTracker,
+    platform: Platform,
+    default_registry: Option<String>,
+    events: Arc<OnceLock<broadcast::Sender<FetchEvent>>>,
 }
 
 impl Default for OciFetcher {
     fn default() -> Self {
         Self {
             additional_ca_paths: Vec::default(),
+            additional_ca_pems: Vec::default(),
             allow_latest: false,
             allow_insecure: false,
-            auth: oci_client::secrets::RegistryAuth::Anonymous,
+            insecure_registries: HashSet::default(),
+            auth: (&crate::RegistryAuth::from_env()).into(),
+            registry_auths: HashMap::default(),
+            media_types: HashMap::default(),
+            rate_limit_retries: DEFAULT_RATE_LIMIT_RETRIES,
+            max_retry_after: DEFAULT_MAX_RETRY_AFTER,
+            manifest_retries: DEFAULT_MANIFEST_RETRIES,
+            manifest_retry_delay: DEFAULT_MANIFEST_RETRY_DELAY,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT,
+            require_signed_components: false,
+            memory_cache: None,
+            metrics: None,
+            download_tmp_dir: None,
+            clients: ClientCache::default(),
+            fetches: FetchGroup::default(),
+            manifest_ttl: None,
+            cache_store: None,
+            max_artifact_bytes: None,
+            pull_policy: PullPolicy::default(),
+            allowed_digests: None,
+            required_provider_issuers: None,
+            cache_dir: None,
+            eviction_callback: None,
+            verify_extracted_providers: false,
+            anonymous_fallback: false,
+            token_file: None,
+            max_cache_bytes: None,
+            cache_usage: CacheUsageTracker::default(),
+            platform: Platform::current(),
+            default_registry: None,
+            events: Arc::default(),
         }
     }
 }
@@ -65,6 +816,7 @@ impl From<&RegistryConfig> for OciFetcher {
             allow_latest: *allow_latest,
             allow_insecure: *allow_insecure,
             additional_ca_paths: additional_ca_paths.clone(),
+            ..Self::default()
         }
     }
 }
@@ -84,191 +836,5656 @@ impl From<RegistryConfig> for OciFetcher {
             allow_latest,
             allow_insecure,
             additional_ca_paths,
+            ..Self::default()
+        }
+    }
+}
+
+/// Bounded in-memory cache of component bytes keyed by manifest digest, which evicts the least
+/// recently used entries once full
+#[derive(Debug)]
+struct MemoryCache {
+    max_bytes: usize,
+    used: usize,
+    entries: HashMap<String, Bytes>,
+    /// Digests of the cached entries, from least to most recently used
+    order: VecDeque<String>,
+    /// Digests tagged references of cached entries resolved to, along with when they were resolved
+    tags: HashMap<String, (String, Instant)>,
+}
+
+impl MemoryCache {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            used: 0,
+            entries: HashMap::default(),
+            order: VecDeque::default(),
+            tags: HashMap::default(),
+        }
+    }
+
+    /// Returns the digest the tagged `reference` resolved to, if it was resolved within `ttl`
+    fn resolved(&self, reference: &str, ttl: Duration) -> Option<String> {
+        let (digest, resolved_at) = self.tags.get(reference)?;
+        (resolved_at.elapsed() < ttl).then(|| digest.clone())
+    }
+
+    /// Records that the tagged `reference` resolved to the component with manifest `digest`, as
+    /// long as that component is cached
+    fn resolve(&mut self, reference: String, digest: String) {
+        if self.entries.contains_key(&digest) {
+            self.tags.insert(reference, (digest, Instant::now()));
         }
     }
+
+    fn get(&mut self, digest: &str) -> Option<Bytes> {
+        let bytes = self.entries.get(digest)?.clone();
+        self.touch(digest);
+        Some(bytes)
+    }
+
+    fn touch(&mut self, digest: &str) {
+        if let Some(pos) = self.order.iter().position(|d| d == digest) {
+            if let Some(digest) = self.order.remove(pos) {
+                self.order.push_back(digest);
+            }
+        }
+    }
+
+    /// Inserts `bytes` of the component with manifest `digest`, returning the entries evicted to
+    /// make room for it
+    fn insert(&mut self, digest: String, bytes: Bytes) -> Vec<EvictedEntry> {
+        let mut evicted = Vec::new();
+        if bytes.len() > self.max_bytes {
+            return evicted;
+        }
+        if self.entries.contains_key(&digest) {
+            self.touch(&digest);
+            return evicted;
+        }
+        while self.used + bytes.len() > self.max_bytes {
+            let Some(key) = self.order.pop_front() else {
+                break;
+            };
+            if let Some(removed) = self.entries.remove(&key) {
+                self.used -= removed.len();
+                self.tags.retain(|_, (digest, _)| *digest != key);
+                evicted.push(EvictedEntry {
+                    source: EvictionSource::Memory,
+                    digest: Some(key.clone()),
+                    key,
+                    bytes: removed.len() as u64,
+                });
+            }
+        }
+        self.used += bytes.len();
+        self.order.push_back(digest.clone());
+        self.entries.insert(digest, bytes);
+        evicted
+    }
 }
 
-/// Default directory in which OCI artifacts are cached
+async fn read_bytes(path: &Path) -> anyhow::Result<Bytes> {
+    fs::read(path)
+        .await
+        .map(Bytes::from)
+        .with_context(|| format!("failed to read `{}`", path.display()))
+}
+
+/// Environment variable overriding the default directory in which OCI artifacts are cached
+pub const OCI_CACHE_DIR_ENV: &str = "WASMCLOUD_OCI_CACHE_DIR";
+
+/// Default directory in which OCI artifacts are cached, which is the directory set in the
+/// `WASMCLOUD_OCI_CACHE_DIR` environment variable if set, or a directory within the system's
+/// temporary directory otherwise. The directory is created if it does not exist
 pub async fn oci_cache_dir() -> anyhow::Result<PathBuf> {
-    let path = temp_dir().join("wasmcloud_ocicache");
+    create_cache_dir(None).await
+}
+
+/// Returns the directory in which OCI artifacts are cached, in order of precedence the
+/// `configured` directory, the directory set in the environment (`from_env`) or the default
+fn resolve_cache_dir(configured: Option<&Path>, from_env: Option<OsString>) -> PathBuf {
+    match (configured, from_env) {
+        (Some(dir), _) => dir.to_path_buf(),
+        (None, Some(dir)) if !dir.is_empty() => PathBuf::from(dir),
+        (None, _) => temp_dir().join("wasmcloud_ocicache"),
+    }
+}
+
+/// Creates the OCI cache directory resolved by [`resolve_cache_dir`] if it does not exist yet
+async fn create_cache_dir(configured: Option<&Path>) -> anyhow::Result<PathBuf> {
+    let path = resolve_cache_dir(configured, std::env::var_os(OCI_CACHE_DIR_ENV));
     if !fs::try_exists(&path).await? {
         fs::create_dir_all(&path).await?;
     }
     Ok(path)
 }
 
-#[allow(unused)]
-async fn cache_oci_image(
-    image: ImageData,
-    cache_filepath: impl AsRef<Path>,
-    digest_filepath: impl AsRef<Path>,
-) -> std::io::Result<()> {
-    let mut cache_file = fs::File::create(cache_filepath).await?;
-    let content = image
-        .layers
-        .into_iter()
-        .flat_map(|l| l.data)
-        .collect::<Vec<_>>();
-    cache_file.write_all(&content).await?;
-    cache_file.flush().await?;
-    if let Some(digest) = image.digest {
-        let mut digest_file = fs::File::create(digest_filepath).await?;
-        digest_file.write_all(digest.as_bytes()).await?;
-        digest_file.flush().await?;
-    }
-    Ok(())
+/// Returns the path of a content-addressed blob within the OCI cache directory, e.g.
+/// `<dir>/blobs/sha256/<hex>` for a `sha256:<hex>` digest
+fn blob_path(dir: impl AsRef<Path>, digest: &str) -> PathBuf {
+    let (algorithm, hex) = digest.split_once(':').unwrap_or(("sha256", digest));
+    dir.as_ref().join("blobs").join(algorithm).join(hex)
 }
 
-fn prune_filepath(img: &str) -> String {
-    let mut img = img.replace(':', "_");
-    img = img.replace('/', "_");
-    img = img.replace('.', "_");
-    img
+fn sha256_digest(content: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(content)))
 }
 
-/// A type to indicate whether there was a cache hit or miss when loading artifacts
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum CacheResult {
-    Hit,
-    Miss,
+/// Writes `content` to the content-addressed blob store under `dir`, returning the blob path.
+/// Blobs that are already present are left untouched, which dedupes identical content shared by
+/// several references.
+///
+/// The content is first written to a temporary file in `tmp_dir`, or next to the blob if unset,
+/// and only moved into place once complete, so a partially-written blob is never observed.
+async fn write_blob(
+    dir: impl AsRef<Path>,
+    content: &[u8],
+    tmp_dir: Option<&Path>,
+) -> std::io::Result<(PathBuf, String)> {
+    let digest = sha256_digest(content);
+    let blob = blob_path(dir, &digest);
+    if fs::metadata(&blob).await.is_ok() {
+        return Ok((blob, digest));
+    }
+    if let Some(parent) = blob.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let local_tmp = blob.with_extension(format!("{}.tmp", ulid::Ulid::new()));
+    let tmp = TempFile(Some(match tmp_dir {
+        Some(tmp_dir) => {
+            fs::create_dir_all(tmp_dir).await?;
+            tmp_dir.join(local_tmp.file_name().unwrap_or_default())
+        }
+        None => local_tmp.clone(),
+    }));
+    let mut file = fs::File::create(tmp.path()).await?;
+    file.write_all(content).await?;
+    file.flush().await?;
+    drop(file);
+    match fs::rename(tmp.path(), &blob).await {
+        Ok(()) => tmp.persist(),
+        Err(e) if tmp.path() == local_tmp => return Err(e),
+        Err(_) => {
+            // The scratch directory may be on a different filesystem than the cache, in which case
+            // the blob is copied next to its final location first to keep the final move atomic
+            let local_tmp = TempFile(Some(local_tmp));
+            fs::copy(tmp.path(), local_tmp.path()).await?;
+            fs::rename(local_tmp.path(), &blob).await?;
+            local_tmp.persist();
+        }
+    }
+    Ok((blob, digest))
 }
 
-impl OciFetcher {
-    /// Fetch an OCI artifact to a path and return that path. Returns the path and whether or not
-    /// there was a cache hit/miss
-    pub async fn fetch_path(
-        &self,
-        output_dir: impl AsRef<Path>,
-        img: impl AsRef<str>,
-        accepted_media_types: Vec<&str>,
-        cache: OciArtifactCacheUpdate,
-    ) -> anyhow::Result<(PathBuf, CacheResult)> {
-        let output_dir = output_dir.as_ref();
-        let img = img.as_ref().to_lowercase(); // the OCI spec does not allow for capital letters in references
-        if !self.allow_latest && img.ends_with(":latest") {
-            bail!("fetching images tagged 'latest' is currently prohibited in this host. This option can be overridden with WASMCLOUD_OCI_ALLOW_LATEST")
-        }
-        let pruned_filepath = prune_filepath(&img);
-        let cache_file = output_dir.join(&pruned_filepath);
-        let mut digest_file = output_dir.join(&pruned_filepath).clone();
-        digest_file.set_extension("digest");
+/// A temporary file, which is removed when dropped unless it was moved into place. This way,
+/// nothing is left behind if a fetch is dropped while writing it
+struct TempFile(Option<PathBuf>);
 
-        let img = Reference::from_str(&img)?;
+impl TempFile {
+    fn path(&self) -> &Path {
+        self.0.as_deref().unwrap_or_else(|| Path::new(""))
+    }
 
-        let protocol = if self.allow_insecure {
-            ClientProtocol::HttpsExcept(vec![img.registry().to_string()])
-        } else {
-            ClientProtocol::Https
+    /// Keeps the file, as it was moved into place
+    fn persist(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let Some(path) = self.0.take() else {
+            return;
         };
-        let mut certs = tls::NATIVE_ROOTS_OCI.to_vec();
-        if !self.additional_ca_paths.is_empty() {
-            certs.extend(
-                tls::load_certs_from_paths(&self.additional_ca_paths)
-                    .context("failed to load CA certs from provided paths")?
-                    .iter()
-                    .map(|cert| oci_client::client::Certificate {
-                        encoding: oci_client::client::CertificateEncoding::Der,
-                        data: cert.to_vec(),
-                    }),
-            );
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!(tmp = %path.display(), ?e, "failed to remove temporary download file");
+            }
         }
-        let c = oci_client::Client::new(oci_client::client::ClientConfig {
-            protocol,
-            extra_root_certificates: certs,
-            ..Default::default()
-        });
+    }
+}
 
-        // In case of a cache miss where the file does not exist, pull a fresh OCI Image
-        if fs::metadata(&cache_file).await.is_ok() {
-            let (_, oci_digest) = c
-                .pull_manifest(&img, &self.auth)
-                .await
-                .context("failed to fetch OCI manifest")?;
-            // If the digest file doesn't exist that is ok, we just unwrap to an empty string
-            let file_digest = fs::read_to_string(&digest_file).await.unwrap_or_default();
-            if !oci_digest.is_empty() && !file_digest.is_empty() && file_digest == oci_digest {
-                return Ok((cache_file, CacheResult::Hit));
+/// Number of in-flight fetches per cache entry. Entries with fetches in flight must not be pruned
+static IN_FLIGHT: Lazy<Mutex<HashMap<PathBuf, usize>>> = Lazy::new(Mutex::default);
+
+/// Guard protecting a cache entry from being pruned while a fetch is in flight
+struct InFlightGuard(PathBuf);
+
+impl InFlightGuard {
+    fn new(entry: PathBuf) -> Self {
+        *IN_FLIGHT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(entry.clone())
+            .or_default() += 1;
+        Self(entry)
+    }
+
+    fn is_held(entry: &Path) -> bool {
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains_key(entry)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        let mut in_flight = IN_FLIGHT.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(count) = in_flight.get_mut(&self.0) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(&self.0);
             }
         }
+    }
+}
+
+/// Policy determining which entries are removed when pruning the OCI cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CachePrunePolicy {
+    /// Remove entries fetched longer ago than the given duration
+    OlderThan(Duration),
+    /// Remove entries that have not been used within the given duration
+    NotAccessedSince(Duration),
+    /// Remove the least recently used entries until the cache takes up at most the given number
+    /// of bytes
+    MaxSize(u64),
+}
+
+/// Statistics about a completed prune of the OCI cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CachePruneStats {
+    /// Number of cache entries removed
+    pub entries: usize,
+    /// Number of bytes reclaimed
+    pub bytes: u64,
+}
+
+impl CachePruneStats {
+    fn from_evicted(evicted: &[EvictedEntry]) -> Self {
+        Self {
+            entries: evicted.len(),
+            bytes: evicted.iter().map(|entry| entry.bytes).sum(),
+        }
+    }
+}
+
+/// An entry of the OCI cache, as listed by [`OciFetcher::list_cache`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheEntry {
+    /// Key the entry is stored under, see [`cache_key`]
+    pub key: String,
+    /// Normalized reference the entry was fetched or imported by, which is unknown for entries
+    /// cached by previous versions
+    pub reference: Option<String>,
+    /// Digest of the manifest the cached content was pulled from, if known
+    pub digest: Option<String>,
+    /// Size of the cached content in bytes
+    pub size: u64,
+    /// When the entry was last fetched
+    pub fetched_at: SystemTime,
+    /// When the entry was last used
+    pub accessed_at: SystemTime,
+    /// Metadata recorded when the entry was cached, which is unknown for entries cached by
+    /// previous versions
+    pub metadata: Option<CacheMetadata>,
+}
+
+/// Metadata recorded alongside each artifact in the OCI cache when it is cached, so that cached
+/// artifacts can be inspected without contacting the registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CacheMetadata {
+    /// Version of the metadata format
+    pub version: u32,
+    /// Digest of the manifest the content was pulled from, if known
+    #[serde(default)]
+    pub digest: Option<String>,
+    /// Media type of the first layer the content was assembled from
+    pub media_type: String,
+    /// Number of layers the content was assembled from
+    pub layers: usize,
+    /// Size of the content in bytes
+    pub size: u64,
+    /// When the content was cached
+    pub fetched_at: SystemTime,
+    /// Registry the content was pulled from, or would be revalidated against if it was imported
+    pub registry: String,
+}
+
+/// A reference stored in the OCI cache, as found on disk
+#[derive(Debug)]
+struct StoredEntry {
+    index: CacheIndex,
+    blob: PathBuf,
+    size: u64,
+    fetched_at: SystemTime,
+    accessed_at: SystemTime,
+}
+
+/// Lists the references stored in the OCI cache under `dir`
+async fn stored_entries(dir: &Path) -> anyhow::Result<Vec<StoredEntry>> {
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("failed to read OCI cache directory `{}`", dir.display()))?;
+    let mut stored = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("blob") {
+            continue;
+        }
+        let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let index = CacheIndex {
+            dir: dir.to_path_buf(),
+            key: key.to_string(),
+        };
+        let (Ok(index_md), Ok(digest)) = (entry.metadata().await, fs::read_to_string(&path).await)
+        else {
+            continue;
+        };
+        let blob = blob_path(dir, digest.trim());
+        let size = fs::metadata(&blob)
+            .await
+            .map(|md| md.len())
+            .unwrap_or_default();
+        let fetched_at = index_md.modified()?;
+        let accessed_at = index_md.accessed().unwrap_or(fetched_at).max(fetched_at);
+        stored.push(StoredEntry {
+            index,
+            blob,
+            size,
+            fetched_at,
+            accessed_at,
+        });
+    }
+    Ok(stored)
+}
+
+/// Appends the file at `path` within the OCI cache under `dir` to a cache archive
+async fn append_cache_file<W: AsyncWrite + Unpin + Send + 'static>(
+    archive: &mut tokio_tar::Builder<W>,
+    dir: &Path,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let name = path.strip_prefix(dir).unwrap_or(path);
+    let content = fs::read(path)
+        .await
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    let mut header = tokio_tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, content.as_slice())
+        .await
+        .with_context(|| format!("failed to add `{}` to cache archive", name.display()))
+}
+
+/// A file within a cache archive
+#[derive(Debug, PartialEq)]
+enum CacheArchivePath {
+    /// A blob with the given digest
+    Blob(String),
+    /// An index file of the reference with the given key
+    Index { key: String, extension: String },
+}
+
+/// Parses a path within a cache archive, returning `None` for anything that does not belong in
+/// the OCI cache, including paths that would escape it
+fn cache_archive_path(path: &Path) -> Option<CacheArchivePath> {
+    let components = path
+        .components()
+        .map(|component| match component {
+            std::path::Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    match components.as_slice() {
+        ["blobs", algorithm, hex]
+            if *algorithm == "sha256"
+                && hex.len() == 64
+                && hex.bytes().all(|b| b.is_ascii_hexdigit()) =>
+        {
+            Some(CacheArchivePath::Blob(format!("{algorithm}:{hex}")))
+        }
+        [name] => {
+            let (key, extension) = name.rsplit_once('.')?;
+            (!key.is_empty()
+                && ["blob", "digest", "json", "manifest", "mediatypes", "ref"].contains(&extension))
+            .then(|| CacheArchivePath::Index {
+                key: key.to_string(),
+                extension: extension.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Removes entries from the OCI cache under `dir` according to `policy`, returning the removed
+/// entries. Entries with a fetch in flight are skipped, and blobs are only removed once no
+/// remaining entry references them.
+async fn prune_oci_cache(
+    dir: &Path,
+    policy: CachePrunePolicy,
+) -> anyhow::Result<Vec<EvictedEntry>> {
+    let mut entries = stored_entries(dir).await?;
+    let now = SystemTime::now();
+    let elapsed = |t: SystemTime| now.duration_since(t).unwrap_or_default();
+    let mut prune: Vec<StoredEntry> = match policy {
+        CachePrunePolicy::OlderThan(age) => entries
+            .into_iter()
+            .filter(|entry| elapsed(entry.fetched_at) > age)
+            .collect(),
+        CachePrunePolicy::NotAccessedSince(age) => entries
+            .into_iter()
+            .filter(|entry| elapsed(entry.accessed_at) > age)
+            .collect(),
+        CachePrunePolicy::MaxSize(max) => {
+            // Keep the most recently used entries that fit, counting shared blobs only once
+            entries.sort_by_key(|entry| Reverse(entry.accessed_at));
+            let mut total = 0u64;
+            let mut kept = HashSet::new();
+            entries
+                .into_iter()
+                .filter(|entry| {
+                    if kept.contains(&entry.blob) {
+                        return false;
+                    }
+                    if total.saturating_add(entry.size) <= max {
+                        total += entry.size;
+                        kept.insert(entry.blob.clone());
+                        false
+                    } else {
+                        true
+                    }
+                })
+                .collect()
+        }
+    };
+    prune.retain(|entry| !InFlightGuard::is_held(&entry.index.legacy_file()));
+
+    let mut digests = Vec::with_capacity(prune.len());
+    for entry in &prune {
+        digests.push(fs::read_to_string(entry.index.digest_file()).await.ok());
+        for file in [
+            entry.index.blob_file(),
+            entry.index.digest_file(),
+            entry.index.manifest_file(),
+            entry.index.media_types_file(),
+            entry.index.reference_file(),
+            entry.index.metadata_file(),
+        ] {
+            match fs::remove_file(&file).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("failed to remove `{}`", file.display())))
+                }
+            }
+        }
+    }
+    // Only remove blobs that are no longer referenced by any remaining entry
+    let remaining = stored_entries(dir).await?;
+    let mut removed = HashSet::new();
+    let mut evicted = Vec::with_capacity(prune.len());
+    for (entry, digest) in prune.into_iter().zip(digests) {
+        let mut bytes = 0;
+        if !removed.contains(&entry.blob) && !remaining.iter().any(|e| e.blob == entry.blob) {
+            match fs::remove_file(&entry.blob).await {
+                Ok(()) => bytes = entry.size,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(anyhow::Error::new(e)
+                        .context(format!("failed to remove `{}`", entry.blob.display())))
+                }
+            }
+            removed.insert(entry.blob);
+        }
+        evicted.push(EvictedEntry {
+            source: EvictionSource::Disk,
+            key: entry.index.key,
+            digest,
+            bytes,
+        });
+    }
+    Ok(evicted)
+}
+
+/// Paths of the per-reference index files in the OCI cache directory
+#[derive(Debug)]
+struct CacheIndex {
+    dir: PathBuf,
+    key: String,
+}
+
+impl CacheIndex {
+    fn new(dir: impl AsRef<Path>, img: &str) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            key: cache_key(img),
+        }
+    }
+
+    /// File holding the digest of the manifest the cached content was pulled from
+    fn digest_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("digest")
+    }
+
+    /// File holding the manifest the cached content was pulled from
+    fn manifest_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("manifest")
+    }
+
+    /// File holding the media types of the layers the cached content was assembled from
+    fn media_types_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("mediatypes")
+    }
+
+    /// Whether the cached content was assembled only from layers of the accepted media types
+    async fn has_media_types(&self, accepted_media_types: &[&str]) -> bool {
+        let Ok(media_types) = fs::read_to_string(self.media_types_file()).await else {
+            return false;
+        };
+        let mut media_types = media_types.lines().peekable();
+        media_types.peek().is_some()
+            && media_types.all(|media_type| accepted_media_types.contains(&media_type))
+    }
+
+    /// File mapping the key back to the normalized reference the content was cached for, followed
+    /// by the digest of its manifest on the next line, if known. The key is derived from the
+    /// reference lossily, so this is what lets operators tell which artifact an entry holds
+    fn reference_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("ref")
+    }
+
+    /// Records the normalized reference the content is cached for, along with its manifest digest
+    async fn write_reference(
+        &self,
+        reference: &Reference,
+        digest: Option<&str>,
+    ) -> std::io::Result<()> {
+        let reference = reference.whole();
+        let digest = digest.unwrap_or_default();
+        fs::write(self.reference_file(), format!("{reference}\n{digest}\n")).await
+    }
+
+    /// File holding the [`CacheMetadata`] of the cached content as JSON
+    fn metadata_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("json")
+    }
+
+    /// Records the [`CacheMetadata`] of content cached for `reference`
+    async fn write_metadata(
+        &self,
+        reference: &Reference,
+        digest: Option<&str>,
+        media_types: &str,
+        size: u64,
+    ) -> std::io::Result<()> {
+        let metadata = CacheMetadata {
+            version: CACHE_METADATA_VERSION,
+            digest: digest.map(ToString::to_string),
+            media_type: media_types.lines().next().unwrap_or_default().to_string(),
+            layers: media_types.lines().count(),
+            size,
+            fetched_at: SystemTime::now(),
+            registry: reference.registry().to_string(),
+        };
+        fs::write(self.metadata_file(), serde_json::to_vec(&metadata)?).await
+    }
+
+    /// Returns the [`CacheMetadata`] of the cached content. Entries cached by previous versions
+    /// have no metadata, and metadata that cannot be parsed is treated the same way
+    async fn metadata(&self) -> Option<CacheMetadata> {
+        let metadata = fs::read(self.metadata_file()).await.ok()?;
+        match serde_json::from_slice(&metadata) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                debug!(key = self.key, ?e, "ignoring unreadable OCI cache metadata");
+                None
+            }
+        }
+    }
+
+    /// Returns the normalized reference the content is cached for and its manifest digest, as
+    /// far as they are known
+    async fn reference(&self) -> (Option<String>, Option<String>) {
+        let Ok(content) = fs::read_to_string(self.reference_file()).await else {
+            return (None, None);
+        };
+        let mut lines = content.lines().map(|line| {
+            Some(line.trim())
+                .filter(|line| !line.is_empty())
+                .map(ToString::to_string)
+        });
+        (lines.next().flatten(), lines.next().flatten())
+    }
+
+    /// File holding the digest of the cached content within the blob store
+    fn blob_file(&self) -> PathBuf {
+        self.dir.join(&self.key).with_extension("blob")
+    }
+
+    /// Location of the cached content in the flat, reference-keyed layout used before the cache
+    /// became content-addressable
+    fn legacy_file(&self) -> PathBuf {
+        self.dir.join(&self.key)
+    }
+
+    /// Returns the path of the cached blob for this reference, if there is one
+    async fn blob(&self) -> Option<PathBuf> {
+        if let Err(e) = self.migrate().await {
+            warn!(
+                key = self.key,
+                ?e,
+                "failed to migrate legacy OCI cache entry"
+            );
+        }
+        let digest = fs::read_to_string(self.blob_file()).await.ok()?;
+        let blob = blob_path(&self.dir, digest.trim());
+        fs::metadata(&blob).await.is_ok().then_some(blob)
+    }
+
+    /// Returns the path of the content cached in `store` for this reference, if there is any.
+    /// Content the store does not keep on the local filesystem is copied into the cache directory
+    async fn cached_file(&self, store: &dyn CacheStore) -> anyhow::Result<Option<PathBuf>> {
+        if let Some(path) = store.path(&self.key).await? {
+            return Ok(Some(path));
+        }
+        let Some(content) = store.get(&self.key).await? else {
+            return Ok(None);
+        };
+        let blob = self
+            .copy_content(&content)
+            .await
+            .map_err(OciFetchError::Cache)?;
+        Ok(Some(blob))
+    }
+
+    /// Copies content a [`CacheStore`] keeps elsewhere into the blob store and points this
+    /// reference at it, returning the blob path
+    async fn copy_content(&self, content: &[u8]) -> std::io::Result<PathBuf> {
+        let (blob, digest) = write_blob(&self.dir, content, None).await?;
+        fs::write(self.blob_file(), digest).await?;
+        Ok(blob)
+    }
+
+    /// Whether the cached content at `cache_file` was pulled from the manifest with `digest`,
+    /// according to the manifest digest recorded in `store`.
+    ///
+    /// Content cached without a manifest digest is re-hashed and compared to the manifest's
+    /// artifact layer instead, restoring the digest on a match so later fetches can skip this
+    async fn matches_manifest(
+        &self,
+        store: &dyn CacheStore,
+        cache_file: &Path,
+        manifest: &OciManifest,
+        digest: &str,
+        accepted_media_types: &[&str],
+    ) -> bool {
+        if digest.is_empty() {
+            return false;
+        }
+        match store.digest(&self.key).await {
+            Ok(Some(cached_digest)) => return cached_digest == digest,
+            Ok(None) => {}
+            Err(_) => return false,
+        }
+        let OciManifest::Image(manifest) = manifest else {
+            return false;
+        };
+        let Ok(layers) = select_layers(manifest, accepted_media_types) else {
+            return false;
+        };
+        let [layer] = layers.as_slice() else {
+            return false;
+        };
+        let Ok(content) = fs::read(cache_file).await else {
+            return false;
+        };
+        if sha256_digest(&content) != layer.digest {
+            return false;
+        }
+        if let Err(e) = store.put(&self.key, Bytes::from(content), digest).await {
+            warn!(key = self.key, ?e, "failed to restore OCI cache digest");
+        }
+        true
+    }
+
+    /// Records that the cached content for this reference was just used
+    async fn mark_accessed(&self) -> std::io::Result<()> {
+        let file = fs::File::open(self.blob_file()).await?.into_std().await;
+        file.set_times(std::fs::FileTimes::new().set_accessed(SystemTime::now()))
+    }
+
+    /// Imports the content of a legacy flat cache file for this reference into the blob store
+    async fn migrate(&self) -> std::io::Result<bool> {
+        let legacy = self.legacy_file();
+        match fs::metadata(&legacy).await {
+            Ok(md) if md.is_file() => {}
+            _ => return Ok(false),
+        }
+        let content = fs::read(&legacy).await?;
+        let (_, digest) = write_blob(&self.dir, &content, None).await?;
+        fs::write(self.blob_file(), digest).await?;
+        fs::remove_file(&legacy).await?;
+        Ok(true)
+    }
+
+    /// Stores the content of a pulled artifact in `store` along with the digest of its manifest,
+    /// and records the metadata of this reference, returning the path of the content.
+    /// `media_types` lists the media types of the layers the content was assembled from, one per
+    /// line
+    ///
+    /// The cached manifest is removed first and written last, so that an entry left incomplete,
+    /// e.g. by a cancelled fetch, is revalidated against the registry rather than trusted.
+    async fn store(
+        &self,
+        store: &dyn CacheStore,
+        reference: &Reference,
+        content: Bytes,
+        media_types: &str,
+        manifest_digest: &str,
+        manifest: &OciManifest,
+    ) -> anyhow::Result<PathBuf> {
+        if let Err(e) = fs::remove_file(self.manifest_file()).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(OciFetchError::Cache(e).into());
+            }
+        }
+        let size = content.len() as u64;
+        store
+            .put(&self.key, content.clone(), manifest_digest)
+            .await?;
+        let blob = match store.path(&self.key).await? {
+            Some(blob) => blob,
+            None => self
+                .copy_content(&content)
+                .await
+                .map_err(OciFetchError::Cache)?,
+        };
+        async {
+            fs::write(self.media_types_file(), media_types).await?;
+            self.write_reference(reference, Some(manifest_digest))
+                .await?;
+            self.write_metadata(reference, Some(manifest_digest), media_types, size)
+                .await?;
+            self.write_manifest(manifest).await
+        }
+        .await
+        .map_err(OciFetchError::Cache)?;
+        Ok(blob)
+    }
+
+    /// Stores content that was not pulled from a registry, e.g. copied from an air-gapped
+    /// medium, in the blob store and points this reference at it, returning the digest of the
+    /// content.
+    ///
+    /// Unless known from a reference pinned to it, the manifest digest is left unset, so that
+    /// revalidating the entry against the registry verifies the content against the manifest's
+    /// layer digest instead.
+    async fn import(
+        &self,
+        reference: &Reference,
+        content: &[u8],
+        media_type: &str,
+        manifest_digest: Option<&str>,
+    ) -> std::io::Result<String> {
+        for file in [self.digest_file(), self.manifest_file()] {
+            if let Err(e) = fs::remove_file(file).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e);
+                }
+            }
+        }
+        let (_, digest) = write_blob(&self.dir, content, None).await?;
+        fs::write(self.blob_file(), &digest).await?;
+        fs::write(self.media_types_file(), media_type).await?;
+        self.write_reference(reference, manifest_digest).await?;
+        self.write_metadata(reference, manifest_digest, media_type, content.len() as u64)
+            .await?;
+        if let Some(manifest_digest) = manifest_digest {
+            fs::write(self.digest_file(), manifest_digest).await?;
+        }
+        Ok(digest)
+    }
+
+    /// Caches the manifest the content for this reference was pulled from, or was last
+    /// revalidated against
+    async fn write_manifest(&self, manifest: &OciManifest) -> std::io::Result<()> {
+        let manifest = serde_json::to_vec(manifest)?;
+        fs::write(self.manifest_file(), manifest).await
+    }
+
+    /// Returns the cached manifest for this reference if it was last retrieved from the registry
+    /// within `ttl`
+    async fn fresh_manifest(&self, ttl: Duration) -> Option<OciManifest> {
+        let modified = fs::metadata(self.manifest_file())
+            .await
+            .ok()?
+            .modified()
+            .ok()?;
+        if SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default()
+            > ttl
+        {
+            return None;
+        }
+        self.manifest().await
+    }
+
+    /// Returns the cached manifest for this reference, if any
+    async fn manifest(&self) -> Option<OciManifest> {
+        serde_json::from_slice(&fs::read(self.manifest_file()).await.ok()?).ok()
+    }
+
+    /// Describes the cache hit of the content at `cache_file`, pulled from the manifest with
+    /// `digest`
+    async fn hit(
+        &self,
+        cache_file: PathBuf,
+        digest: Option<String>,
+        manifest: Option<&OciManifest>,
+    ) -> FetchedPath {
+        match self.mark_accessed().await {
+            Ok(()) => {}
+            // Content kept by a configured store on the local filesystem is not indexed here
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!(key = self.key, ?e, "failed to record OCI cache access"),
+        }
+        let (media_type, size) = match self.metadata().await {
+            Some(CacheMetadata {
+                media_type, size, ..
+            }) => (media_type, size),
+            None => {
+                let media_type = fs::read_to_string(self.media_types_file())
+                    .await
+                    .ok()
+                    .and_then(|media_types| media_types.lines().next().map(ToString::to_string))
+                    .unwrap_or_default();
+                let size = fs::metadata(&cache_file)
+                    .await
+                    .map(|md| md.len())
+                    .unwrap_or_default();
+                (media_type, size)
+            }
+        };
+        FetchedPath {
+            path: cache_file,
+            cache: CacheResult::Hit,
+            digest,
+            annotations: manifest.map(manifest_annotations).unwrap_or_default(),
+            media_type,
+            size,
+            downloaded: 0,
+        }
+    }
+}
+
+/// Imports all cache entries stored in the flat, reference-keyed layout used by previous
+/// versions into the content-addressable layout, returning the number of migrated entries.
+///
+/// Entries are also migrated lazily when first accessed, so calling this is only necessary to
+/// reclaim the space of duplicated entries up front.
+///
+/// # Errors
+///
+/// Returns an error if the cache directory cannot be read or an entry fails to migrate
+pub async fn migrate_oci_cache(dir: impl AsRef<Path>) -> anyhow::Result<usize> {
+    let dir = dir.as_ref();
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("failed to read OCI cache directory `{}`", dir.display()))?;
+    let mut migrated = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        // Legacy entries are plain files without an extension, since references are pruned of `.`
+        if !entry.file_type().await?.is_file() || path.extension().is_some() {
+            continue;
+        }
+        let Some(key) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let index = CacheIndex {
+            dir: dir.to_path_buf(),
+            key: key.to_string(),
+        };
+        if index
+            .migrate()
+            .await
+            .with_context(|| format!("failed to migrate `{}`", path.display()))?
+        {
+            migrated += 1;
+        }
+    }
+    Ok(migrated)
+}
+
+/// Storage backing the cache of artifacts fetched by an [`OciFetcher`], e.g. a store shared by
+/// several hosts, so that an artifact pulled by one of them is reused by the others. By default,
+/// artifacts are kept in the OCI cache directory by a [`FsCacheStore`].
+///
+/// Entries are keyed by the [`cache_key`] of the reference they were fetched by, and record the
+/// digest of the manifest they were pulled from, which is compared with the digest currently
+/// resolved by the registry to decide whether the entry is still up to date.
+// `async_trait` marks the boxed futures it returns as `#[must_use]`
+#[allow(clippy::double_must_use)]
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// Returns the content cached for `key`, if any
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Bytes>>;
+
+    /// Caches `content` for `key`, pulled from the manifest with `digest`
+    async fn put(&self, key: &str, content: Bytes, digest: &str) -> anyhow::Result<()>;
+
+    /// Returns the manifest digest the content cached for `key` was pulled from, if any
+    async fn digest(&self, key: &str) -> anyhow::Result<Option<String>>;
+
+    /// Returns the path of the content cached for `key` if the store keeps it on the local
+    /// filesystem. Otherwise, artifacts fetched to a path are read using [`CacheStore::get`] and
+    /// copied into the OCI cache directory
+    async fn path(&self, _key: &str) -> anyhow::Result<Option<PathBuf>> {
+        Ok(None)
+    }
+}
+
+/// [`CacheStore`] keeping artifacts in a directory on the local filesystem, using the same
+/// layout as the default OCI cache
+#[derive(Clone, Debug)]
+pub struct FsCacheStore {
+    dir: PathBuf,
+    download_tmp_dir: Option<PathBuf>,
+}
+
+impl FsCacheStore {
+    /// Returns a store keeping artifacts in `dir`, which may be shared with other hosts, e.g. over
+    /// NFS
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            download_tmp_dir: None,
+        }
+    }
+
+    /// Used to set the directory content is written to before it is moved into the store, see
+    /// [`OciFetcher::with_download_tmp_dir`]
+    #[must_use]
+    pub fn with_download_tmp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.download_tmp_dir = Some(dir.into());
+        self
+    }
+}
+
+#[async_trait]
+impl CacheStore for FsCacheStore {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Bytes>> {
+        let Some(blob) = self.path(key).await? else {
+            return Ok(None);
+        };
+        read_bytes(&blob).await.map(Some)
+    }
+
+    /// The manifest digest is removed first and written last, so that an entry left incomplete,
+    /// e.g. by a cancelled fetch, is never mistaken for the content of the previous manifest
+    async fn put(&self, key: &str, content: Bytes, digest: &str) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .map_err(OciFetchError::Cache)
+            .with_context(|| format!("failed to create `{}`", self.dir.display()))?;
+        let index = CacheIndex::new(&self.dir, key);
+        if let Err(e) = fs::remove_file(index.digest_file()).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(OciFetchError::Cache(e)).context("failed to remove cached digest");
+            }
+        }
+        let (_, blob_digest) = write_blob(&self.dir, &content, self.download_tmp_dir.as_deref())
+            .await
+            .map_err(OciFetchError::Cache)
+            .context("failed to write cached content")?;
+        fs::write(index.blob_file(), blob_digest)
+            .await
+            .map_err(OciFetchError::Cache)
+            .context("failed to write cache entry")?;
+        fs::write(index.digest_file(), digest)
+            .await
+            .map_err(OciFetchError::Cache)
+            .context("failed to write cached digest")
+    }
+
+    async fn digest(&self, key: &str) -> anyhow::Result<Option<String>> {
+        match fs::read_to_string(CacheIndex::new(&self.dir, key).digest_file()).await {
+            Ok(digest) => Ok(Some(digest)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).context("failed to read cached digest"),
+        }
+    }
+
+    async fn path(&self, key: &str) -> anyhow::Result<Option<PathBuf>> {
+        Ok(CacheIndex::new(&self.dir, key).blob().await)
+    }
+}
+
+fn prune_filepath(img: &str) -> String {
+    let mut img = img.replace(':', "_");
+    img = img.replace('/', "_");
+    img = img.replace('.', "_");
+    img
+}
+
+/// Normalizes `oci_ref` the same way fetches do: the reference is lowercased, since the OCI
+/// specification does not allow capital letters in repository names, and parsed according to the
+/// OCI reference grammar, which fills in the default registry and tag. Two references refer to
+/// the same artifact if the [`Reference::whole`] of their normalized forms are equal
+///
+/// # Errors
+///
+/// Returns [`OciFetchError::InvalidReference`] if `oci_ref` is not a valid reference
+pub fn normalize_reference(oci_ref: &str) -> anyhow::Result<Reference> {
+    Ok(Reference::from_str(&oci_ref.to_lowercase()).map_err(OciFetchError::from)?)
+}
+
+/// Whether the cached copy of `img` may be served after revalidating it against the registry
+/// failed with `err`. Only transient failures qualify, as e.g. a missing or inaccessible artifact
+/// must not keep being served, and `latest` tags never do, as they are expected to move
+fn serves_stale(img: &Reference, err: &anyhow::Error) -> bool {
+    img.tag() != Some("latest") && OciFetchError::find(err).is_some_and(OciFetchError::is_retryable)
+}
+
+/// Prepends `registry` to `oci_ref` unless the reference already names a registry. As in the
+/// OCI reference grammar, the first component of a reference's path names a registry if it
+/// contains a `.` or `:`, or is `localhost`
+#[must_use]
+pub fn qualify_reference(oci_ref: &str, registry: &str) -> String {
+    let names_registry = oci_ref.split_once('/').is_some_and(|(first, _)| {
+        first.contains(['.', ':']) || first.eq_ignore_ascii_case("localhost")
+    });
+    if names_registry {
+        oci_ref.to_string()
+    } else {
+        format!("{}/{oci_ref}", registry.trim_end_matches('/'))
+    }
+}
+
+/// Returns the key under which artifacts fetched by `oci_ref` are cached in the OCI cache
+/// directory. Note that the key is derived from the reference as given, so references that only
+/// normalize to the same artifact, such as `wasmcloud/http:0.2.0` and
+/// `docker.io/wasmcloud/http:0.2.0`, are cached separately
+#[must_use]
+pub fn cache_key(oci_ref: &str) -> String {
+    prune_filepath(&oci_ref.to_lowercase())
+}
+
+/// A type to indicate whether there was a cache hit or miss when loading artifacts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheResult {
+    Hit,
+    Miss,
+}
+
+/// A component fetched from OCI along with metadata from its manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchedComponent {
+    /// The component bytes
+    pub bytes: Vec<u8>,
+    /// The resolved manifest digest, if the registry returned one
+    pub digest: Option<String>,
+    /// Annotations set on the image manifest
+    pub annotations: HashMap<String, String>,
+}
+
+/// A provider fetched from OCI along with the digest of its manifest
+#[derive(Debug, Clone)]
+pub struct FetchedProvider {
+    /// Path of the extracted provider binary
+    pub path: PathBuf,
+    /// The claims embedded in the provider archive, if any
+    pub claims: Option<jwt::Token<jwt::CapabilityProvider>>,
+    /// The resolved manifest digest, if the registry returned one
+    pub digest: Option<String>,
+}
+
+/// Details of an OCI artifact fetched to a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchResult {
+    /// Path of the fetched artifact
+    pub path: PathBuf,
+    /// The resolved manifest digest, if the registry returned one
+    pub digest: Option<String>,
+    /// Media type of the layer holding the artifact. For artifacts assembled from multiple
+    /// layers, this is the media type of the first one
+    pub media_type: String,
+    /// Whether the artifact was served from the cache
+    pub from_cache: bool,
+    /// Size of the artifact in bytes
+    pub size: u64,
+}
+
+/// Details of an OCI artifact as declared by its manifest, see [`OciFetcher::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The normalized reference that was validated
+    pub reference: String,
+    /// The resolved manifest digest
+    pub digest: String,
+    /// Media type of the layer holding the artifact. For artifacts assembled from multiple
+    /// layers, this is the media type of the first one
+    pub media_type: String,
+    /// Size of the artifact in bytes, as declared by the manifest
+    pub size: u64,
+}
+
+/// Result of fetching an OCI artifact to a path, including the manifest details that were
+/// retrieved along the way
+struct FetchedPath {
+    path: PathBuf,
+    cache: CacheResult,
+    digest: Option<String>,
+    annotations: HashMap<String, String>,
+    /// Media type of the (first) artifact layer
+    media_type: String,
+    /// Size of the artifact in bytes
+    size: u64,
+    /// Number of artifact bytes downloaded from the registry
+    downloaded: u64,
+}
+
+/// Ensures that the OCI 1.1 `artifactType` of `manifest`, if it declares one, identifies one of
+/// the given kinds of artifact. Manifests without an artifact type are identified by their layer
+/// media types alone, see [`select_layers`]
+fn check_artifact_type(
+    manifest: &OciImageManifest,
+    kinds: &[ArtifactKind],
+) -> Result<(), OciFetchError> {
+    let Some(artifact_type) = &manifest.artifact_type else {
+        return Ok(());
+    };
+    let expected: Vec<_> = kinds
+        .iter()
+        .flat_map(ArtifactKind::artifact_types)
+        .collect();
+    if expected.contains(&&artifact_type.as_str()) {
+        return Ok(());
+    }
+    Err(OciFetchError::UnsupportedArtifactType {
+        artifact_type: artifact_type.clone(),
+        expected: expected.into_iter().map(ToString::to_string).collect(),
+    })
+}
+
+/// Selects the layers of `manifest` holding the artifact content.
+///
+/// Wasm artifacts, identified by their config media type, carry exactly one wasm layer, which is
+/// selected on its own, while any sidecar layers (e.g. WIT or configuration) are ignored. Other
+/// image manifests, such as those of conventional Docker or OCI container images, may carry any
+/// number of layers, of which only those with an accepted media type are selected.
+fn select_layers<'a>(
+    manifest: &'a OciImageManifest,
+    accepted_media_types: &[&str],
+) -> Result<Vec<&'a OciDescriptor>, OciFetchError> {
+    if manifest.config.media_type == WASM_MANIFEST_CONFIG_MEDIA_TYPE {
+        let wasm_layers: Vec<_> = manifest
+            .layers
+            .iter()
+            .filter(|layer| layer.media_type == WASM_LAYER_MEDIA_TYPE)
+            .collect();
+        match wasm_layers.as_slice() {
+            [_, _, ..] => {
+                return Err(OciFetchError::InvalidArtifact {
+                    layers: wasm_layers.len(),
+                })
+            }
+            [layer] if accepted_media_types.contains(&WASM_LAYER_MEDIA_TYPE) => {
+                return Ok(vec![layer])
+            }
+            _ => {}
+        }
+    }
+    let layers: Vec<_> = manifest
+        .layers
+        .iter()
+        .filter(|layer| accepted_media_types.contains(&layer.media_type.as_str()))
+        .collect();
+    if layers.is_empty() {
+        return Err(OciFetchError::NoMatchingLayer {
+            media_types: manifest
+                .layers
+                .iter()
+                .map(|layer| layer.media_type.clone())
+                .collect(),
+        });
+    }
+    Ok(layers)
+}
+
+fn manifest_annotations(manifest: &OciManifest) -> HashMap<String, String> {
+    let annotations = match manifest {
+        OciManifest::Image(manifest) => manifest.annotations.as_ref(),
+        OciManifest::ImageIndex(index) => index.annotations.as_ref(),
+    };
+    annotations
+        .map(|annotations| annotations.clone().into_iter().collect())
+        .unwrap_or_default()
+}
+
+impl OciFetcher {
+    /// Fetch an OCI artifact to a path and return that path. Returns the path and whether or not
+    /// there was a cache hit/miss
+    pub async fn fetch_path(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<(PathBuf, CacheResult)> {
+        let FetchResult {
+            path, from_cache, ..
+        } = self
+            .fetch_path_detailed(output_dir, img, accepted_media_types, cache)
+            .await?;
+        let cache = if from_cache {
+            CacheResult::Hit
+        } else {
+            CacheResult::Miss
+        };
+        Ok((path, cache))
+    }
+
+    /// Fetch an OCI artifact to a path like [`OciFetcher::fetch_path`], aborting once `cancel` is
+    /// cancelled, e.g. because the workload the artifact was fetched for is no longer needed.
+    ///
+    /// Cancelling removes any partially written files and never leaves a corrupt cache entry
+    /// behind. Dropping the future returned by any fetch method is equally safe.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching fails, or [`OciFetchError::Cancelled`] if it was cancelled
+    pub async fn fetch_path_cancellable(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<(PathBuf, CacheResult)> {
+        tokio::select! {
+            biased;
+            () = cancel.cancelled() => bail!(OciFetchError::Cancelled),
+            res = self.fetch_path(output_dir, img, accepted_media_types, cache) => res,
+        }
+    }
+
+    /// Fetch an OCI artifact to a path, returning the path along with the resolved digest, media
+    /// type and size of the artifact and whether it was served from the cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching fails
+    pub async fn fetch_path_detailed(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<FetchResult> {
+        let FetchedPath {
+            path,
+            cache,
+            digest,
+            media_type,
+            size,
+            ..
+        } = self
+            .fetch_path_with_manifest(output_dir, img, accepted_media_types, cache)
+            .await?;
+        Ok(FetchResult {
+            path,
+            digest,
+            media_type,
+            from_cache: cache == CacheResult::Hit,
+            size,
+        })
+    }
+
+    async fn fetch_path_with_manifest(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<FetchedPath> {
+        let img = img.as_ref();
+        let start = Instant::now();
+        self.emit(
+            || self.event_reference(img),
+            || FetchEventKind::FetchStarted,
+        );
+        let res = self
+            .fetch_path_from_registry(output_dir, img, accepted_media_types, cache)
+            .await;
+        let (outcome, size, downloaded) = match &res {
+            Ok(FetchedPath {
+                cache: CacheResult::Hit,
+                size,
+                downloaded,
+                ..
+            }) => (FetchOutcome::Hit, *size, *downloaded),
+            Ok(FetchedPath {
+                cache: CacheResult::Miss,
+                size,
+                downloaded,
+                ..
+            }) => (FetchOutcome::Miss, *size, *downloaded),
+            Err(_) => (FetchOutcome::Error, 0, 0),
+        };
+        self.record_fetch(img, outcome, downloaded, start);
+        self.emit_outcome(img, &res, outcome, size, downloaded, start);
+        res
+    }
+
+    /// Sends a [`FetchEvent`] about the fetch of `reference` to subscribers, if there are any. The
+    /// event is only built if it is sent
+    fn emit(&self, reference: impl FnOnce() -> String, kind: impl FnOnce() -> FetchEventKind) {
+        let Some(events) = self.events.get() else {
+            return;
+        };
+        if events.receiver_count() == 0 {
+            return;
+        }
+        // Sending only fails if all subscribers are gone, in which case nobody is interested
+        let _ = events.send(FetchEvent {
+            reference: reference(),
+            kind: kind(),
+        });
+    }
+
+    /// Returns the normalized reference reported in [`FetchEvent`]s about fetches of `oci_ref`
+    fn event_reference(&self, oci_ref: &str) -> String {
+        let img = self.normalize(oci_ref);
+        normalize_reference(&img).map_or(img, |img| img.whole())
+    }
+
+    /// Sends the [`FetchEvent`]s ending the fetch of `img` started at `start` to subscribers
+    fn emit_outcome<T>(
+        &self,
+        img: &str,
+        res: &anyhow::Result<T>,
+        outcome: FetchOutcome,
+        bytes: u64,
+        downloaded: u64,
+        start: Instant,
+    ) {
+        match res {
+            Ok(_) => {
+                if outcome == FetchOutcome::Hit {
+                    self.emit(|| self.event_reference(img), || FetchEventKind::CacheHit);
+                }
+                self.emit(
+                    || self.event_reference(img),
+                    || FetchEventKind::FetchCompleted {
+                        bytes,
+                        downloaded,
+                        duration: start.elapsed(),
+                    },
+                );
+            }
+            Err(err) => self.emit(
+                || self.event_reference(img),
+                || FetchEventKind::FetchFailed {
+                    error: format!("{err:#}"),
+                },
+            ),
+        }
+    }
+
+    /// Reports a fetch of `img` started at `start` to the configured [`FetchMetrics`], if any
+    fn record_fetch(&self, img: &str, outcome: FetchOutcome, downloaded: u64, start: Instant) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        let registry = normalize_reference(img)
+            .map(|img| img.registry().to_string())
+            .unwrap_or_default();
+        metrics
+            .0
+            .record_fetch(&registry, outcome, downloaded, start.elapsed());
+    }
+
+    async fn fetch_path_from_registry(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: impl AsRef<str>,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<FetchedPath> {
+        let requested = img.as_ref();
+        let img = self.normalize(requested);
+        self.fetch_normalized_path(output_dir, &img, accepted_media_types, cache)
+            .await
+            .with_context(|| {
+                // Report the reference as given by the user, as well as what was actually fetched
+                if requested == img {
+                    format!("failed to fetch `{requested}`")
+                } else {
+                    format!("failed to fetch `{requested}`, normalized to `{img}`")
+                }
+            })
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            img,
+            digest = field::Empty,
+            bytes = field::Empty,
+            cache_hit = field::Empty,
+        )
+    )]
+    async fn fetch_normalized_path(
+        &self,
+        output_dir: impl AsRef<Path>,
+        img: &str,
+        accepted_media_types: Vec<&str>,
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<FetchedPath> {
+        if !self.allow_latest && img.ends_with(":latest") {
+            bail!(OciFetchError::LatestDisallowed)
+        }
+        let index = CacheIndex::new(output_dir, img);
+        let _in_flight = InFlightGuard::new(index.legacy_file());
+        let store = self.store_for(&index.dir);
+
+        let img = Reference::from_str(img).map_err(OciFetchError::from)?;
+
+        let (mut c, certs) = self.client(&img)?;
+
+        // Always pulling replaces whatever is cached
+        let cache = match (self.pull_policy, cache) {
+            (PullPolicy::Always, OciArtifactCacheUpdate::Update) => {
+                OciArtifactCacheUpdate::ForceRefresh
+            }
+            (_, cache) => cache,
+        };
+
+        // In case of a cache miss where the file does not exist, pull a fresh OCI Image
+        let span = Span::current();
+        let cached = if cache == OciArtifactCacheUpdate::ForceRefresh {
+            // Make sure a stale manifest is not considered fresh should the refresh fail
+            if let Err(e) = fs::remove_file(index.manifest_file()).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!(
+                        key = index.key,
+                        ?e,
+                        "failed to invalidate cached OCI manifest"
+                    );
+                }
+            }
+            None
+        } else if self.pull_policy == PullPolicy::Always {
+            None
+        } else {
+            match index
+                .cached_file(store.as_ref())
+                .instrument(debug_span!("cache_lookup"))
+                .await
+            {
+                Ok(cached) => cached,
+                Err(e) => {
+                    warn!(key = index.key, ?e, "failed to look up cached OCI artifact");
+                    None
+                }
+            }
+        };
+        // Only reuse cached content assembled from layers of media types the caller accepts, as
+        // the same reference may be fetched as different kinds of artifacts. Content other hosts
+        // put in a configured store has no local record of its media types, and is trusted
+        let cached = match cached {
+            Some(cache_file)
+                if index.has_media_types(&accepted_media_types).await
+                    || (self.cache_store.is_some()
+                        && fs::metadata(index.media_types_file()).await.is_err()) =>
+            {
+                Some(cache_file)
+            }
+            _ => None,
+        };
+        if let (Some(cache_file), PullPolicy::IfNotPresent | PullPolicy::Never) =
+            (&cached, self.pull_policy)
+        {
+            // Reuse whatever is cached without contacting the registry
+            return self
+                .cached_hit(store.as_ref(), &index, cache_file.clone(), &span)
+                .await;
+        }
+        if self.pull_policy == PullPolicy::Never {
+            bail!(OciFetchError::NotCached)
+        }
+        // Manifest resolved while revalidating outdated content, which is pulled from
+        let mut outdated = None;
+        if let Some(cache_file) = cached {
+            // Skip the registry round-trip while the manifest cached along with the artifact is fresh
+            let fresh = match self.manifest_ttl {
+                Some(ttl) => index.fresh_manifest(ttl).await,
+                None => None,
+            };
+            let revalidated = fresh.is_none();
+            let (manifest, oci_digest) = match fresh {
+                Some(manifest) => (
+                    manifest,
+                    store
+                        .digest(&index.key)
+                        .await
+                        .ok()
+                        .flatten()
+                        .unwrap_or_default(),
+                ),
+                None => match self
+                    .retry_manifest(&mut c, &img, |mut c| {
+                        let (store, index, certs, img) = (store.as_ref(), &index, &certs, &img);
+                        async move {
+                            let res = self
+                                .revalidate_manifest(store, index, &mut c, certs, img)
+                                .await;
+                            (c, res)
+                        }
+                    })
+                    .instrument(debug_span!("resolve_manifest"))
+                    .await
+                {
+                    Ok(resolved) => resolved,
+                    Err(err) if serves_stale(&img, &err) => {
+                        warn!(
+                            %img,
+                            ?err,
+                            "failed to revalidate cached OCI artifact, using cached copy"
+                        );
+                        return self
+                            .cached_hit(store.as_ref(), &index, cache_file, &span)
+                            .await;
+                    }
+                    Err(err) => return Err(err),
+                },
+            };
+            span.record("digest", &oci_digest);
+            self.emit(
+                || img.whole(),
+                || FetchEventKind::ManifestResolved {
+                    digest: oci_digest.clone(),
+                },
+            );
+            self.ensure_digest_allowed(&oci_digest)?;
+            if index
+                .matches_manifest(
+                    store.as_ref(),
+                    &cache_file,
+                    &manifest,
+                    &oci_digest,
+                    &accepted_media_types,
+                )
+                .await
+            {
+                if revalidated {
+                    if let Err(e) = index.write_manifest(&manifest).await {
+                        warn!(key = index.key, ?e, "failed to cache OCI manifest");
+                    }
+                }
+                span.record("cache_hit", true);
+                return Ok(index
+                    .hit(cache_file, Some(oci_digest), Some(&manifest))
+                    .await);
+            }
+            // A manifest just resolved from the registry is current, so it is not pulled again
+            if let (true, OciManifest::Image(manifest)) = (revalidated, manifest) {
+                outdated = Some((manifest, oci_digest));
+            }
+        }
+
+        let (manifest, digest) = match outdated {
+            Some(resolved) => resolved,
+            None => {
+                let (manifest, digest) = self
+                    .pull_platform_manifest(&mut c, &certs, &img)
+                    .instrument(debug_span!("resolve_manifest"))
+                    .await?;
+                self.emit(
+                    || img.whole(),
+                    || FetchEventKind::ManifestResolved {
+                        digest: digest.clone(),
+                    },
+                );
+                self.ensure_digest_allowed(&digest)?;
+                (manifest, digest)
+            }
+        };
+        let (content, media_types) = self
+            .pull_layers(&c, &certs, &img, &manifest, &accepted_media_types)
+            .await?;
+        let media_type = media_types.lines().next().unwrap_or_default().to_string();
+        let downloaded = content.len() as u64;
+        span.record("cache_hit", false);
+        span.record("bytes", downloaded);
+        span.record("digest", &digest);
+        let annotations = manifest
+            .annotations
+            .clone()
+            .map(|annotations| annotations.into_iter().collect())
+            .unwrap_or_default();
+        // Update the OCI artifact cache if specified
+        let cache_file =
+            if let OciArtifactCacheUpdate::Update | OciArtifactCacheUpdate::ForceRefresh = cache {
+                index
+                    .store(
+                        store.as_ref(),
+                        &img,
+                        Bytes::from(content),
+                        &media_types,
+                        &digest,
+                        &OciManifest::Image(manifest),
+                    )
+                    .await
+                    .context("failed to cache OCI bytes")
+                    .inspect(|blob| {
+                        // Usage is accounted for off the path of the fetch, which holds the entry
+                        // in flight, so that it is never pruned to make room for others
+                        self.cache_written(&index, blob, downloaded);
+                    })?
+            } else {
+                blob_path(&index.dir, &sha256_digest(&content))
+            };
+        let digest = Some(digest);
+
+        Ok(FetchedPath {
+            path: cache_file,
+            cache: CacheResult::Miss,
+            digest,
+            annotations,
+            media_type,
+            size: downloaded,
+            downloaded,
+        })
+    }
+
+    /// Describes a cache hit of the content at `cache_file` served without revalidating it
+    /// against the registry
+    async fn cached_hit(
+        &self,
+        store: &dyn CacheStore,
+        index: &CacheIndex,
+        cache_file: PathBuf,
+        span: &Span,
+    ) -> anyhow::Result<FetchedPath> {
+        let digest = store.digest(&index.key).await.ok().flatten();
+        if let Some(digest) = &digest {
+            span.record("digest", digest);
+        }
+        self.ensure_digest_allowed(digest.as_deref().unwrap_or_default())?;
+        span.record("cache_hit", true);
+        let manifest = index.manifest().await;
+        Ok(index.hit(cache_file, digest, manifest.as_ref()).await)
+    }
+
+    /// Runs `request` resolving the manifest of `img` to revalidate a cached artifact against,
+    /// retrying it while it fails transiently. Rate-limited requests are not retried here, as
+    /// they already were.
+    ///
+    /// `request` is passed the client and hands it back, since it may be replaced, e.g. when
+    /// falling back to anonymous access.
+    async fn retry_manifest<T, F, Fut>(
+        &self,
+        c: &mut oci_client::Client,
+        img: &Reference,
+        mut request: F,
+    ) -> anyhow::Result<T>
+    where
+        F: FnMut(oci_client::Client) -> Fut,
+        Fut: Future<Output = (oci_client::Client, anyhow::Result<T>)>,
+    {
+        let mut delay = self.manifest_retry_delay;
+        let mut attempt = 0;
+        loop {
+            let res;
+            (*c, res) = request(c.clone()).await;
+            match res {
+                Err(err)
+                    if attempt < self.manifest_retries
+                        && OciFetchError::find(&err).is_some_and(|e| {
+                            e.is_retryable() && !matches!(e, OciFetchError::RateLimited { .. })
+                        }) =>
+                {
+                    attempt += 1;
+                    debug!(%img, ?err, attempt, ?delay, "failed to resolve OCI manifest, retrying");
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Resolves the manifest of `img` to revalidate the content cached for it in `store` against.
+    ///
+    /// If a manifest is cached along with the content, only its digest is requested using `HEAD`,
+    /// which transfers no manifest, and the cached manifest is reused while the digest is
+    /// unchanged. Otherwise, e.g. for content cached by previous versions or outdated content, the
+    /// manifest is pulled.
+    ///
+    /// References to image indexes never resolve to the digest of the cached platform manifest, so
+    /// the manifest is pulled whenever the digest differs to tell them apart from updated content.
+    async fn revalidate_manifest(
+        &self,
+        store: &dyn CacheStore,
+        index: &CacheIndex,
+        c: &mut oci_client::Client,
+        certs: &[oci_client::client::Certificate],
+        img: &Reference,
+    ) -> anyhow::Result<(OciManifest, String)> {
+        if let (Ok(Some(cached_digest)), Some(manifest)) =
+            (store.digest(&index.key).await, index.manifest().await)
+        {
+            let digest = self
+                .authenticated(c, img, certs, |c, auth| async move {
+                    c.fetch_manifest_digest(img, &auth).await
+                })
+                .await
+                .context("failed to fetch OCI manifest digest")?;
+            if digest == cached_digest {
+                return Ok((manifest, digest));
+            }
+        }
+        let (manifest, digest) = self.pull_platform_manifest(c, certs, img).await?;
+        Ok((OciManifest::Image(manifest), digest))
+    }
+
+    /// Pulls the manifest of `img`. If `img` refers to an image index, the manifest for the
+    /// configured [`Platform`] is selected from it and pulled instead
+    async fn pull_platform_manifest(
+        &self,
+        c: &mut oci_client::Client,
+        certs: &[oci_client::client::Certificate],
+        img: &Reference,
+    ) -> anyhow::Result<(OciImageManifest, String)> {
+        let (manifest, digest) = self
+            .authenticated(c, img, certs, |c, auth| async move {
+                c.pull_manifest(img, &auth).await
+            })
+            .await
+            .context("failed to fetch OCI manifest")?;
+        let index = match manifest {
+            OciManifest::Image(manifest) => return Ok((manifest, digest)),
+            OciManifest::ImageIndex(index) => index,
+        };
+        let Some(entry) = index.manifests.iter().find(|entry| {
+            entry
+                .platform
+                .as_ref()
+                .is_some_and(|platform| self.platform.matches(platform))
+        }) else {
+            bail!(OciFetchError::NoMatchingPlatform {
+                platform: self.platform.to_string(),
+                available: index
+                    .manifests
+                    .iter()
+                    .filter_map(|entry| entry.platform.as_ref())
+                    .map(|platform| format!("{}/{}", platform.os, platform.architecture))
+                    .collect(),
+            })
+        };
+        debug!(
+            %img,
+            platform = %self.platform,
+            digest = entry.digest,
+            "selected manifest from OCI image index"
+        );
+        let platform_img = img.clone_with_digest(entry.digest.clone());
+        self.authenticated(c, &platform_img, certs, |c, auth| {
+            let img = &platform_img;
+            async move { c.pull_image_manifest(img, &auth).await }
+        })
+        .await
+        .context("failed to fetch OCI manifest for platform")
+    }
+
+    /// Pulls the layers of `manifest` holding the artifact content. Returns the content along with
+    /// the media types of the layers it was assembled from, one per line
+    async fn pull_layers(
+        &self,
+        c: &oci_client::Client,
+        certs: &[oci_client::client::Certificate],
+        img: &Reference,
+        manifest: &OciImageManifest,
+        accepted_media_types: &[&str],
+    ) -> anyhow::Result<(Vec<u8>, String)> {
+        // As a client, we should reject invalid OCI artifacts
+        let kinds = self.accepted_kinds(accepted_media_types);
+        if !kinds.is_empty() {
+            check_artifact_type(manifest, &kinds)?;
+        }
+        let layers = select_layers(manifest, accepted_media_types)?;
+        let total = layers
+            .iter()
+            .map(|layer| u64::try_from(layer.size).unwrap_or_default())
+            .sum();
+        if let Some(max) = self.max_artifact_bytes {
+            // Check the sizes declared by the manifest up front, so that nothing is downloaded
+            if total > max {
+                bail!(OciFetchError::TooLarge { size: total, max })
+            }
+        }
+        let media_types = layers
+            .iter()
+            .map(|layer| layer.media_type.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut content = Vec::new();
+        for layer in &layers {
+            // Registries may redirect blob requests to separate storage. Redirects are followed by
+            // the HTTP client, which drops the `Authorization` header when the target is a
+            // different host, so registry credentials are never sent to the storage backend
+            let data = self
+                .retry_rate_limited(img, certs, || async {
+                    let mut data = Vec::with_capacity(layer.size.try_into().unwrap_or_default());
+                    c.pull_blob(img, layer, &mut data).await?;
+                    Ok(data)
+                })
+                .instrument(debug_span!("download", layer = layer.digest))
+                .await
+                .with_context(|| format!("failed to fetch OCI layer `{}`", layer.digest))?;
+            content.extend(data);
+            self.emit(
+                || img.whole(),
+                || FetchEventKind::LayerProgress {
+                    digest: layer.digest.clone(),
+                    downloaded: content.len() as u64,
+                    total,
+                },
+            );
+            if let Some(max) = self.max_artifact_bytes {
+                let size = content.len() as u64;
+                if size > max {
+                    bail!(OciFetchError::TooLarge { size, max })
+                }
+            }
+        }
+        Ok((content, media_types))
+    }
+
+    /// Returns the credentials to use for the registry of `img`, which are those set for the
+    /// registry with [`OciFetcher::with_registry_auths`], or the fetcher's default credentials
+    fn auth_for(&self, img: &Reference) -> &oci_client::secrets::RegistryAuth {
+        self.registry_auths
+            .get(img.registry())
+            .or_else(|| self.registry_auths.get(img.resolve_registry()))
+            .unwrap_or(&self.auth)
+    }
+
+    /// Returns the credentials to use for the registry of `img`, reading the token file set with
+    /// [`OciFetcher::with_token_file`] if it applies.
+    ///
+    /// Clients remember the credentials first used for a registry, so once the token is rotated
+    /// the cached clients are dropped and `c` is replaced by a client using the new token
+    async fn current_auth(
+        &self,
+        c: &mut oci_client::Client,
+        img: &Reference,
+    ) -> anyhow::Result<oci_client::secrets::RegistryAuth> {
+        let token_file = match &self.token_file {
+            Some(token_file)
+                if !self.registry_auths.contains_key(img.registry())
+                    && !self.registry_auths.contains_key(img.resolve_registry()) =>
+            {
+                token_file
+            }
+            _ => return Ok(self.auth_for(img).clone()),
+        };
+        let (token, rotated) = token_file.read().await?;
+        if rotated {
+            debug!(path = %token_file.path.display(), "registry token was rotated");
+            self.clients
+                .0
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+            (*c, _) = self.client(img)?;
+        }
+        Ok(oci_client::secrets::RegistryAuth::Basic(
+            TOKEN_FILE_USERNAME.to_string(),
+            token,
+        ))
+    }
+
+    /// Returns the kinds of artifacts being fetched, i.e. those whose layer media types are all
+    /// in `accepted_media_types`
+    fn accepted_kinds(&self, accepted_media_types: &[&str]) -> Vec<ArtifactKind> {
+        [ArtifactKind::Component, ArtifactKind::Provider]
+            .into_iter()
+            .filter(|kind| {
+                self.media_types(*kind)
+                    .iter()
+                    .all(|media_type| accepted_media_types.contains(media_type))
+            })
+            .collect()
+    }
+
+    /// Returns the directory in which fetched artifacts are cached. A directory set with
+    /// [`OciFetcher::with_cache_dir`] takes precedence over the `WASMCLOUD_OCI_CACHE_DIR`
+    /// environment variable, which takes precedence over the default, see [`oci_cache_dir`]
+    async fn cache_dir(&self) -> anyhow::Result<PathBuf> {
+        create_cache_dir(self.cache_dir.as_deref()).await
+    }
+
+    /// Ensures that the artifact with manifest `digest` may be fetched, if only specific digests
+    /// are allowed
+    fn ensure_digest_allowed(&self, digest: &str) -> Result<(), OciFetchError> {
+        match &self.allowed_digests {
+            Some(allowed) if !allowed.contains(digest) => Err(OciFetchError::DigestNotAllowed {
+                digest: digest.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Verifies the claims of a provider archive, if providers must be signed by specific issuers
+    fn verify_provider_claims(
+        &self,
+        claims: Option<&jwt::Token<jwt::CapabilityProvider>>,
+    ) -> anyhow::Result<()> {
+        let Some(issuers) = &self.required_provider_issuers else {
+            return Ok(());
+        };
+        let Some(claims) = claims else {
+            bail!(OciFetchError::Unsigned)
+        };
+        let v = jwt::validate_token::<jwt::CapabilityProvider>(&claims.jwt)
+            .context("failed to validate provider token")?;
+        ensure!(!v.expired, "token expired at `{}`", v.expires_human);
+        ensure!(
+            !v.cannot_use_yet,
+            "token cannot be used before `{}`",
+            v.not_before_human
+        );
+        ensure!(v.signature_valid, "signature is not valid");
+        ensure!(
+            issuers.contains(&claims.claims.issuer),
+            OciFetchError::UntrustedIssuer {
+                issuer: claims.claims.issuer.clone(),
+            }
+        );
+        Ok(())
+    }
+
+    /// Returns the [`CacheStore`] holding the content of artifacts cached in `dir`, which is the
+    /// store set with [`OciFetcher::with_cache_store`], or a [`FsCacheStore`] keeping it in `dir`
+    fn store_for(&self, dir: &Path) -> Arc<dyn CacheStore> {
+        if let Some(store) = &self.cache_store {
+            return Arc::clone(&store.0);
+        }
+        let store = FsCacheStore::new(dir);
+        match &self.download_tmp_dir {
+            Some(tmp_dir) => Arc::new(store.with_download_tmp_dir(tmp_dir)),
+            None => Arc::new(store),
+        }
+    }
+
+    /// Returns the registry client used to fetch `img`, along with the root certificates it
+    /// trusts. Clients are built on first use and reused by later fetches
+    fn client(&self, img: &Reference) -> anyhow::Result<RegistryClient> {
+        let mut insecure = self.insecure_registries_for(img);
+        insecure.sort();
+        let mut clients = self
+            .clients
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((client, certs)) = clients.get(&insecure) {
+            return Ok((client.clone(), Arc::clone(certs)));
+        }
+        let (client, certs) = self.new_client(insecure.clone())?;
+        clients.insert(insecure, (client.clone(), Arc::clone(&certs)));
+        Ok((client, certs))
+    }
+
+    /// Builds a registry client that may access `insecure` registries over plain HTTP
+    fn new_client(&self, insecure: Vec<String>) -> anyhow::Result<RegistryClient> {
+        let mut certs = tls::native_roots_oci().to_vec();
+        if !self.additional_ca_paths.is_empty() {
+            certs.extend(
+                tls::load_certs_from_paths(&self.additional_ca_paths)
+                    .context("failed to load CA certs from provided paths")?
+                    .iter()
+                    .map(|cert| oci_client::client::Certificate {
+                        encoding: oci_client::client::CertificateEncoding::Der,
+                        data: cert.to_vec(),
+                    }),
+            );
+        }
+        for pem in &self.additional_ca_pems {
+            certs.extend(
+                tls::read_certs_from_pem(pem)
+                    .context("failed to parse provided CA certs")?
+                    .iter()
+                    .map(|cert| oci_client::client::Certificate {
+                        encoding: oci_client::client::CertificateEncoding::Der,
+                        data: cert.to_vec(),
+                    }),
+            );
+        }
+        let protocol = if insecure.is_empty() {
+            ClientProtocol::Https
+        } else {
+            ClientProtocol::HttpsExcept(insecure)
+        };
+        let client = oci_client::Client::new(oci_client::client::ClientConfig {
+            protocol,
+            extra_root_certificates: certs.clone(),
+            user_agent: self.user_agent,
+            connect_timeout: Some(self.connect_timeout),
+            read_timeout: Some(self.read_timeout),
+            ..Default::default()
+        });
+        Ok((client, Arc::from(certs)))
+    }
+
+    /// Registries that may be accessed over plain HTTP when fetching `img`. These are the
+    /// configured insecure registries, plus the registry of `img` itself if insecure access is
+    /// allowed globally
+    fn insecure_registries_for(&self, img: &Reference) -> Vec<String> {
+        let mut registries: Vec<String> = self.insecure_registries.iter().cloned().collect();
+        if self.allow_insecure && !self.insecure_registries.contains(img.registry()) {
+            registries.push(img.registry().to_string());
+        }
+        registries
+    }
+
+    /// Runs a registry request, retrying it while the registry responds with HTTP 429. Each retry
+    /// waits for as long as the registry asks for in its `Retry-After` header, capped to the
+    /// configured maximum, falling back to a doubling delay if the header is absent.
+    async fn retry_rate_limited<T, F, Fut>(
+        &self,
+        img: &Reference,
+        certs: &[oci_client::client::Certificate],
+        mut request: F,
+    ) -> Result<T, OciFetchError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, OciDistributionError>>,
+    {
+        let mut fallback = RATE_LIMIT_FALLBACK_DELAY;
+        let mut attempt = 0;
+        loop {
+            let err = match request().await {
+                Ok(v) => return Ok(v),
+                Err(err) if is_rate_limited(&err) => err,
+                Err(err) => return Err(err.into()),
+            };
+            let retry_after = self.probe_retry_after(img, certs).await;
+            if attempt >= self.rate_limit_retries {
+                return Err(OciFetchError::RateLimited { retry_after });
+            }
+            attempt += 1;
+            let delay = retry_after.unwrap_or(fallback).min(self.max_retry_after);
+            fallback = fallback.saturating_mul(2);
+            warn!(%img, ?err, attempt, ?delay, "rate limited by registry, retrying");
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Runs a registry request for `img` with the credentials for its registry, retrying it once
+    /// anonymously if the registry rejects them and anonymous fallback is enabled.
+    ///
+    /// Clients remember the credentials first used for a registry, so the anonymous retry uses a
+    /// client of its own, which replaces `c` so that the remaining requests for the artifact, e.g.
+    /// for its layers, are anonymous as well.
+    async fn authenticated<T, F, Fut>(
+        &self,
+        c: &mut oci_client::Client,
+        img: &Reference,
+        certs: &[oci_client::client::Certificate],
+        mut request: F,
+    ) -> anyhow::Result<T>
+    where
+        F: FnMut(oci_client::Client, oci_client::secrets::RegistryAuth) -> Fut,
+        Fut: Future<Output = Result<T, OciDistributionError>>,
+    {
+        let auth = self.current_auth(c, img).await?;
+        let err = match self
+            .retry_rate_limited(img, certs, || request(c.clone(), auth.clone()))
+            .await
+        {
+            Err(err @ OciFetchError::Unauthorized(_))
+                if self.anonymous_fallback
+                    && auth != oci_client::secrets::RegistryAuth::Anonymous =>
+            {
+                err
+            }
+            res => return Ok(res?),
+        };
+        warn!(%img, ?err, "registry rejected credentials, retrying anonymously");
+        let mut insecure = self.insecure_registries_for(img);
+        insecure.sort();
+        (*c, _) = self.new_client(insecure)?;
+        Ok(self
+            .retry_rate_limited(img, certs, || {
+                request(c.clone(), oci_client::secrets::RegistryAuth::Anonymous)
+            })
+            .await?)
+    }
+
+    /// Looks up how long the registry asks clients to wait after rate-limiting a request.
+    ///
+    /// `oci-client` does not expose the headers of failed responses, so the manifest is requested
+    /// again using `HEAD`, which registries do not count towards pull limits, and the
+    /// `Retry-After` header of that response is used.
+    async fn probe_retry_after(
+        &self,
+        img: &Reference,
+        certs: &[oci_client::client::Certificate],
+    ) -> Option<Duration> {
+        let client = certs
+            .iter()
+            .filter_map(|cert| reqwest::Certificate::from_der(&cert.data).ok())
+            .fold(
+                reqwest::ClientBuilder::default()
+                    .user_agent(self.user_agent)
+                    .connect_timeout(self.connect_timeout)
+                    .read_timeout(self.read_timeout),
+                reqwest::ClientBuilder::add_root_certificate,
+            )
+            .build()
+            .ok()?;
+        // Matches the scheme the registry client picks for the request being retried
+        let registry = img.resolve_registry();
+        let scheme = if self
+            .insecure_registries_for(img)
+            .iter()
+            .any(|insecure| insecure == registry)
+        {
+            "http"
+        } else {
+            "https"
+        };
+        let url = format!(
+            "{scheme}://{registry}/v2/{repository}/manifests/{reference}",
+            repository = img.repository(),
+            reference = img.digest().or(img.tag()).unwrap_or("latest"),
+        );
+        let res = client.head(url).send().await.ok()?;
+        if res.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return None;
+        }
+        let retry_after = res.headers().get(reqwest::header::RETRY_AFTER)?;
+        parse_retry_after(retry_after.to_str().ok()?)
+    }
+
+    /// Fetch an arbitrary OCI artifact into the OCI cache, accepting only layers of the given
+    /// media types. Returns the cached path and whether or not there was a cache hit/miss
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching fails
+    pub async fn fetch_artifact(
+        &self,
+        oci_ref: impl AsRef<str>,
+        accepted_media_types: &[&str],
+    ) -> anyhow::Result<(PathBuf, CacheResult)> {
+        self.fetch_artifact_with(
+            oci_ref,
+            accepted_media_types,
+            OciArtifactCacheUpdate::Update,
+        )
+        .await
+    }
+
+    async fn fetch_artifact_with(
+        &self,
+        oci_ref: impl AsRef<str>,
+        accepted_media_types: &[&str],
+        cache: OciArtifactCacheUpdate,
+    ) -> anyhow::Result<(PathBuf, CacheResult)> {
+        self.fetch_path(
+            self.cache_dir().await?,
+            oci_ref,
+            accepted_media_types.to_vec(),
+            cache,
+        )
+        .await
+        .context("failed to fetch OCI path")
+    }
+
+    /// Fetch component from OCI
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_component(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<Vec<u8>> {
+        self.fetch_component_bytes(oci_ref)
+            .await
+            .map(|bytes| bytes.to_vec())
+    }
+
+    /// Fetch component from OCI as [`Bytes`]. If an in-memory cache is enabled using
+    /// [`OciFetcher::with_memory_cache`], components found in it are returned without reading
+    /// the on-disk cache. The registry is not contacted for references pinned to a digest, nor for
+    /// tags the pull policy allows to be served from cache without revalidation: those resolved at
+    /// any time with [`PullPolicy::IfNotPresent`] or [`PullPolicy::Never`], and those resolved
+    /// within the [`OciFetcher::with_manifest_ttl`] with [`PullPolicy::Revalidate`].
+    ///
+    /// Concurrent fetches of the same reference through this fetcher (or its clones) are
+    /// coalesced into a single fetch, whose result is shared by all callers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_component_bytes(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<Bytes> {
+        let oci_ref = oci_ref.as_ref().to_string();
+        let key = normalize_reference(&self.normalize(&oci_ref))
+            .map(|img| img.whole())
+            .unwrap_or_else(|_| self.normalize(&oci_ref));
+        // Concurrent fetches of the same reference share a single fetch
+        let fetch = self
+            .fetches
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let fetcher = self.clone();
+                async move {
+                    let res = fetcher
+                        .fetch_component_bytes_uncoalesced(&oci_ref)
+                        .await
+                        .map_err(|e| SharedError(Arc::new(e)));
+                    fetcher
+                        .fetches
+                        .0
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .remove(&key);
+                    res
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        fetch.await.map_err(anyhow::Error::new)
+    }
+
+    async fn fetch_component_bytes_uncoalesced(&self, oci_ref: &str) -> anyhow::Result<Bytes> {
+        let Some(memory_cache) = &self.memory_cache else {
+            let (path, _) = self
+                .fetch_artifact(oci_ref, &self.media_types(ArtifactKind::Component))
+                .await?;
+            return read_bytes(&path).await;
+        };
+        let img = normalize_reference(oci_ref).ok();
+        let tag = img
+            .as_ref()
+            .filter(|img| img.digest().is_none())
+            .map(Reference::whole);
+        // Tags resolve to the digest they last resolved to for as long as the pull policy reuses
+        // cached content without asking the registry
+        let tag_ttl = match self.pull_policy {
+            PullPolicy::IfNotPresent | PullPolicy::Never => Some(Duration::MAX),
+            PullPolicy::Revalidate => self.manifest_ttl,
+            PullPolicy::Always => None,
+        };
+        let resolved = match (&img, &tag) {
+            (_, Some(tag)) => tag_ttl.and_then(|ttl| {
+                memory_cache
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .resolved(tag, ttl)
+            }),
+            (Some(img), None) if self.pull_policy != PullPolicy::Always => {
+                img.digest().map(ToString::to_string)
+            }
+            _ => None,
+        };
+        if let Some(digest) = &resolved {
+            self.ensure_digest_allowed(digest)?;
+        }
+        if let Some(bytes) = resolved.and_then(|digest| {
+            memory_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&digest)
+        }) {
+            return Ok(bytes);
+        }
+
+        let FetchedPath { path, digest, .. } = self
+            .fetch_path_with_manifest(
+                self.cache_dir().await?,
+                oci_ref,
+                self.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await
+            .context("failed to fetch OCI path")?;
+        let Some(digest) = digest else {
+            return read_bytes(&path).await;
+        };
+        let cached = memory_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&digest);
+        let bytes = match cached {
+            Some(bytes) => bytes,
+            None => {
+                let bytes = read_bytes(&path).await?;
+                self.cache_in_memory(memory_cache, digest.clone(), bytes.clone());
+                bytes
+            }
+        };
+        if let Some(tag) = tag {
+            memory_cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .resolve(tag, digest);
+        }
+        Ok(bytes)
+    }
+
+    /// Fetch component from OCI along with the digest and annotations of its manifest
+    ///
+    /// The annotations are taken from the manifest retrieved as part of the fetch, so no
+    /// additional registry requests are made.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_component_with_metadata(
+        &self,
+        oci_ref: impl AsRef<str>,
+    ) -> anyhow::Result<FetchedComponent> {
+        let FetchedPath {
+            path,
+            digest,
+            annotations,
+            ..
+        } = self
+            .fetch_path_with_manifest(
+                self.cache_dir().await?,
+                oci_ref,
+                self.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await
+            .context("failed to fetch OCI path")?;
+        let bytes = fs::read(&path)
+            .await
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        Ok(FetchedComponent {
+            bytes,
+            digest,
+            annotations,
+        })
+    }
+
+    /// Fetch component from OCI along with its embedded wascap claims, if any. The claims are
+    /// validated, so a returned token is always signed and currently valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fetching fails, the embedded claims are invalid, or the component
+    /// carries no claims while signed components are required
+    pub async fn fetch_component_signed(
+        &self,
+        oci_ref: impl AsRef<str>,
+    ) -> anyhow::Result<(Vec<u8>, Option<jwt::Token<jwt::Component>>)> {
+        let bytes = self.fetch_component(oci_ref).await?;
+        let claims = self.component_claims(&bytes)?;
+        Ok((bytes, claims))
+    }
+
+    /// Extracts and validates the wascap claims embedded in a component
+    fn component_claims(&self, wasm: &[u8]) -> anyhow::Result<Option<jwt::Token<jwt::Component>>> {
+        let Some(claims) =
+            wascap::wasm::extract_claims(wasm).context("failed to extract component claims")?
+        else {
+            if self.require_signed_components {
+                bail!(OciFetchError::Unsigned)
+            }
+            return Ok(None);
+        };
+        let v = jwt::validate_token::<jwt::Component>(&claims.jwt)
+            .context("failed to validate component token")?;
+        ensure!(!v.expired, "token expired at `{}`", v.expires_human);
+        ensure!(
+            !v.cannot_use_yet,
+            "token cannot be used before `{}`",
+            v.not_before_human
+        );
+        ensure!(v.signature_valid, "signature is not valid");
+        Ok(Some(claims))
+    }
+
+    /// Fetch provider from OCI
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_provider(
+        &self,
+        oci_ref: impl AsRef<str>,
+        host_id: impl AsRef<str>,
+    ) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+        self.fetch_provider_with_metadata(oci_ref, host_id)
+            .await
+            .map(|FetchedProvider { path, claims, .. }| (path, claims))
+    }
+
+    /// Fetch provider from OCI along with the digest of its manifest, identifying the exact
+    /// provider content that was fetched regardless of the tag it is referenced by
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_provider_with_metadata(
+        &self,
+        oci_ref: impl AsRef<str>,
+        host_id: impl AsRef<str>,
+    ) -> anyhow::Result<FetchedProvider> {
+        let FetchResult {
+            path,
+            digest,
+            from_cache,
+            ..
+        } = self
+            .fetch_path_detailed(
+                self.cache_dir().await?,
+                oci_ref.as_ref(),
+                self.media_types(ArtifactKind::Provider),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await
+            .context("failed to fetch OCI path")?;
+        let should_cache = if from_cache {
+            UseParFileCache::Use
+        } else {
+            UseParFileCache::Ignore
+        };
+        let (exe, claims) = crate::par::read_verified_with(
+            &path,
+            host_id,
+            oci_ref,
+            should_cache,
+            self.verify_extracted_providers,
+            |claims| self.verify_provider_claims(claims),
+        )
+        .await
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+        Ok(FetchedProvider {
+            path: exe,
+            claims,
+            digest,
+        })
+    }
+
+    /// Fetch component from OCI, bypassing any cached copy and replacing it with the artifact
+    /// pulled from the registry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_component_force(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<Vec<u8>> {
+        let (path, _) = self
+            .fetch_artifact_with(
+                oci_ref,
+                &self.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::ForceRefresh,
+            )
+            .await?;
+        fs::read(&path)
+            .await
+            .with_context(|| format!("failed to read `{}`", path.display()))
+    }
+
+    /// Fetch provider from OCI, bypassing any cached copy and replacing it with the artifact
+    /// pulled from the registry
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either fetching fails or reading the fetched OCI path fails
+    pub async fn fetch_provider_force(
+        &self,
+        oci_ref: impl AsRef<str>,
+        host_id: impl AsRef<str>,
+    ) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+        let (path, _) = self
+            .fetch_artifact_with(
+                oci_ref.as_ref(),
+                &self.media_types(ArtifactKind::Provider),
+                OciArtifactCacheUpdate::ForceRefresh,
+            )
+            .await?;
+        crate::par::read_verified(&path, host_id, oci_ref, UseParFileCache::Ignore, |claims| {
+            self.verify_provider_claims(claims)
+        })
+        .await
+        .with_context(|| format!("failed to read `{}`", path.display()))
+    }
+
+    /// Validates that `oci_ref` refers to an artifact that could be fetched as a component or
+    /// provider, without downloading it. The reference is parsed and checked against the fetcher
+    /// settings, and only the manifest is pulled from the registry, so the same errors a fetch
+    /// would run into are returned (e.g. [`OciFetchError::LatestDisallowed`],
+    /// [`OciFetchError::Unauthorized`] or [`OciFetchError::NotFound`]). Nothing is written to the
+    /// cache, and the registry is contacted regardless of the pull policy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the artifact could not be fetched
+    pub async fn validate(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<ValidationReport> {
+        let requested = oci_ref.as_ref();
+        let img = self.normalize(requested);
+        self.validate_normalized(&img)
+            .await
+            .with_context(|| format!("failed to validate `{requested}`"))
+    }
+
+    /// Checks whether `oci_ref` exists in its registry and may be pulled with the configured
+    /// credentials, without downloading anything. Only the digest of the manifest is requested,
+    /// using `HEAD`, with the same TLS, authentication and insecure registry configuration as
+    /// fetches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the reference is invalid or the registry could not tell whether it
+    /// exists, e.g. because it rejected the credentials or could not be reached. A missing
+    /// artifact is not an error
+    pub async fn exists(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<bool> {
+        let requested = oci_ref.as_ref();
+        let img = self.normalize(requested);
+        if !self.allow_latest && img.ends_with(":latest") {
+            bail!(OciFetchError::LatestDisallowed)
+        }
+        let img = Reference::from_str(&img).map_err(OciFetchError::from)?;
+        let (mut c, certs) = self.client(&img)?;
+        match self
+            .authenticated(&mut c, &img, &certs, |c, auth| {
+                let img = &img;
+                async move { c.fetch_manifest_digest(img, &auth).await }
+            })
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(OciFetchError::find(&err), Some(OciFetchError::NotFound(_))) => {
+                Ok(false)
+            }
+            Err(err) => Err(err.context(format!("failed to check whether `{requested}` exists"))),
+        }
+    }
+
+    async fn validate_normalized(&self, img: &str) -> anyhow::Result<ValidationReport> {
+        if !self.allow_latest && img.ends_with(":latest") {
+            bail!(OciFetchError::LatestDisallowed)
+        }
+        let img = Reference::from_str(img).map_err(OciFetchError::from)?;
+        let (mut c, certs) = self.client(&img)?;
+        let (manifest, digest) = self.pull_platform_manifest(&mut c, &certs, &img).await?;
+        self.ensure_digest_allowed(&digest)?;
+        let mut accepted_media_types = self.media_types(ArtifactKind::Component);
+        for media_type in self.media_types(ArtifactKind::Provider) {
+            if !accepted_media_types.contains(&media_type) {
+                accepted_media_types.push(media_type);
+            }
+        }
+        check_artifact_type(
+            &manifest,
+            &[ArtifactKind::Component, ArtifactKind::Provider],
+        )?;
+        let layers = select_layers(&manifest, &accepted_media_types)?;
+        let size = layers
+            .iter()
+            .map(|layer| u64::try_from(layer.size).unwrap_or_default())
+            .sum();
+        if let Some(max) = self.max_artifact_bytes {
+            if size > max {
+                bail!(OciFetchError::TooLarge { size, max })
+            }
+        }
+        Ok(ValidationReport {
+            reference: img.whole(),
+            digest,
+            media_type: layers[0].media_type.clone(),
+            size,
+        })
+    }
+
+    /// Fetch each of the given references into the OCI cache ahead of time, so that later fetches
+    /// are served from the cache. References are fetched concurrently (bounded), and a failure to
+    /// fetch one reference does not abort the others.
+    ///
+    /// Returns the cached path or error for each reference, in the order they were given
+    pub async fn warm(&self, refs: &[&str]) -> Vec<(String, anyhow::Result<PathBuf>)> {
+        let mut accepted_media_types = self.media_types(ArtifactKind::Component);
+        for media_type in self.media_types(ArtifactKind::Provider) {
+            if !accepted_media_types.contains(&media_type) {
+                accepted_media_types.push(media_type);
+            }
+        }
+        let accepted_media_types = &accepted_media_types;
+        stream::iter(refs)
+            .map(|oci_ref| async move {
+                let res = self
+                    .fetch_artifact(oci_ref, accepted_media_types)
+                    .await
+                    .map(|(path, _)| path);
+                (oci_ref.to_string(), res)
+            })
+            .buffered(WARM_CONCURRENCY)
+            .collect()
+            .await
+    }
+
+    /// Fetch each of the given component references, with up to `concurrency` fetches in flight at
+    /// once. Unlike [`OciFetcher::warm`], this returns the component bytes. A failure to fetch one
+    /// reference does not abort the others, and fetches of the same reference are coalesced.
+    ///
+    /// Returns the component bytes or error for each reference, in the order they were given
+    pub async fn fetch_components(
+        &self,
+        refs: &[&str],
+        concurrency: usize,
+    ) -> Vec<(String, anyhow::Result<Vec<u8>>)> {
+        stream::iter(refs)
+            .map(
+                |oci_ref| async move { (oci_ref.to_string(), self.fetch_component(oci_ref).await) },
+            )
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Imports the component or provider archive at `path` into the OCI cache as the artifact
+    /// referenced by `oci_ref`, returning the digest of its content. Components are recognized by
+    /// their WebAssembly header, anything else is imported as a provider archive.
+    ///
+    /// Later fetches of `oci_ref` are served from the cache, without contacting the registry if
+    /// the [`PullPolicy`] allows it. Otherwise, the imported content is verified against the
+    /// manifest in the registry, and only pulled again if it does not match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `oci_ref` is invalid or `path` cannot be read or cached
+    pub async fn import(
+        &self,
+        oci_ref: impl AsRef<str>,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<String> {
+        let path = path.as_ref();
+        let img = self.normalize(oci_ref.as_ref());
+        let reference = Reference::from_str(&img).map_err(OciFetchError::from)?;
+        let content = fs::read(path)
+            .await
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        let media_type = if content.starts_with(b"\0asm") {
+            WASM_LAYER_MEDIA_TYPE
+        } else {
+            PROVIDER_ARCHIVE_MEDIA_TYPE
+        };
+        let index = CacheIndex::new(self.cache_dir().await?, &img);
+        let digest = index
+            .import(&reference, &content, media_type, reference.digest())
+            .await
+            .map_err(OciFetchError::Cache)
+            .with_context(|| format!("failed to import `{}`", path.display()))?;
+        self.cache_written(
+            &index,
+            &blob_path(&index.dir, &digest),
+            content.len() as u64,
+        );
+        Ok(digest)
+    }
+
+    /// Writes every entry of the OCI cache, along with the blobs they reference, to `writer` as a
+    /// tar archive, which can be restored on another host with [`OciFetcher::import_cache`].
+    /// Returns the number of exported entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be read or the archive cannot be written
+    pub async fn export_cache(
+        &self,
+        writer: impl AsyncWrite + Unpin + Send + 'static,
+    ) -> anyhow::Result<usize> {
+        let dir = self.cache_dir().await?;
+        let mut archive = tokio_tar::Builder::new(writer);
+        let mut exported = 0;
+        let mut blobs = HashSet::new();
+        for entry in stored_entries(&dir).await? {
+            if fs::metadata(&entry.blob).await.is_err() {
+                continue;
+            }
+            if blobs.insert(entry.blob.clone()) {
+                append_cache_file(&mut archive, &dir, &entry.blob).await?;
+            }
+            for file in [
+                entry.index.manifest_file(),
+                entry.index.media_types_file(),
+                entry.index.reference_file(),
+                entry.index.metadata_file(),
+                entry.index.digest_file(),
+                entry.index.blob_file(),
+            ] {
+                if fs::metadata(&file).await.is_ok() {
+                    append_cache_file(&mut archive, &dir, &file).await?;
+                }
+            }
+            exported += 1;
+        }
+        let mut writer = archive
+            .into_inner()
+            .await
+            .context("failed to write cache archive")?;
+        writer
+            .flush()
+            .await
+            .context("failed to write cache archive")?;
+        Ok(exported)
+    }
+
+    /// Restores the OCI cache entries from a tar archive written by
+    /// [`OciFetcher::export_cache`], replacing cached entries for the same references. Restored
+    /// entries keep the manifest digests they were pulled from, so they are served from the
+    /// cache as if they were fetched on this host. Returns the number of restored entries.
+    ///
+    /// Blobs are verified against their digest before being added to the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, contains unexpected files or blobs that do
+    /// not match their digest, or if the cache cannot be written
+    pub async fn import_cache(
+        &self,
+        reader: impl AsyncRead + Unpin + Send + 'static,
+    ) -> anyhow::Result<usize> {
+        let dir = self.cache_dir().await?;
+        let mut archive = tokio_tar::Archive::new(reader);
+        let mut entries = archive.entries().context("failed to read cache archive")?;
+        let mut indexes: HashMap<String, HashMap<String, Vec<u8>>> = HashMap::new();
+        while let Some(entry) = entries.next().await {
+            let mut entry = entry.context("failed to read cache archive")?;
+            let path = entry
+                .path()
+                .context("failed to read cache archive")?
+                .into_owned();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).await.with_context(|| {
+                format!("failed to read `{}` from cache archive", path.display())
+            })?;
+            match cache_archive_path(&path) {
+                Some(CacheArchivePath::Blob(digest)) => {
+                    ensure!(
+                        sha256_digest(&content) == digest,
+                        "blob `{digest}` in cache archive does not match its digest"
+                    );
+                    write_blob(&dir, &content, None)
+                        .await
+                        .map_err(OciFetchError::Cache)?;
+                }
+                Some(CacheArchivePath::Index { key, extension }) => {
+                    indexes.entry(key).or_default().insert(extension, content);
+                }
+                None => bail!("unexpected file `{}` in cache archive", path.display()),
+            }
+        }
+        let mut imported = 0;
+        for (key, mut files) in indexes {
+            let Some(blob) = files.remove("blob") else {
+                continue;
+            };
+            let digest = String::from_utf8_lossy(&blob);
+            ensure!(
+                fs::metadata(blob_path(&dir, digest.trim())).await.is_ok(),
+                "blob `{}` referenced by `{key}` is missing from cache archive",
+                digest.trim()
+            );
+            let index = CacheIndex {
+                dir: dir.clone(),
+                key,
+            };
+            // Write the manifest digest last, so the entry is only trusted once complete
+            for file in [
+                index.digest_file(),
+                index.manifest_file(),
+                index.reference_file(),
+                index.metadata_file(),
+            ] {
+                if let Err(e) = fs::remove_file(file).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        return Err(OciFetchError::Cache(e).into());
+                    }
+                }
+            }
+            for (file, extension) in [
+                (index.manifest_file(), "manifest"),
+                (index.media_types_file(), "mediatypes"),
+                (index.reference_file(), "ref"),
+                (index.metadata_file(), "json"),
+                (index.blob_file(), "blob"),
+                (index.digest_file(), "digest"),
+            ] {
+                let content = if extension == "blob" {
+                    Some(blob.clone())
+                } else {
+                    files.remove(extension)
+                };
+                if let Some(content) = content {
+                    fs::write(file, content)
+                        .await
+                        .map_err(OciFetchError::Cache)?;
+                }
+            }
+            imported += 1;
+        }
+        if imported > 0 {
+            self.refresh_cache_usage(&dir).await;
+        }
+        Ok(imported)
+    }
+
+    /// Lists the entries of the OCI cache, along with the references they were cached for
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be read
+    pub async fn list_cache(&self) -> anyhow::Result<Vec<CacheEntry>> {
+        let dir = self.cache_dir().await?;
+        let mut listed = Vec::new();
+        for entry in stored_entries(&dir).await? {
+            let (reference, digest) = entry.index.reference().await;
+            let metadata = entry.index.metadata().await;
+            let digest = match digest {
+                Some(digest) => Some(digest),
+                None => fs::read_to_string(entry.index.digest_file()).await.ok(),
+            };
+            listed.push(CacheEntry {
+                key: entry.index.key,
+                reference,
+                digest,
+                size: entry.size,
+                fetched_at: entry.fetched_at,
+                accessed_at: entry.accessed_at,
+                metadata,
+            });
+        }
+        listed.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(listed)
+    }
+
+    /// Removes entries from the OCI cache according to `policy`, returning the number of entries
+    /// removed and the bytes reclaimed. Entries with a fetch currently in flight are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be read or an entry cannot be removed
+    pub async fn prune_cache(&self, policy: CachePrunePolicy) -> anyhow::Result<CachePruneStats> {
+        let dir = self.cache_dir().await?;
+        let evicted = prune_oci_cache(&dir, policy).await?;
+        let stats = CachePruneStats::from_evicted(&evicted);
+        self.notify_evicted(evicted);
+        if stats.entries > 0 {
+            self.refresh_cache_usage(&dir).await;
+        }
+        Ok(stats)
+    }
+
+    /// Whether usage of the on-disk cache needs to be tracked, i.e. it is reported or limited
+    fn tracks_cache_usage(&self) -> bool {
+        self.metrics.is_some() || self.max_cache_bytes.is_some()
+    }
+
+    /// Accounts for an entry written to the on-disk cache, pointing at `blob` of `size` bytes.
+    /// Usage is reported, and the cache is pruned if it exceeds its limit, in the background
+    fn cache_written(&self, index: &CacheIndex, blob: &Path, size: u64) {
+        if !self.tracks_cache_usage() {
+            return;
+        }
+        let fetcher = self.clone();
+        let dir = index.dir.clone();
+        let key = index.key.clone();
+        let blob = blob.to_path_buf();
+        tokio::spawn(
+            async move {
+                let usage = {
+                    let mut tracker = fetcher.cache_usage.0.lock().await;
+                    let index = match tracker.take() {
+                        Some(index) if index.dir == dir => Some(index),
+                        _ => CacheUsageIndex::scan(&dir)
+                            .await
+                            .inspect_err(|err| debug!(?err, "failed to scan OCI cache"))
+                            .ok(),
+                    };
+                    let Some(mut index) = index else {
+                        return;
+                    };
+                    index.insert(key, blob, size);
+                    let usage = index.usage(fetcher.max_cache_bytes);
+                    *tracker = Some(index);
+                    usage
+                };
+                fetcher.report_cache_usage(usage);
+                match fetcher.max_cache_bytes {
+                    Some(max) if usage.bytes > max => {
+                        if let Err(err) = fetcher.prune_cache(CachePrunePolicy::MaxSize(max)).await
+                        {
+                            warn!(?err, "failed to prune OCI cache down to its limit");
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            .in_current_span(),
+        );
+    }
+
+    /// Rebuilds the running totals of the on-disk cache under `dir` and reports them
+    async fn refresh_cache_usage(&self, dir: &Path) {
+        if !self.tracks_cache_usage() {
+            return;
+        }
+        let mut tracker = self.cache_usage.0.lock().await;
+        match CacheUsageIndex::scan(dir).await {
+            Ok(index) => {
+                let usage = index.usage(self.max_cache_bytes);
+                *tracker = Some(index);
+                drop(tracker);
+                self.report_cache_usage(usage);
+            }
+            Err(err) => {
+                debug!(?err, "failed to scan OCI cache");
+                *tracker = None;
+            }
+        }
+    }
+
+    /// Reports `usage` to the configured [`FetchMetrics`], if any
+    fn report_cache_usage(&self, usage: CacheUsage) {
+        if let Some(MetricsHook(metrics)) = &self.metrics {
+            metrics.record_cache_usage(usage);
+        }
+    }
+
+    /// Caches component `bytes` with manifest `digest` in the in-memory cache
+    fn cache_in_memory(&self, memory_cache: &Mutex<MemoryCache>, digest: String, bytes: Bytes) {
+        let evicted = memory_cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(digest, bytes);
+        self.notify_evicted(evicted);
+    }
+
+    /// Reports `evicted` entries to the callback set with [`OciFetcher::with_eviction_callback`].
+    /// The callback runs on a blocking thread, so that it does not delay the fetch that caused
+    /// the eviction
+    fn notify_evicted(&self, evicted: Vec<EvictedEntry>) {
+        let Some(EvictionHook(callback)) = &self.eviction_callback else {
+            return;
+        };
+        if evicted.is_empty() {
+            return;
+        }
+        let callback = Arc::clone(callback);
+        tokio::task::spawn_blocking(move || evicted.into_iter().for_each(|entry| callback(entry)));
+    }
+
+    /// Layer media types accepted when fetching the given kind of artifact, including any
+    /// registered with [`OciFetcher::with_media_type`]
+    #[must_use]
+    pub fn media_types(&self, kind: ArtifactKind) -> Vec<&str> {
+        let mut media_types = kind.default_media_types().to_vec();
+        if let Some(extra) = self.media_types.get(&kind) {
+            media_types.extend(extra.iter().map(String::as_str));
+        }
+        media_types
+    }
+
+    /// Used to register an additional layer media type that will be accepted when fetching the
+    /// given kind of artifact
+    pub fn with_media_type(mut self, kind: ArtifactKind, media_type: impl Into<String>) -> Self {
+        self.media_types
+            .entry(kind)
+            .or_default()
+            .push(media_type.into());
+        self
+    }
+
+    /// Used to set the registries that may be accessed over plain HTTP. All other registries are
+    /// only accessed over HTTPS
+    pub fn with_insecure_registries(
+        mut self,
+        registries: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.clients = ClientCache::default();
+        self.insecure_registries = registries.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Used to set how long the manifest of a cached artifact is trusted without asking the
+    /// registry whether the reference still resolves to it. Within this time, cache hits do not
+    /// require any network access. By default the registry is consulted on every fetch
+    pub fn with_manifest_ttl(mut self, ttl: Duration) -> Self {
+        self.manifest_ttl = Some(ttl);
+        self
+    }
+
+    /// Used to set a scratch directory that downloads are written to before being moved into the
+    /// cache once complete, e.g. fast local disk when the cache is on network storage
+    pub fn with_download_tmp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.download_tmp_dir = Some(dir.into());
+        self
+    }
+
+    /// Used to set a receiver for metrics about every fetch performed by this fetcher
+    pub fn with_metrics(mut self, metrics: Arc<dyn FetchMetrics>) -> Self {
+        self.metrics = Some(MetricsHook(metrics));
+        self
+    }
+
+    /// Used to enable an in-memory cache of up to `max_bytes` of component bytes in front of the
+    /// on-disk cache, evicting the least recently used components once full. The cache is shared
+    /// by all clones of this fetcher
+    pub fn with_memory_cache(mut self, max_bytes: usize) -> Self {
+        self.memory_cache = Some(Arc::new(Mutex::new(MemoryCache::new(max_bytes))));
+        self
+    }
+
+    /// Used to cache fetched artifacts in `store` rather than the OCI cache directory, e.g. to share
+    /// pulled artifacts between hosts. Content the store does not keep on the local filesystem is
+    /// copied into the OCI cache directory to be read from a path, which is also where the cached
+    /// manifests and other metadata of artifacts are kept
+    pub fn with_cache_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.cache_store = Some(CacheStoreHook(store));
+        self
+    }
+
+    /// Used to set the maximum size of fetched artifacts in bytes. Artifacts whose manifest
+    /// declares a larger size are rejected before any of their content is downloaded
+    pub fn with_max_artifact_bytes(mut self, max: u64) -> Self {
+        self.max_artifact_bytes = Some(max);
+        self
+    }
+
+    /// Subscribes to [`FetchEvent`]s about the lifecycle of fetches performed by this fetcher and
+    /// its clones. Events are only built while there are subscribers, so fetchers nobody
+    /// subscribed to pay nothing for them.
+    ///
+    /// Delivery is best-effort: events of a fetch are delivered in order, but subscribers falling
+    /// more than 256 events behind miss the oldest ones, which is reported as
+    /// [`broadcast::error::RecvError::Lagged`], rather than slowing down fetches. Events of
+    /// concurrent fetches may be interleaved.
+    pub fn subscribe(&self) -> broadcast::Receiver<FetchEvent> {
+        self.events
+            .get_or_init(|| broadcast::channel(FETCH_EVENTS_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Used to set the registry prepended to references that do not name one, such as
+    /// `myteam/mycomponent:1.0`, which would otherwise be fetched from Docker Hub. References
+    /// naming a registry are fetched from it regardless, see [`qualify_reference`]
+    pub fn with_default_registry(mut self, registry: impl Into<String>) -> Self {
+        self.default_registry = Some(registry.into());
+        self
+    }
+
+    /// Returns `oci_ref` as fetched by this fetcher, i.e. lowercased, since the OCI specification
+    /// does not allow capital letters in repository names, and prefixed with the default
+    /// registry if it does not name one
+    fn normalize(&self, oci_ref: &str) -> String {
+        match &self.default_registry {
+            Some(registry) => qualify_reference(oci_ref, registry).to_lowercase(),
+            None => oci_ref.to_lowercase(),
+        }
+    }
+
+    /// Used to set when the registry is contacted for artifacts that may already be cached, see
+    /// [`PullPolicy`]
+    pub fn with_pull_policy(mut self, policy: PullPolicy) -> Self {
+        self.pull_policy = policy;
+        self
+    }
+
+    /// Used to set a callback invoked with every entry evicted from the in-memory cache or pruned
+    /// from the on-disk cache, e.g. for auditing. The callback runs on a blocking thread, off the
+    /// path of the fetch that caused the eviction
+    pub fn with_eviction_callback(mut self, callback: EvictionCallback) -> Self {
+        self.eviction_callback = Some(EvictionHook(callback));
+        self
+    }
+
+    /// Used to set the credentials for specific registries, keyed by registry host (e.g.
+    /// `docker.io` or `123456789012.dkr.ecr.us-east-1.amazonaws.com`). Artifacts from registries
+    /// without credentials of their own are fetched with the fetcher's default credentials
+    pub fn with_registry_auths(
+        mut self,
+        auths: impl IntoIterator<Item = (impl Into<String>, crate::RegistryAuth)>,
+    ) -> Self {
+        self.registry_auths.extend(
+            auths
+                .into_iter()
+                .map(|(registry, auth)| (registry.into().to_lowercase(), auth.into())),
+        );
+        self
+    }
+
+    /// Used to limit the on-disk cache to `max` bytes. Whenever a write takes the cache over the
+    /// limit, the least recently used entries are pruned in the background, as with
+    /// [`CachePrunePolicy::MaxSize`]. The limit is also reported along with cache usage to the
+    /// receiver set with [`OciFetcher::with_metrics`]
+    pub fn with_max_cache_bytes(mut self, max: u64) -> Self {
+        self.max_cache_bytes = Some(max);
+        self
+    }
+
+    /// Used to set the directory in which fetched artifacts are cached, overriding the
+    /// `WASMCLOUD_OCI_CACHE_DIR` environment variable and the default, see [`oci_cache_dir`]
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Used to only allow fetching artifacts whose manifest digest is in `digests`, regardless of
+    /// the tag they are referenced by. This composes with signature verification, e.g. to ensure
+    /// only vetted content runs even if a tag is repointed to content signed by a trusted key
+    pub fn with_allowed_digests(
+        mut self,
+        digests: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.allowed_digests = Some(digests.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Used to require that fetched provider archives carry valid wascap claims issued by one of
+    /// `issuers`. The claims are verified before the provider is extracted, so nothing from an
+    /// untrusted archive is written to disk. No issuers are required if `issuers` is empty
+    pub fn with_required_provider_issuers(mut self, issuers: &[String]) -> Self {
+        self.required_provider_issuers =
+            (!issuers.is_empty()).then(|| issuers.iter().cloned().collect());
+        self
+    }
+
+    /// Used to verify that previously extracted provider binaries still match the digest recorded
+    /// at extraction time before reusing them, extracting them again otherwise. This hashes the
+    /// extracted binary on every cached fetch, so it is disabled by default
+    pub fn with_verify_extracted_providers(mut self, verify: bool) -> Self {
+        self.verify_extracted_providers = verify;
+        self
+    }
+
+    /// Used to retry a pull anonymously once if the registry rejects the configured credentials,
+    /// e.g. expired credentials used for a registry serving public artifacts
+    pub fn with_anonymous_fallback(mut self, fallback: bool) -> Self {
+        self.anonymous_fallback = fallback;
+        self
+    }
+
+    /// Used to authenticate with a token read from `path`, e.g. a projected Kubernetes service
+    /// account token, instead of the default credentials. The file is read again on every pull,
+    /// so that rotated tokens are picked up without restarting.
+    ///
+    /// The token is sent as the password of Basic credentials, which registries exchange for a
+    /// bearer token. Registries with credentials set with [`OciFetcher::with_registry_auths`]
+    /// keep using those
+    pub fn with_token_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.token_file = Some(TokenFile {
+            path: path.into(),
+            current: Arc::default(),
+        });
+        self
+    }
+
+    /// Used to require that components fetched with [`OciFetcher::fetch_component_signed`] carry
+    /// signed wascap claims
+    pub fn with_require_signed_components(mut self, require: bool) -> Self {
+        self.require_signed_components = require;
+        self
+    }
+
+    /// Used to set the User-Agent header sent with all registry requests, which defaults to
+    /// `wasmcloud/<version>`
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.clients = ClientCache::default();
+        let user_agent = user_agent.into();
+        let mut user_agents = USER_AGENTS.lock().unwrap_or_else(PoisonError::into_inner);
+        self.user_agent = match user_agents.get(user_agent.as_str()) {
+            Some(user_agent) => user_agent,
+            None => {
+                let user_agent = Box::leak(user_agent.into_boxed_str());
+                user_agents.insert(user_agent);
+                user_agent
+            }
+        };
+        self
+    }
+
+    /// Used to set how long establishing a connection to a registry may take, which defaults to
+    /// 10 seconds.
+    ///
+    /// Timed out requests are not retried by the fetcher, which only retries rate-limited
+    /// requests. The fetch fails with [`OciFetchError::Network`] instead, which
+    /// [`OciFetchError::is_retryable`] reports as retryable, so a short timeout lets callers
+    /// retrying fetches move on from an unreachable registry quickly
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.clients = ClientCache::default();
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Used to set how long a registry response may go without sending any data, which defaults
+    /// to 60 seconds. This does not limit the total duration of a download, so large artifacts
+    /// are not cut off while data keeps arriving.
+    ///
+    /// Like connection timeouts, read timeouts fail the fetch with a retryable
+    /// [`OciFetchError::Network`] rather than being retried by the fetcher
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.clients = ClientCache::default();
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Used to set the platform whose manifest is fetched when a reference resolves to an OCI image
+    /// index, which defaults to [`Platform::current`]. Fetches fail with
+    /// [`OciFetchError::NoMatchingPlatform`] if an index has no manifest for the platform
+    pub fn with_platform(mut self, platform: Platform) -> Self {
+        self.platform = platform;
+        self
+    }
+
+    /// Used to set how many times resolving the manifest to revalidate a cached artifact against is
+    /// retried after failing transiently, e.g. due to a network error, and the delay before the
+    /// first retry, which is doubled on every attempt. Defaults to 2 retries, starting after 100
+    /// milliseconds.
+    ///
+    /// Should resolving the manifest still fail, the cached artifact is served instead of
+    /// failing the fetch, unless its reference is tagged `latest`, which is expected to move
+    pub fn with_manifest_retries(mut self, retries: u32, delay: Duration) -> Self {
+        self.manifest_retries = retries;
+        self.manifest_retry_delay = delay;
+        self
+    }
+
+    /// Used to set how many times a request rate-limited by the registry is retried, and the
+    /// maximum time to wait before each retry regardless of the registry's `Retry-After` header
+    pub fn with_rate_limit_retries(mut self, retries: u32, max_retry_after: Duration) -> Self {
+        self.rate_limit_retries = retries;
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    /// Used to set additional CA paths that will be used as part of fetching components and providers
+    pub fn with_additional_ca_paths(mut self, paths: &[impl AsRef<Path>]) -> Self {
+        self.clients = ClientCache::default();
+        self.additional_ca_paths = paths.iter().map(AsRef::as_ref).map(PathBuf::from).collect();
+        self
+    }
+
+    /// Reloads the trusted root certificates, i.e. the native root certificate store of the system
+    /// and the certificates in [`OciFetcher::with_additional_ca_paths`], so that rotated or
+    /// revoked CAs take effect without a restart.
+    ///
+    /// The new roots are swapped in only once they all loaded successfully, and registry clients
+    /// built with the previous roots are discarded, so later fetches use the new roots. The native
+    /// root store is shared by all fetchers.
+    pub fn refresh_trust(&self) -> anyhow::Result<()> {
+        if !self.additional_ca_paths.is_empty() {
+            tls::load_certs_from_paths(&self.additional_ca_paths)
+                .context("failed to load CA certs from provided paths")?;
+        }
+        let roots = tls::refresh_native_roots().context("failed to reload native root store")?;
+        debug!(roots, "reloaded trusted root certificates");
+        self.clients
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        Ok(())
+    }
+
+    /// Used to set additional PEM-encoded CA certificates that will be used as part of fetching
+    /// components and providers, in addition to those loaded from
+    /// [`OciFetcher::with_additional_ca_paths`]
+    pub fn with_additional_ca_pems(mut self, pems: &[impl AsRef<[u8]>]) -> Self {
+        self.clients = ClientCache::default();
+        self.additional_ca_pems = pems.iter().map(|pem| pem.as_ref().to_vec()).collect();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_identical_content_is_deduplicated() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let (first, first_digest) = write_blob(dir.path(), b"component", None).await?;
+        let (second, second_digest) = write_blob(dir.path(), b"component", None).await?;
+        assert_eq!(first, second);
+        assert_eq!(first_digest, second_digest);
+        assert_eq!(first, blob_path(dir.path(), &first_digest));
+        assert!(first.starts_with(dir.path().join("blobs").join("sha256")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blobs_are_written_via_download_tmp_dir() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let tmp = tempfile::tempdir()?;
+        let (blob, _) = write_blob(dir.path(), b"component", Some(tmp.path())).await?;
+        assert_eq!(fs::read(&blob).await?, b"component");
+        // Nothing is left behind in either directory
+        assert_eq!(std::fs::read_dir(tmp.path())?.count(), 0);
+        let parent = blob.parent().expect("blob should have a parent");
+        assert_eq!(std::fs::read_dir(parent)?.count(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_legacy_cache_entries_are_migrated() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let index = CacheIndex::new(dir.path(), "ghcr.io/wasmcloud/http:0.1.0");
+        fs::write(index.legacy_file(), b"legacy").await?;
+        fs::write(index.digest_file(), "sha256:manifest").await?;
+
+        assert_eq!(migrate_oci_cache(dir.path()).await?, 1);
+        assert!(fs::metadata(index.legacy_file()).await.is_err());
+        let blob = index.blob().await.expect("migrated entry should be cached");
+        assert_eq!(fs::read(&blob).await?, b"legacy");
+        assert_eq!(
+            fs::read_to_string(index.digest_file()).await?,
+            "sha256:manifest"
+        );
+
+        // Migration is idempotent
+        assert_eq!(migrate_oci_cache(dir.path()).await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_media_types_are_checked() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let index = CacheIndex::new(dir.path(), "ghcr.io/wasmcloud/http:0.1.0");
+        let components = ArtifactKind::Component.default_media_types();
+        let providers = ArtifactKind::Provider.default_media_types();
+        // Entries cached before media types were recorded are not trusted
+        assert!(!index.has_media_types(components).await);
+
+        let manifest = OciManifest::Image(manifest(
+            WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+            &[WASM_LAYER_MEDIA_TYPE],
+        ));
+        index
+            .store(
+                &FsCacheStore::new(dir.path()),
+                &Reference::from_str("ghcr.io/wasmcloud/http:0.1.0")?,
+                Bytes::from_static(b"component"),
+                WASM_LAYER_MEDIA_TYPE,
+                "sha256:manifest",
+                &manifest,
+            )
+            .await?;
+        assert!(index.has_media_types(components).await);
+        assert!(!index.has_media_types(providers).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_with_and_without_digest() -> anyhow::Result<()> {
+        let accepted = ArtifactKind::Component.default_media_types();
+        let mut image = manifest(WASM_MANIFEST_CONFIG_MEDIA_TYPE, &[WASM_LAYER_MEDIA_TYPE]);
+        image.layers[0].digest = sha256_digest(b"component");
+        let image = OciManifest::Image(image);
+        let cached = |dir: &Path, content: Option<&[u8]>, digest: Option<&str>| {
+            let dir = dir.to_path_buf();
+            let content = content.map(<[u8]>::to_vec);
+            let digest = digest.map(str::to_string);
+            async move {
+                let index = CacheIndex::new(&dir, "ghcr.io/wasmcloud/http:0.1.0");
+                if let Some(content) = content {
+                    let (_, blob_digest) = write_blob(&dir, &content, None).await?;
+                    fs::write(index.blob_file(), blob_digest).await?;
+                }
+                if let Some(digest) = digest {
+                    fs::write(index.digest_file(), digest).await?;
+                }
+                anyhow::Ok(index)
+            }
+        };
+
+        // Content and digest present
+        let dir = tempfile::tempdir()?;
+        let index = cached(dir.path(), Some(b"component"), Some("sha256:manifest")).await?;
+        let blob = index.blob().await.expect("content should be cached");
+        assert!(
+            index
+                .matches_manifest(
+                    &FsCacheStore::new(dir.path()),
+                    &blob,
+                    &image,
+                    "sha256:manifest",
+                    accepted
+                )
+                .await
+        );
+        assert!(
+            !index
+                .matches_manifest(
+                    &FsCacheStore::new(dir.path()),
+                    &blob,
+                    &image,
+                    "sha256:new",
+                    accepted
+                )
+                .await
+        );
+
+        // Content present without a digest is re-hashed against the manifest
+        let dir = tempfile::tempdir()?;
+        let index = cached(dir.path(), Some(b"component"), None).await?;
+        let blob = index.blob().await.expect("content should be cached");
+        assert!(
+            index
+                .matches_manifest(
+                    &FsCacheStore::new(dir.path()),
+                    &blob,
+                    &image,
+                    "sha256:manifest",
+                    accepted
+                )
+                .await
+        );
+        assert_eq!(
+            fs::read_to_string(index.digest_file()).await?,
+            "sha256:manifest"
+        );
+        let dir = tempfile::tempdir()?;
+        let index = cached(dir.path(), Some(b"tampered"), None).await?;
+        let blob = index.blob().await.expect("content should be cached");
+        assert!(
+            !index
+                .matches_manifest(
+                    &FsCacheStore::new(dir.path()),
+                    &blob,
+                    &image,
+                    "sha256:manifest",
+                    accepted
+                )
+                .await
+        );
+        assert!(fs::metadata(index.digest_file()).await.is_err());
+
+        // Without content, there is nothing to reuse regardless of the digest
+        let dir = tempfile::tempdir()?;
+        let index = cached(dir.path(), None, Some("sha256:manifest")).await?;
+        assert!(index.blob().await.is_none());
+        let dir = tempfile::tempdir()?;
+        let index = cached(dir.path(), None, None).await?;
+        assert!(index.blob().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_path_detailed() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let img = "ghcr.io/wasmcloud/http:0.1.0";
+        let mut image = manifest(WASM_MANIFEST_CONFIG_MEDIA_TYPE, &[WASM_LAYER_MEDIA_TYPE]);
+        image.layers[0].digest = sha256_digest(b"component");
+        CacheIndex::new(dir.path(), img)
+            .store(
+                &FsCacheStore::new(dir.path()),
+                &Reference::from_str("ghcr.io/wasmcloud/http:0.1.0")?,
+                Bytes::from_static(b"component"),
+                WASM_LAYER_MEDIA_TYPE,
+                "sha256:manifest",
+                &OciManifest::Image(image),
+            )
+            .await?;
+
+        // A fresh cached manifest is served without contacting the registry
+        let fetcher = OciFetcher::default().with_manifest_ttl(Duration::from_secs(60));
+        let res = fetcher
+            .fetch_path_detailed(
+                dir.path(),
+                img,
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await?;
+        assert!(res.from_cache);
+        assert_eq!(res.digest.as_deref(), Some("sha256:manifest"));
+        assert_eq!(res.media_type, WASM_LAYER_MEDIA_TYPE);
+        assert_eq!(res.size, b"component".len() as u64);
+        assert_eq!(fs::read(&res.path).await?, b"component");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pull_policy() -> anyhow::Result<()> {
+        assert_eq!(
+            "IfNotPresent".parse::<PullPolicy>()?,
+            PullPolicy::IfNotPresent
+        );
+        assert_eq!(
+            "if-not-present".parse::<PullPolicy>()?,
+            PullPolicy::IfNotPresent
+        );
+        assert_eq!("always".parse::<PullPolicy>()?, PullPolicy::Always);
+        assert!("sometimes".parse::<PullPolicy>().is_err());
+
+        // Nothing listens on the registry, so any request to it fails
+        let dir = tempfile::tempdir()?;
+        let cached = "127.0.0.1:1/wasmcloud/http:0.1.0";
+        let image = OciManifest::Image(manifest(
+            WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+            &[WASM_LAYER_MEDIA_TYPE],
+        ));
+        CacheIndex::new(dir.path(), cached)
+            .store(
+                &FsCacheStore::new(dir.path()),
+                &Reference::from_str("ghcr.io/wasmcloud/http:0.1.0")?,
+                Bytes::from_static(b"component"),
+                WASM_LAYER_MEDIA_TYPE,
+                "sha256:manifest",
+                &image,
+            )
+            .await?;
+        let fetch = |policy, img: &'static str| {
+            let fetcher = OciFetcher::default().with_pull_policy(policy);
+            let dir = dir.path().to_path_buf();
+            async move {
+                fetcher
+                    .fetch_path_detailed(
+                        dir,
+                        img,
+                        fetcher.media_types(ArtifactKind::Component),
+                        OciArtifactCacheUpdate::Update,
+                    )
+                    .await
+            }
+        };
+        for policy in [PullPolicy::IfNotPresent, PullPolicy::Never] {
+            let res = fetch(policy, cached).await?;
+            assert!(res.from_cache);
+            assert_eq!(res.digest.as_deref(), Some("sha256:manifest"));
+        }
+        // Failing to revalidate falls back to the cached copy
+        assert!(fetch(PullPolicy::Revalidate, cached).await?.from_cache);
+        assert!(fetch(PullPolicy::Always, cached).await.is_err());
+        let err = fetch(PullPolicy::Never, "127.0.0.1:1/wasmcloud/http:0.2.0")
+            .await
+            .expect_err("uncached artifact should not be pulled");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::NotCached)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_auths() -> anyhow::Result<()> {
+        let fetcher = OciFetcher::default().with_registry_auths([
+            (
+                "Docker.io",
+                crate::RegistryAuth::Basic("hub".into(), "secret".into()),
+            ),
+            (
+                "123456789012.dkr.ecr.us-east-1.amazonaws.com",
+                crate::RegistryAuth::Basic("AWS".into(), "token".into()),
+            ),
+        ]);
+        let auth_for =
+            |img| -> anyhow::Result<_> { Ok(fetcher.auth_for(&normalize_reference(img)?).clone()) };
+        assert!(matches!(
+            auth_for("wasmcloud/http:0.2.0")?,
+            oci_client::secrets::RegistryAuth::Basic(user, _) if user == "hub"
+        ));
+        assert!(matches!(
+            auth_for("123456789012.dkr.ecr.us-east-1.amazonaws.com/http:0.2.0")?,
+            oci_client::secrets::RegistryAuth::Basic(user, _) if user == "AWS"
+        ));
+        assert!(matches!(
+            auth_for("ghcr.io/wasmcloud/http:0.2.0")?,
+            oci_client::secrets::RegistryAuth::Anonymous
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_token_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("token");
+        fs::write(&path, "first\n").await?;
+        let fetcher = OciFetcher::default()
+            .with_token_file(&path)
+            .with_registry_auths([(
+                "docker.io",
+                crate::RegistryAuth::Basic("hub".into(), "secret".into()),
+            )]);
+        let img = normalize_reference("ghcr.io/wasmcloud/http:0.2.0")?;
+        let (mut c, _) = fetcher.client(&img)?;
+        let token = |password: &str| {
+            oci_client::secrets::RegistryAuth::Basic(
+                TOKEN_FILE_USERNAME.to_string(),
+                password.to_string(),
+            )
+        };
+        assert_eq!(fetcher.current_auth(&mut c, &img).await?, token("first"));
+
+        // Rotations are picked up, even if the file is briefly absent
+        fs::remove_file(&path).await?;
+        let rotate = tokio::spawn({
+            let path = path.clone();
+            async move {
+                tokio::time::sleep(TOKEN_FILE_RETRY_DELAY).await;
+                fs::write(path, "second").await
+            }
+        });
+        assert_eq!(fetcher.current_auth(&mut c, &img).await?, token("second"));
+        rotate.await??;
+
+        // Registries with credentials of their own do not use the token
+        let hub = normalize_reference("wasmcloud/http:0.2.0")?;
+        assert!(matches!(
+            fetcher.current_auth(&mut c, &hub).await?,
+            oci_client::secrets::RegistryAuth::Basic(user, _) if user == "hub"
+        ));
+
+        fs::remove_file(&path).await?;
+        assert!(fetcher.current_auth(&mut c, &img).await.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_reference() -> anyhow::Result<()> {
+        let short = normalize_reference("WasmCloud/HTTP:0.2.0")?;
+        let full = normalize_reference("docker.io/wasmcloud/http:0.2.0")?;
+        assert_eq!(short.whole(), full.whole());
+        assert_eq!(short.repository(), "wasmcloud/http");
+        assert_eq!(normalize_reference("wasmcloud/http")?.tag(), Some("latest"));
+        let err = normalize_reference("wasmcloud/http:").expect_err("empty tag is invalid");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::InvalidReference(_))
+        ));
+
+        assert_eq!(
+            cache_key("ghcr.io/wasmCloud/http:0.2.0"),
+            "ghcr_io_wasmcloud_http_0_2_0"
+        );
+        assert_eq!(
+            CacheIndex::new("cache", "ghcr.io/wasmcloud/http:0.2.0").key,
+            cache_key("ghcr.io/wasmcloud/http:0.2.0")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_qualify_reference() {
+        for (oci_ref, qualified) in [
+            (
+                "myteam/mycomponent:1.0",
+                "registry.example.com/myteam/mycomponent:1.0",
+            ),
+            ("mycomponent:1.0", "registry.example.com/mycomponent:1.0"),
+            (
+                "ghcr.io/wasmcloud/http:0.1.0",
+                "ghcr.io/wasmcloud/http:0.1.0",
+            ),
+            ("localhost:5000/http:0.1.0", "localhost:5000/http:0.1.0"),
+            ("localhost/http:0.1.0", "localhost/http:0.1.0"),
+            (
+                "docker.io/wasmcloud/http:0.1.0",
+                "docker.io/wasmcloud/http:0.1.0",
+            ),
+        ] {
+            assert_eq!(
+                qualify_reference(oci_ref, "registry.example.com/"),
+                qualified
+            );
+        }
+        let fetcher = OciFetcher::default().with_default_registry("Registry.example.com");
+        assert_eq!(
+            fetcher.normalize("MyTeam/http@sha256:abc"),
+            "registry.example.com/myteam/http@sha256:abc"
+        );
+        assert_eq!(
+            OciFetcher::default().normalize("MyTeam/http:0.1.0"),
+            "myteam/http:0.1.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validate() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let fetcher = OciFetcher::default().with_cache_dir(dir.path().join("cache"));
+
+        let err = fetcher
+            .validate("registry/component:latest")
+            .await
+            .expect_err("latest tag should be disallowed");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::LatestDisallowed)
+        ));
+        let err = fetcher
+            .validate("127.0.0.1:1/wasmcloud/http:0.2.0")
+            .await
+            .expect_err("unreachable registry should fail validation");
+        assert!(OciFetchError::find(&err).is_some_and(OciFetchError::is_retryable));
+        assert!(fs::metadata(dir.path().join("cache")).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_usage() -> anyhow::Result<()> {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<CacheUsage>>);
+
+        impl FetchMetrics for Recorder {
+            fn record_fetch(&self, _: &str, _: FetchOutcome, _: u64, _: Duration) {}
+
+            fn record_cache_usage(&self, usage: CacheUsage) {
+                self.0
+                    .lock()
+                    .expect("lock should not be poisoned")
+                    .push(usage);
+            }
+        }
+
+        impl Recorder {
+            /// Waits for usage to be reported as `bytes` in `entries` entries
+            async fn wait_for(&self, bytes: u64, entries: usize) -> anyhow::Result<()> {
+                tokio::time::timeout(Duration::from_secs(10), async {
+                    loop {
+                        let reported = self
+                            .0
+                            .lock()
+                            .expect("lock should not be poisoned")
+                            .iter()
+                            .any(|usage| {
+                                usage
+                                    == &CacheUsage {
+                                        bytes,
+                                        entries,
+                                        limit: Some(150),
+                                    }
+                            });
+                        if reported {
+                            return;
+                        }
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                    }
+                })
+                .await
+                .with_context(|| {
+                    format!("usage of {bytes} bytes in {entries} entries not reported")
+                })
+            }
+        }
+
+        let dir = tempfile::tempdir()?;
+        let recorder = Arc::new(Recorder::default());
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path().join("cache"))
+            .with_max_cache_bytes(150)
+            .with_metrics(recorder.clone());
+        let zeros = dir.path().join("zeros");
+        let ones = dir.path().join("ones");
+        fs::write(&zeros, [0; 100]).await?;
+        fs::write(&ones, [1; 100]).await?;
+
+        fetcher.import("registry/first:0.1.0", &zeros).await?;
+        recorder.wait_for(100, 1).await?;
+        // Shares its blob with the first entry, which is only counted once
+        fetcher.import("registry/second:0.1.0", &zeros).await?;
+        recorder.wait_for(100, 2).await?;
+        // Takes the cache over its limit, so the least recently used entries are pruned
+        fetcher.import("registry/third:0.1.0", &ones).await?;
+        recorder.wait_for(200, 3).await?;
+        recorder.wait_for(100, 1).await?;
+        assert!(fs::metadata(
+            CacheIndex::new(dir.path().join("cache"), "registry/third:0.1.0").blob_file()
+        )
+        .await
+        .is_ok());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let component = dir.path().join("component.wasm");
+        fs::write(&component, b"\0asm\x01\0\0\0").await?;
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path().join("cache"))
+            .with_pull_policy(PullPolicy::Never);
+        let img = "registry/imported:0.1.0";
+        let digest = fetcher.import(img, &component).await?;
+        assert_eq!(digest, sha256_digest(b"\0asm\x01\0\0\0"));
+        let index = CacheIndex::new(dir.path().join("cache"), img);
+        assert_eq!(fs::read_to_string(index.blob_file()).await?, digest);
+        assert_eq!(
+            fs::read_to_string(index.media_types_file()).await?,
+            WASM_LAYER_MEDIA_TYPE
+        );
+        // The manifest digest is unknown, so revalidation would verify the content instead
+        assert!(fs::metadata(index.digest_file()).await.is_err());
+        assert_eq!(fetcher.fetch_component(img).await?, b"\0asm\x01\0\0\0");
+        // Imported provider archives are not served as components
+        let provider = dir.path().join("provider.par.gz");
+        fs::write(&provider, b"provider").await?;
+        fetcher.import("registry/provider:0.1.0", &provider).await?;
+        assert!(fetcher
+            .fetch_component("registry/provider:0.1.0")
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_cache() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let component = dir.path().join("component.wasm");
+        fs::write(&component, b"\0asm\x01\0\0\0").await?;
+        let pinned = format!("registry/pinned@{}", sha256_digest(b"manifest"));
+        let source = OciFetcher::default().with_cache_dir(dir.path().join("source"));
+        source.import("registry/imported:0.1.0", &component).await?;
+        source.import(&pinned, &component).await?;
+
+        let archive = dir.path().join("cache.tar");
+        assert_eq!(
+            source
+                .export_cache(fs::File::create(&archive).await?)
+                .await?,
+            2
+        );
+
+        let target = OciFetcher::default()
+            .with_cache_dir(dir.path().join("target"))
+            .with_pull_policy(PullPolicy::Never);
+        assert_eq!(
+            target.import_cache(fs::File::open(&archive).await?).await?,
+            2
+        );
+        assert_eq!(
+            target.fetch_component("registry/imported:0.1.0").await?,
+            b"\0asm\x01\0\0\0"
+        );
+        let index = CacheIndex::new(dir.path().join("target"), &pinned);
+        assert_eq!(
+            fs::read_to_string(index.digest_file()).await?,
+            sha256_digest(b"manifest")
+        );
+
+        // Blobs that do not match their digest are rejected
+        let mut tampered = tokio_tar::Builder::new(Vec::new());
+        let mut header = tokio_tar::Header::new_gnu();
+        header.set_size(8);
+        header.set_cksum();
+        tampered
+            .append_data(
+                &mut header,
+                blob_path("", &sha256_digest(b"\0asm\x01\0\0\0")),
+                b"tampered".as_slice(),
+            )
+            .await?;
+        let tampered = tampered.into_inner().await?;
+        assert!(target
+            .import_cache(std::io::Cursor::new(tampered))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_list_cache() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let component = dir.path().join("component.wasm");
+        fs::write(&component, b"\0asm\x01\0\0\0").await?;
+        let pinned = format!("ghcr.io/wasmcloud/http@{}", sha256_digest(b"manifest"));
+        let fetcher = OciFetcher::default().with_cache_dir(dir.path().join("cache"));
+        fetcher.import("wasmcloud/http:0.2.0", &component).await?;
+        fetcher.import(&pinned, &component).await?;
+        // Entries cached by previous versions have no reference or metadata sidecars
+        let legacy = CacheIndex::new(dir.path().join("cache"), "ghcr.io/wasmcloud/legacy:0.1.0");
+        fetcher
+            .import("ghcr.io/wasmcloud/legacy:0.1.0", &component)
+            .await?;
+        fs::remove_file(legacy.reference_file()).await?;
+        fs::remove_file(legacy.metadata_file()).await?;
+
+        let entries = fetcher.list_cache().await?;
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.key.as_str(),
+                    entry.reference.as_deref(),
+                    entry.digest.as_deref(),
+                    entry.size,
+                )
+            })
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (
+                    cache_key(&pinned).as_str(),
+                    Some(pinned.as_str()),
+                    Some(sha256_digest(b"manifest").as_str()),
+                    8
+                ),
+                (legacy.key.as_str(), None, None, 8),
+                (
+                    cache_key("wasmcloud/http:0.2.0").as_str(),
+                    Some("docker.io/wasmcloud/http:0.2.0"),
+                    None,
+                    8
+                ),
+            ]
+        );
+        let metadata = entries[0]
+            .metadata
+            .clone()
+            .context("metadata should be recorded")?;
+        assert_eq!(metadata.version, CACHE_METADATA_VERSION);
+        assert_eq!(metadata.digest, Some(sha256_digest(b"manifest")));
+        assert_eq!(metadata.media_type, WASM_LAYER_MEDIA_TYPE);
+        assert_eq!(metadata.layers, 1);
+        assert_eq!(metadata.size, 8);
+        assert_eq!(metadata.registry, "ghcr.io");
+        assert_eq!(entries[1].metadata, None);
+        assert_eq!(
+            entries[2].metadata.as_ref().map(|m| m.registry.as_str()),
+            Some("docker.io")
+        );
+
+        // Unreadable metadata is ignored
+        fs::write(
+            CacheIndex::new(dir.path().join("cache"), &pinned).metadata_file(),
+            b"{",
+        )
+        .await?;
+        assert_eq!(fetcher.list_cache().await?[0].metadata, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_archive_path() {
+        let digest = sha256_digest(b"content");
+        assert_eq!(
+            cache_archive_path(&blob_path("", &digest)),
+            Some(CacheArchivePath::Blob(digest))
+        );
+        assert_eq!(
+            cache_archive_path(Path::new("registry_component_0.1.0.digest")),
+            Some(CacheArchivePath::Index {
+                key: "registry_component_0.1.0".to_string(),
+                extension: "digest".to_string(),
+            })
+        );
+        assert_eq!(cache_archive_path(Path::new("../escape.blob")), None);
+        assert_eq!(cache_archive_path(Path::new("/etc/passwd")), None);
+        assert_eq!(cache_archive_path(Path::new("blobs/sha256/..")), None);
+        assert_eq!(cache_archive_path(Path::new("component.exe")), None);
+    }
 
-        let imgdata = c
-            .pull(&img, &self.auth, accepted_media_types)
+    #[tokio::test]
+    async fn test_required_provider_issuers() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let issuer = nkeys::KeyPair::new_account();
+        let mut par = provider_archive::ProviderArchive::new("provider", "wasmCloud", None, None);
+        par.add_library(
+            &format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+            b"provider",
+        )
+        .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let path = dir.path().join("provider.par");
+        par.write(&path, &issuer, &nkeys::KeyPair::new_service(), false)
             .await
-            .context("failed to fetch OCI bytes")?;
-        // As a client, we should reject invalid OCI artifacts
-        if imgdata
-            .manifest
-            .as_ref()
-            .map(|m| m.media_type.as_deref().unwrap_or_default() == WASM_MANIFEST_MEDIA_TYPE)
-            .unwrap_or(false)
-            && imgdata.layers.len() > 1
-        {
-            bail!(
-                "Found invalid OCI wasm artifact, expected single layer, found {} layers",
-                imgdata.layers.len()
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let img = "registry/provider:0.1.0";
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path().join("cache"))
+            .with_pull_policy(PullPolicy::Never);
+        fetcher.import(img, &path).await?;
+        fs::write(
+            CacheIndex::new(dir.path().join("cache"), img).digest_file(),
+            "sha256:manifest",
+        )
+        .await?;
+
+        let host_id = ulid::Ulid::new().to_string();
+        let untrusted = fetcher
+            .clone()
+            .with_required_provider_issuers(&[nkeys::KeyPair::new_account().public_key()])
+            .fetch_provider(img, &host_id)
+            .await
+            .expect_err("provider from untrusted issuer was fetched");
+        assert!(matches!(
+            untrusted.downcast_ref(),
+            Some(OciFetchError::UntrustedIssuer { issuer: i }) if *i == issuer.public_key()
+        ));
+        let exe = crate::par::cache_path(&host_id, img);
+        assert!(fs::metadata(&exe).await.is_err());
+
+        let FetchedProvider {
+            path: trusted,
+            claims,
+            digest,
+        } = fetcher
+            .with_required_provider_issuers(&[issuer.public_key()])
+            .fetch_provider_with_metadata(img, &host_id)
+            .await?;
+        assert_eq!(trusted, exe);
+        assert_eq!(
+            claims.map(|claims| claims.claims.issuer),
+            Some(issuer.public_key())
+        );
+        assert_eq!(digest.as_deref(), Some("sha256:manifest"));
+        fs::remove_file(&exe).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_manifest_expires() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let index = CacheIndex::new(dir.path(), "ghcr.io/wasmcloud/http:0.1.0");
+        let ttl = Duration::from_secs(60);
+        assert!(index.fresh_manifest(ttl).await.is_none());
+
+        let manifest = OciManifest::Image(manifest(
+            WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+            &[WASM_LAYER_MEDIA_TYPE],
+        ));
+        index.write_manifest(&manifest).await?;
+        assert!(matches!(
+            index.fresh_manifest(ttl).await,
+            Some(OciManifest::Image(m)) if m.layers.len() == 1
+        ));
+
+        std::fs::File::options()
+            .write(true)
+            .open(index.manifest_file())?
+            .set_modified(SystemTime::now() - Duration::from_secs(120))?;
+        assert!(index.fresh_manifest(ttl).await.is_none());
+        Ok(())
+    }
+
+    async fn store(dir: &Path, img: &str, content: &[u8]) -> anyhow::Result<CacheIndex> {
+        let index = CacheIndex::new(dir, img);
+        let (_, digest) = write_blob(dir, content, None).await?;
+        fs::write(index.blob_file(), digest).await?;
+        fs::write(index.digest_file(), "sha256:manifest").await?;
+        Ok(index)
+    }
+
+    #[tokio::test]
+    async fn test_prune_cache() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let old = store(dir.path(), "registry/old:0.1.0", &[0; 100]).await?;
+        // Shares its blob with `old`, so evicting `old` alone must not remove the blob
+        let shared = store(dir.path(), "registry/shared:0.1.0", &[0; 100]).await?;
+        let new = store(dir.path(), "registry/new:0.1.0", &[1; 50]).await?;
+        for (index, age) in [(&old, 30), (&shared, 20), (&new, 10)] {
+            let file = std::fs::File::open(index.blob_file())?;
+            file.set_times(
+                std::fs::FileTimes::new()
+                    .set_accessed(SystemTime::now() - Duration::from_secs(age))
+                    .set_modified(SystemTime::now() - Duration::from_secs(age)),
+            )?;
+        }
+
+        let evicted = prune_oci_cache(
+            dir.path(),
+            CachePrunePolicy::OlderThan(Duration::from_secs(25)),
+        )
+        .await?;
+        assert_eq!(
+            evicted,
+            [EvictedEntry {
+                source: EvictionSource::Disk,
+                key: old.key.clone(),
+                digest: Some("sha256:manifest".into()),
+                bytes: 0,
+            }]
+        );
+        assert!(old.blob().await.is_none());
+        assert!(shared.blob().await.is_some());
+        assert!(new.blob().await.is_some());
+
+        let evicted = prune_oci_cache(dir.path(), CachePrunePolicy::MaxSize(60)).await?;
+        assert_eq!(
+            CachePruneStats::from_evicted(&evicted),
+            CachePruneStats {
+                entries: 1,
+                bytes: 100
+            }
+        );
+        assert!(shared.blob().await.is_none());
+        assert!(new.blob().await.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_prune_cache_skips_in_flight_entries() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let index = store(dir.path(), "registry/busy:0.1.0", b"busy").await?;
+        let guard = InFlightGuard::new(index.legacy_file());
+        let evicted =
+            prune_oci_cache(dir.path(), CachePrunePolicy::OlderThan(Duration::ZERO)).await?;
+        assert!(evicted.is_empty());
+        assert!(index.blob().await.is_some());
+
+        drop(guard);
+        let evicted =
+            prune_oci_cache(dir.path(), CachePrunePolicy::OlderThan(Duration::ZERO)).await?;
+        assert_eq!(evicted.len(), 1);
+        assert!(index.blob().await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_evictions_are_reported() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        store(dir.path(), "registry/old:0.1.0", b"old").await?;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path())
+            .with_eviction_callback(Arc::new(move |entry| {
+                let _ = tx.send(entry);
+            }));
+        let stats = fetcher
+            .prune_cache(CachePrunePolicy::OlderThan(Duration::ZERO))
+            .await?;
+        assert_eq!(
+            stats,
+            CachePruneStats {
+                entries: 1,
+                bytes: 3
+            }
+        );
+        let entry = rx.recv().await.expect("eviction should be reported");
+        assert_eq!(entry.source, EvictionSource::Disk);
+        assert_eq!(entry.bytes, 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_warm_reports_each_reference() {
+        let fetcher = OciFetcher::default();
+        let results = fetcher
+            .warm(&["registry/latest:latest", "not a valid reference"])
+            .await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "registry/latest:latest");
+        assert!(matches!(
+            results[0].1.as_ref().map_err(OciFetchError::find),
+            Err(Some(OciFetchError::LatestDisallowed))
+        ));
+        assert_eq!(results[1].0, "not a valid reference");
+        assert!(matches!(
+            results[1].1.as_ref().map_err(OciFetchError::find),
+            Err(Some(OciFetchError::InvalidReference(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_components() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = Arc::new(FsCacheStore::new(dir.path()));
+        for (img, content) in [
+            ("registry/a:0.1.0", &b"a"[..]),
+            ("registry/b:0.1.0", &b"b"[..]),
+        ] {
+            store
+                .put(
+                    &cache_key(img),
+                    Bytes::from_static(content),
+                    "sha256:manifest",
+                )
+                .await?;
+        }
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path())
+            .with_cache_store(store)
+            .with_pull_policy(PullPolicy::Never);
+        let results = fetcher
+            .fetch_components(
+                &[
+                    "registry/a:0.1.0",
+                    "registry/missing:0.1.0",
+                    "registry/b:0.1.0",
+                    "registry/a:0.1.0",
+                ],
+                2,
             )
+            .await;
+        let refs: Vec<_> = results.iter().map(|(img, _)| img.as_str()).collect();
+        assert_eq!(
+            refs,
+            [
+                "registry/a:0.1.0",
+                "registry/missing:0.1.0",
+                "registry/b:0.1.0",
+                "registry/a:0.1.0"
+            ]
+        );
+        assert_eq!(results[0].1.as_deref().ok(), Some(&b"a"[..]));
+        assert!(matches!(
+            results[1].1.as_ref().map_err(OciFetchError::find),
+            Err(Some(OciFetchError::NotCached))
+        ));
+        assert_eq!(results[2].1.as_deref().ok(), Some(&b"b"[..]));
+        assert_eq!(results[3].1.as_deref().ok(), Some(&b"a"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_allowed_digests() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = Arc::new(FsCacheStore::new(dir.path()));
+        for (img, digest) in [
+            ("registry/vetted:0.1.0", "sha256:vetted"),
+            ("registry/repointed:0.1.0", "sha256:unvetted"),
+        ] {
+            store
+                .put(&cache_key(img), Bytes::from_static(b"component"), digest)
+                .await?;
         }
-        // Update the OCI artifact cache if specified
-        if let OciArtifactCacheUpdate::Update = cache {
-            cache_oci_image(imgdata, &cache_file, digest_file)
-                .await
-                .context("failed to cache OCI bytes")?;
+        let fetcher = OciFetcher::default()
+            .with_cache_dir(dir.path())
+            .with_cache_store(store)
+            .with_pull_policy(PullPolicy::Never)
+            .with_allowed_digests(["sha256:vetted"]);
+        assert_eq!(
+            fetcher.fetch_component("registry/vetted:0.1.0").await?,
+            b"component"
+        );
+        let err = fetcher
+            .fetch_component("registry/repointed:0.1.0")
+            .await
+            .expect_err("digest should not be allowed");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::DigestNotAllowed { digest }) if digest == "sha256:unvetted"
+        ));
+
+        // References pinned to a digest are checked before the in-memory cache is consulted
+        let fetcher = OciFetcher::default()
+            .with_memory_cache(1024)
+            .with_allowed_digests(["sha256:vetted"]);
+        let err = fetcher
+            .fetch_component(format!("registry/pinned@{}", sha256_digest(b"manifest")))
+            .await
+            .expect_err("digest should not be allowed");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::DigestNotAllowed { .. })
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_dir_precedence() -> anyhow::Result<()> {
+        let configured = Path::new("/configured");
+        let from_env = || Some(OsString::from("/env"));
+        assert_eq!(resolve_cache_dir(Some(configured), from_env()), configured);
+        assert_eq!(resolve_cache_dir(None, from_env()), Path::new("/env"));
+        let default = temp_dir().join("wasmcloud_ocicache");
+        assert_eq!(resolve_cache_dir(None, Some(OsString::new())), default);
+        assert_eq!(resolve_cache_dir(None, None), default);
+
+        let dir = tempfile::tempdir()?;
+        let configured = dir.path().join("cache");
+        assert_eq!(
+            OciFetcher::default()
+                .with_cache_dir(&configured)
+                .cache_dir()
+                .await?,
+            configured
+        );
+        assert!(configured.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn test_registry_errors_are_classified() {
+        let envelope = |code| oci_client::errors::OciEnvelope {
+            errors: vec![oci_client::errors::OciError {
+                code,
+                message: String::new(),
+                detail: serde_json::Value::Null,
+            }],
+        };
+        let registry_error = |code| OciDistributionError::RegistryError {
+            envelope: envelope(code),
+            url: "https://registry/v2/foo/manifests/0.1.0".into(),
+        };
+        assert!(matches!(
+            OciFetchError::from(registry_error(OciErrorCode::Denied)),
+            OciFetchError::Unauthorized(_)
+        ));
+        assert!(matches!(
+            OciFetchError::from(registry_error(OciErrorCode::ManifestUnknown)),
+            OciFetchError::NotFound(_)
+        ));
+        assert!(matches!(
+            OciFetchError::from(OciDistributionError::UnauthorizedError { url: String::new() }),
+            OciFetchError::Unauthorized(_)
+        ));
+        let server_error = OciFetchError::from(OciDistributionError::ServerError {
+            code: 503,
+            url: String::new(),
+            message: String::new(),
+        });
+        assert!(server_error.is_retryable());
+        assert!(!OciFetchError::LatestDisallowed.is_retryable());
+        let rate_limited = OciFetchError::from(registry_error(OciErrorCode::Toomanyrequests));
+        assert!(matches!(
+            rate_limited,
+            OciFetchError::RateLimited { retry_after: None }
+        ));
+        assert!(rate_limited.is_retryable());
+    }
+
+    /// Serves HTTP requests on `listener`, answering each with the response `respond` returns for
+    /// the request head. Connections answered with an empty response are held open without ever
+    /// being responded to
+    fn serve(
+        listener: tokio::net::TcpListener,
+        respond: impl Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    ) {
+        let respond = Arc::new(respond);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let respond = Arc::clone(&respond);
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0; 1024];
+                    while !head.ends_with(b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let res = respond(&String::from_utf8_lossy(&head));
+                    if res.is_empty() {
+                        std::future::pending::<()>().await;
+                    }
+                    let _ = stream.write_all(&res).await;
+                });
+            }
+        });
+    }
+
+    fn response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+        let mut res = format!(
+            "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n",
+            body.len()
+        );
+        for (name, value) in headers {
+            res.push_str(&format!("{name}: {value}\r\n"));
         }
+        res.push_str("\r\n");
+        let mut res = res.into_bytes();
+        res.extend_from_slice(body);
+        res
+    }
 
-        Ok((cache_file, CacheResult::Miss))
+    /// Returns a response carrying an OCI distribution error with `code`
+    fn error_response(status: &str, code: &str) -> Vec<u8> {
+        let body = serde_json::json!({
+            "errors": [{ "code": code, "message": code }],
+        });
+        response(status, &[], body.to_string().as_bytes())
     }
 
-    /// Fetch component from OCI
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if either fetching fails or reading the fetched OCI path fails
-    pub async fn fetch_component(&self, oci_ref: impl AsRef<str>) -> anyhow::Result<Vec<u8>> {
-        let (path, _) = self
+    /// Returns the manifest of a Wasm component with a single layer of `content`, declared to be
+    /// `size` bytes large
+    fn wasm_manifest_sized(content: &[u8], size: usize) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": oci_client::manifest::OCI_IMAGE_MEDIA_TYPE,
+            "config": {
+                "mediaType": WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+                "digest": sha256_digest(b"{}"),
+                "size": 2,
+            },
+            "layers": [{
+                "mediaType": WASM_LAYER_MEDIA_TYPE,
+                "digest": sha256_digest(content),
+                "size": size,
+            }],
+        }))
+        .expect("manifest should serialize")
+    }
+
+    /// Returns the manifest of a Wasm component with a single layer of `content`
+    fn wasm_manifest(content: &[u8]) -> Vec<u8> {
+        wasm_manifest_sized(content, content.len())
+    }
+
+    /// Returns a fetcher for [`TestRegistry`]s, which are served anonymously over HTTP
+    fn test_fetcher() -> OciFetcher {
+        OciFetcher {
+            auth: oci_client::secrets::RegistryAuth::Anonymous,
+            allow_insecure: true,
+            ..Default::default()
+        }
+    }
+
+    /// A fake OCI registry serving fixed manifests and blobs, see [`TestRegistry::serve`]
+    #[derive(Default)]
+    struct TestRegistry {
+        /// Manifests and their media types by reference, e.g. `wasmcloud/http:0.1.0`
+        manifests: HashMap<String, (&'static str, Vec<u8>)>,
+        /// Blobs by digest
+        blobs: HashMap<String, Vec<u8>>,
+    }
+
+    impl TestRegistry {
+        /// Serves `manifest` under `reference` and under its digest in the same repository
+        fn with_manifest(
+            mut self,
+            reference: &str,
+            media_type: &'static str,
+            manifest: Vec<u8>,
+        ) -> Self {
+            let (repository, _) = reference
+                .rsplit_once(['@', ':'])
+                .expect("reference should have a tag or digest");
+            self.manifests.insert(
+                format!("{repository}@{}", sha256_digest(&manifest)),
+                (media_type, manifest.clone()),
+            );
+            self.manifests
+                .insert(reference.to_string(), (media_type, manifest));
+            self
+        }
+
+        fn with_blob(mut self, blob: &[u8]) -> Self {
+            self.blobs.insert(sha256_digest(blob), blob.to_vec());
+            self
+        }
+
+        /// Serves a Wasm component with a single layer of `content` under `reference`
+        fn with_component(self, reference: &str, content: &[u8]) -> Self {
+            self.with_manifest(
+                reference,
+                oci_client::manifest::OCI_IMAGE_MEDIA_TYPE,
+                wasm_manifest(content),
+            )
+            .with_blob(content)
+        }
+
+        async fn serve(self) -> anyhow::Result<RunningRegistry> {
+            self.serve_with(|_| None).await
+        }
+
+        /// Serves the registry on a local port, answering requests `respond` returns a response
+        /// for with that response instead
+        async fn serve_with(
+            self,
+            respond: impl Fn(&str) -> Option<Vec<u8>> + Send + Sync + 'static,
+        ) -> anyhow::Result<RunningRegistry> {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+            let addr = listener.local_addr()?;
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            serve(listener, {
+                let requests = Arc::clone(&requests);
+                move |head| {
+                    let request = head.split(' ').take(2).collect::<Vec<_>>().join(" ");
+                    requests
+                        .lock()
+                        .expect("lock should not be poisoned")
+                        .push(request.clone());
+                    if let Some(res) = respond(head) {
+                        return res;
+                    }
+                    let (method, path) = request.split_once(' ').unwrap_or_default();
+                    let path = path.strip_prefix("/v2/").unwrap_or_default();
+                    if path.is_empty() {
+                        response("200 OK", &[], b"")
+                    } else if let Some((repository, reference)) = path.rsplit_once("/manifests/") {
+                        let separator = if reference.contains(':') { '@' } else { ':' };
+                        let Some((media_type, manifest)) = self
+                            .manifests
+                            .get(&format!("{repository}{separator}{reference}"))
+                        else {
+                            return error_response("404 Not Found", "MANIFEST_UNKNOWN");
+                        };
+                        let body = if method == "HEAD" { &[][..] } else { manifest };
+                        response(
+                            "200 OK",
+                            &[
+                                ("content-type", media_type),
+                                ("docker-content-digest", &sha256_digest(manifest)),
+                            ],
+                            body,
+                        )
+                    } else if let Some(blob) = path
+                        .rsplit_once("/blobs/")
+                        .and_then(|(_, digest)| self.blobs.get(digest))
+                    {
+                        response("200 OK", &[], blob)
+                    } else {
+                        error_response("404 Not Found", "BLOB_UNKNOWN")
+                    }
+                }
+            });
+            Ok(RunningRegistry { addr, requests })
+        }
+    }
+
+    /// A [`TestRegistry`] being served
+    struct RunningRegistry {
+        addr: std::net::SocketAddr,
+        /// Method and path of each request received, e.g. `GET /v2/wasmcloud/http/blobs/sha256:...`
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RunningRegistry {
+        /// Returns `reference` qualified with the address of the registry
+        fn reference(&self, reference: &str) -> String {
+            format!("{}/{reference}", self.addr)
+        }
+
+        /// Returns the requests received since the last call, formatted as their method and path
+        fn take_requests(&self) -> Vec<String> {
+            std::mem::take(&mut self.requests.lock().expect("lock should not be poisoned"))
+        }
+
+        /// Returns the number of requests received since the last call to
+        /// [`RunningRegistry::take_requests`] whose path contains `pattern`
+        fn count_requests(&self, pattern: &str) -> usize {
+            self.requests
+                .lock()
+                .expect("lock should not be poisoned")
+                .iter()
+                .filter(|request| request.contains(pattern))
+                .count()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() -> anyhow::Result<()> {
+        // Accept connections, but never respond
+        let registry = TestRegistry::default()
+            .serve_with(|_| Some(Vec::new()))
+            .await?;
+
+        let fetcher = test_fetcher().with_read_timeout(Duration::from_millis(100));
+        let err = tokio::time::timeout(
+            Duration::from_secs(10),
+            fetcher.fetch_component(registry.reference("wasmcloud/http:0.1.0")),
+        )
+        .await
+        .context("fetch should time out before the test does")?
+        .expect_err("fetch from a stalled registry should fail");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Network(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_revalidation_retries() -> anyhow::Result<()> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Number of manifest requests to fail before responding successfully again
+        let failures = Arc::new(AtomicUsize::new(0));
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .with_component("wasmcloud/http:latest", b"component")
+            .serve_with({
+                let failures = Arc::clone(&failures);
+                move |head| {
+                    (head.contains("/manifests/")
+                        && failures
+                            .try_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                            .is_ok())
+                    .then(|| response("503 Service Unavailable", &[], b""))
+                }
+            })
+            .await?;
+
+        let fetcher = OciFetcher {
+            allow_latest: true,
+            ..test_fetcher()
+        }
+        .with_manifest_retries(2, Duration::from_millis(1));
+        let dir = tempfile::tempdir()?;
+        let fetch = |tag: &str| {
+            let fetcher = fetcher.clone();
+            let dir = dir.path().to_path_buf();
+            let img = registry.reference(&format!("wasmcloud/http:{tag}"));
+            async move {
+                fetcher
+                    .fetch_path_detailed(
+                        dir,
+                        img,
+                        fetcher.media_types(ArtifactKind::Component),
+                        OciArtifactCacheUpdate::Update,
+                    )
+                    .await
+            }
+        };
+        for tag in ["0.1.0", "latest"] {
+            assert!(!fetch(tag).await?.from_cache);
+        }
+
+        // Transient failures are retried
+        failures.store(2, Ordering::Relaxed);
+        registry.take_requests();
+        assert!(fetch("0.1.0").await?.from_cache);
+        assert_eq!(registry.count_requests("/manifests/"), 3);
+
+        // The cached copy is served once retries are exhausted, unless the tag is `latest`
+        failures.store(usize::MAX, Ordering::Relaxed);
+        let fetched = fetch("0.1.0").await?;
+        assert!(fetched.from_cache);
+        assert_eq!(
+            fetched.digest,
+            Some(sha256_digest(&wasm_manifest(b"component")))
+        );
+        assert!(fetch("latest").await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_events() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve()
+            .await?;
+
+        let dir = tempfile::tempdir()?;
+        let fetcher = test_fetcher().with_cache_dir(dir.path());
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        let mut events = fetcher.subscribe();
+        let mut received = |img: &str| {
+            let mut received = Vec::new();
+            while let Ok(FetchEvent { reference, kind }) = events.try_recv() {
+                assert_eq!(reference, img);
+                received.push(match kind {
+                    FetchEventKind::FetchCompleted {
+                        bytes, downloaded, ..
+                    } => format!("completed {bytes} {downloaded}"),
+                    FetchEventKind::FetchFailed { .. } => "failed".to_string(),
+                    kind => format!("{kind:?}"),
+                });
+            }
+            received
+        };
+        let manifest_digest = sha256_digest(&wasm_manifest(b"component"));
+        fetcher.fetch_component(&img).await?;
+        assert_eq!(
+            received(&img),
+            [
+                "FetchStarted".to_string(),
+                format!("ManifestResolved {{ digest: {manifest_digest:?} }}"),
+                format!(
+                    "LayerProgress {{ digest: {:?}, downloaded: 9, total: 9 }}",
+                    sha256_digest(b"component")
+                ),
+                "completed 9 9".to_string(),
+            ]
+        );
+        fetcher.fetch_component(&img).await?;
+        assert_eq!(
+            received(&img),
+            [
+                "FetchStarted".to_string(),
+                format!("ManifestResolved {{ digest: {manifest_digest:?} }}"),
+                "CacheHit".to_string(),
+                "completed 9 0".to_string(),
+            ]
+        );
+        let missing = registry.reference("wasmcloud/missing:0.1.0");
+        assert!(fetcher.fetch_component(&missing).await.is_err());
+        assert_eq!(received(&missing), ["FetchStarted", "failed"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_exists() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve_with(|head| {
+                head.contains("/private/")
+                    .then(|| error_response("403 Forbidden", "DENIED"))
+            })
+            .await?;
+
+        let fetcher = test_fetcher();
+        assert!(
+            fetcher
+                .exists(registry.reference("wasmcloud/http:0.1.0"))
+                .await?
+        );
+        assert!(
+            !fetcher
+                .exists(registry.reference("wasmcloud/missing:0.1.0"))
+                .await?
+        );
+        let err = fetcher
+            .exists(registry.reference("wasmcloud/private:0.1.0"))
+            .await
+            .expect_err("denied access should be an error");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Unauthorized(_))
+        ));
+        // The manifest of an existing artifact is not downloaded
+        assert!(registry
+            .take_requests()
+            .iter()
+            .filter(|request| request.contains("/wasmcloud/http/manifests/"))
+            .all(|request| request.starts_with("HEAD ")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_image_index_platform_selection() -> anyhow::Result<()> {
+        let amd64 = wasm_manifest(b"amd64");
+        let arm64 = wasm_manifest(b"arm64");
+        let entries: Vec<_> = [(&amd64, "amd64"), (&arm64, "arm64")]
+            .into_iter()
+            .map(|(manifest, architecture)| {
+                serde_json::json!({
+                    "mediaType": oci_client::manifest::OCI_IMAGE_MEDIA_TYPE,
+                    "digest": sha256_digest(manifest),
+                    "size": manifest.len(),
+                    "platform": { "os": "linux", "architecture": architecture },
+                })
+            })
+            .collect();
+        let index = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": oci_client::manifest::OCI_IMAGE_INDEX_MEDIA_TYPE,
+            "manifests": entries,
+        }))?;
+        let registry = TestRegistry::default()
+            .with_manifest(
+                "wasmcloud/http:0.1.0",
+                oci_client::manifest::OCI_IMAGE_INDEX_MEDIA_TYPE,
+                index,
+            )
+            .with_component("wasmcloud/http:amd64", b"amd64")
+            .with_component("wasmcloud/http:arm64", b"arm64")
+            .serve()
+            .await?;
+
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        let content = test_fetcher()
+            .with_platform(Platform::new("linux", "arm64"))
+            .fetch_component(&img)
+            .await?;
+        assert_eq!(content, b"arm64");
+
+        let err = test_fetcher()
+            .with_platform(Platform::new("windows", "amd64"))
+            .fetch_component(&img)
+            .await
+            .expect_err("fetch without a manifest for the platform should fail");
+        let Some(OciFetchError::NoMatchingPlatform {
+            platform,
+            available,
+        }) = OciFetchError::find(&err)
+        else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(platform, "windows/amd64");
+        assert_eq!(available, &["linux/amd64", "linux/arm64"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_blob_redirects_are_followed_without_credentials() -> anyhow::Result<()> {
+        let storage = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let location = format!("http://{}/signed-blob", storage.local_addr()?);
+        let storage_authorized = Arc::new(Mutex::new(Vec::new()));
+        serve(storage, {
+            let authorized = Arc::clone(&storage_authorized);
+            move |head| {
+                authorized
+                    .lock()
+                    .expect("lock should not be poisoned")
+                    .push(head.to_lowercase().contains("\r\nauthorization:"));
+                response("200 OK", &[], b"component")
+            }
+        });
+        let registry_authorized = Arc::new(Mutex::new(Vec::new()));
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve_with({
+                let authorized = Arc::clone(&registry_authorized);
+                move |head| {
+                    let head = head.to_lowercase();
+                    if head.starts_with("get /v2/ ") {
+                        Some(response(
+                            "401 Unauthorized",
+                            &[("www-authenticate", "Basic realm=\"registry\"")],
+                            b"",
+                        ))
+                    } else if head.contains("/blobs/") {
+                        authorized
+                            .lock()
+                            .expect("lock should not be poisoned")
+                            .push(head.contains("\r\nauthorization:"));
+                        Some(response(
+                            "307 Temporary Redirect",
+                            &[("location", &location)],
+                            b"",
+                        ))
+                    } else {
+                        None
+                    }
+                }
+            })
+            .await?;
+
+        let fetcher = OciFetcher {
+            auth: oci_client::secrets::RegistryAuth::Basic("user".into(), "password".into()),
+            ..test_fetcher()
+        };
+        let dir = tempfile::tempdir()?;
+        let (path, _) = fetcher
             .fetch_path(
-                oci_cache_dir().await?,
-                oci_ref,
-                vec![WASM_MEDIA_TYPE, OCI_MEDIA_TYPE, WASM_LAYER_MEDIA_TYPE],
+                dir.path(),
+                registry.reference("wasmcloud/http:0.1.0"),
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await?;
+        assert_eq!(fs::read(path).await?, b"component");
+        assert_eq!(
+            *registry_authorized
+                .lock()
+                .expect("lock should not be poisoned"),
+            [true]
+        );
+        assert_eq!(
+            *storage_authorized
+                .lock()
+                .expect("lock should not be poisoned"),
+            [false]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_fall_back_to_anonymous() -> anyhow::Result<()> {
+        // The registry serves public artifacts, but rejects the credentials it asks for
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve_with(|head| {
+                let head = head.to_lowercase();
+                if head.starts_with("get /v2/ ") {
+                    Some(response(
+                        "401 Unauthorized",
+                        &[("www-authenticate", "Basic realm=\"registry\"")],
+                        b"",
+                    ))
+                } else {
+                    head.contains("\r\nauthorization:")
+                        .then(|| response("401 Unauthorized", &[], b""))
+                }
+            })
+            .await?;
+
+        let fetcher = OciFetcher {
+            auth: oci_client::secrets::RegistryAuth::Basic("user".into(), "expired".into()),
+            ..test_fetcher()
+        };
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        let dir = tempfile::tempdir()?;
+        let err = fetcher
+            .fetch_path(
+                dir.path(),
+                &img,
+                fetcher.media_types(ArtifactKind::Component),
                 OciArtifactCacheUpdate::Update,
             )
             .await
-            .context("failed to fetch OCI path")?;
-        fs::read(&path)
+            .expect_err("rejected credentials should fail the fetch");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Unauthorized(_))
+        ));
+
+        let fetcher = fetcher.with_anonymous_fallback(true);
+        let (path, _) = fetcher
+            .fetch_path(
+                dir.path(),
+                &img,
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await?;
+        assert_eq!(fs::read(path).await?, b"component");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_store_is_shared() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve()
+            .await?;
+
+        // Fetchers of separate hosts sharing a store only pull the component once
+        let dir = tempfile::tempdir()?;
+        let store = Arc::new(FsCacheStore::new(dir.path()));
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        for _ in 0..2 {
+            let cache_dir = tempfile::tempdir()?;
+            let fetcher = test_fetcher()
+                .with_cache_dir(cache_dir.path())
+                .with_cache_store(store.clone());
+            assert_eq!(fetcher.fetch_component(&img).await?, b"component");
+        }
+
+        // Artifacts fetched to a path are served from the store as well
+        let cache_dir = tempfile::tempdir()?;
+        let fetcher = test_fetcher().with_cache_store(store.clone());
+        let (path, cache) = fetcher
+            .fetch_path(
+                cache_dir.path(),
+                &img,
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+            .await?;
+        assert_eq!(cache, CacheResult::Hit);
+        assert_eq!(fs::read(path).await?, b"component");
+        assert_eq!(registry.count_requests("/blobs/"), 1);
+        let key = cache_key(&img);
+        assert_eq!(
+            store.digest(&key).await?,
+            Some(sha256_digest(&wasm_manifest(b"component")))
+        );
+        assert_eq!(store.get(&key).await?.as_deref(), Some(&b"component"[..]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_manifests_are_not_pulled_again() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve()
+            .await?;
+
+        let fetcher = test_fetcher();
+        let dir = tempfile::tempdir()?;
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        for _ in 0..2 {
+            fetcher
+                .fetch_path(
+                    dir.path(),
+                    &img,
+                    fetcher.media_types(ArtifactKind::Component),
+                    OciArtifactCacheUpdate::Update,
+                )
+                .await?;
+        }
+        // The second fetch only requests the manifest digest
+        let requests: Vec<_> = registry
+            .take_requests()
+            .iter()
+            .filter_map(|request| {
+                let (method, path) = request.split_once(' ')?;
+                let kind = path.rsplit('/').nth(1).filter(|kind| *kind != "v2")?;
+                Some(format!("{method} {kind}"))
+            })
+            .collect();
+        assert_eq!(requests, ["GET manifests", "GET blobs", "HEAD manifests"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_moved_tags_are_revalidated_with_a_single_pull() -> anyhow::Result<()> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let moved = Arc::new(AtomicBool::new(false));
+        let updated = wasm_manifest(b"updated");
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .with_blob(b"updated")
+            .serve_with({
+                let moved = Arc::clone(&moved);
+                move |head| {
+                    (moved.load(Ordering::Relaxed) && head.contains("/manifests/0.1.0 ")).then(
+                        || {
+                            let body = if head.starts_with("HEAD ") {
+                                &[][..]
+                            } else {
+                                &updated
+                            };
+                            response(
+                                "200 OK",
+                                &[
+                                    ("content-type", oci_client::manifest::OCI_IMAGE_MEDIA_TYPE),
+                                    ("docker-content-digest", &sha256_digest(&updated)),
+                                ],
+                                body,
+                            )
+                        },
+                    )
+                }
+            })
+            .await?;
+
+        let fetcher = test_fetcher();
+        let dir = tempfile::tempdir()?;
+        let img = registry.reference("wasmcloud/http:0.1.0");
+        let fetch = || {
+            fetcher.fetch_path(
+                dir.path(),
+                &img,
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
+        };
+        fetch().await?;
+        moved.store(true, Ordering::Relaxed);
+        registry.take_requests();
+        let (path, cache) = fetch().await?;
+        assert_eq!(cache, CacheResult::Miss);
+        assert_eq!(fs::read(path).await?, b"updated");
+        // The manifest resolved while revalidating is reused to pull the updated content
+        assert_eq!(registry.count_requests("/manifests/"), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_oversized_artifacts_are_rejected() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_manifest(
+                "wasmcloud/http:0.1.0",
+                oci_client::manifest::OCI_IMAGE_MEDIA_TYPE,
+                wasm_manifest_sized(b"component", 1 << 30),
+            )
+            .with_blob(b"component")
+            .serve()
+            .await?;
+
+        let fetcher = test_fetcher().with_max_artifact_bytes(50 << 20);
+        let dir = tempfile::tempdir()?;
+        let err = fetcher
+            .fetch_path(
+                dir.path(),
+                registry.reference("wasmcloud/http:0.1.0"),
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+            )
             .await
-            .with_context(|| format!("failed to read `{}`", path.display()))
+            .expect_err("oversized artifact should be rejected");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::TooLarge { size, max }) if *size == 1 << 30 && *max == 50 << 20
+        ));
+        assert_eq!(registry.count_requests("/blobs/"), 0);
+        Ok(())
     }
 
-    /// Fetch provider from OCI
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if either fetching fails or reading the fetched OCI path fails
-    pub async fn fetch_provider(
-        &self,
-        oci_ref: impl AsRef<str>,
-        host_id: impl AsRef<str>,
-    ) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
-        let (path, cache) = self
+    #[tokio::test]
+    async fn test_oversized_provider_archives_are_rejected() -> anyhow::Result<()> {
+        let mut manifest: serde_json::Value =
+            serde_json::from_slice(&wasm_manifest_sized(b"provider", 1 << 30))?;
+        manifest["layers"][0]["mediaType"] = PROVIDER_ARCHIVE_MEDIA_TYPE.into();
+        let registry = TestRegistry::default()
+            .with_manifest(
+                "wasmcloud/http-server:0.1.0",
+                oci_client::manifest::OCI_IMAGE_MEDIA_TYPE,
+                serde_json::to_vec(&manifest)?,
+            )
+            .with_blob(b"provider")
+            .serve()
+            .await?;
+
+        let dir = tempfile::tempdir()?;
+        let err = test_fetcher()
+            .with_cache_dir(dir.path())
+            .with_max_artifact_bytes(100 << 20)
+            .fetch_provider_with_metadata(registry.reference("wasmcloud/http-server:0.1.0"), "host")
+            .await
+            .expect_err("oversized provider archive should be rejected");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::TooLarge { size, max }) if *size == 1 << 30 && *max == 100 << 20
+        ));
+        assert_eq!(registry.count_requests("/blobs/"), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetches_can_be_cancelled() -> anyhow::Result<()> {
+        let cancel = CancellationToken::new();
+        // Blob downloads never complete, but cancel the fetch once started
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve_with({
+                let cancel = cancel.clone();
+                move |head| {
+                    head.contains("/blobs/").then(|| {
+                        cancel.cancel();
+                        Vec::new()
+                    })
+                }
+            })
+            .await?;
+
+        let fetcher = test_fetcher();
+        let dir = tempfile::tempdir()?;
+        let err = fetcher
+            .fetch_path_cancellable(
+                dir.path(),
+                registry.reference("wasmcloud/http:0.1.0"),
+                fetcher.media_types(ArtifactKind::Component),
+                OciArtifactCacheUpdate::Update,
+                cancel,
+            )
+            .await
+            .expect_err("fetch should be cancelled");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Cancelled)
+        ));
+        assert!(std::fs::read_dir(dir.path())?.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_temp_files_are_removed_unless_persisted() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let removed = dir.path().join("removed.tmp");
+        let persisted = dir.path().join("persisted.tmp");
+        std::fs::write(&removed, b"partial")?;
+        std::fs::write(&persisted, b"complete")?;
+        drop(TempFile(Some(removed.clone())));
+        TempFile(Some(persisted.clone())).persist();
+        assert!(!removed.exists());
+        assert!(persisted.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_blob_redirects_are_reported() -> anyhow::Result<()> {
+        // Reserve a port, then close it so that nothing is listening on it
+        let storage_addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await?
+            .local_addr()?;
+        let location = format!("http://{storage_addr}/signed-blob");
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve_with(move |head| {
+                head.contains("/blobs/")
+                    .then(|| response("307 Temporary Redirect", &[("location", &location)], b""))
+            })
+            .await?;
+
+        let fetcher = test_fetcher();
+        let dir = tempfile::tempdir()?;
+        let err = fetcher
             .fetch_path(
-                oci_cache_dir().await?,
-                oci_ref.as_ref(),
-                vec![PROVIDER_ARCHIVE_MEDIA_TYPE, OCI_MEDIA_TYPE],
+                dir.path(),
+                registry.reference("wasmcloud/http:0.1.0"),
+                fetcher.media_types(ArtifactKind::Component),
                 OciArtifactCacheUpdate::Update,
             )
             .await
-            .context("failed to fetch OCI path")?;
-        let should_cache = match cache {
-            CacheResult::Miss => UseParFileCache::Ignore,
-            CacheResult::Hit => UseParFileCache::Use,
-        };
-        crate::par::read(&path, host_id, oci_ref, should_cache)
+            .expect_err("fetch should fail");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Network(_))
+        ));
+        assert!(format!("{err:#}").contains(&sha256_digest(b"component")));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_errors_report_requested_reference() {
+        let fetcher = OciFetcher::default();
+        let err = fetcher
+            .fetch_component("GHCR.io/MyOrg/App:latest")
             .await
-            .with_context(|| format!("failed to read `{}`", path.display()))
+            .expect_err("latest should be disallowed");
+        assert!(format!("{err:#}").contains(
+            "failed to fetch `GHCR.io/MyOrg/App:latest`, normalized to `ghcr.io/myorg/app:latest`"
+        ));
+        let err = fetcher
+            .fetch_component("ghcr.io/myorg/app:latest")
+            .await
+            .expect_err("latest should be disallowed");
+        assert!(format!("{err:#}").contains("failed to fetch `ghcr.io/myorg/app:latest`"));
+        assert!(!format!("{err:#}").contains("normalized"));
     }
 
-    /// Used to set additional CA paths that will be used as part of fetching components and providers
-    pub fn with_additional_ca_paths(mut self, paths: &[impl AsRef<Path>]) -> Self {
-        self.additional_ca_paths = paths.iter().map(AsRef::as_ref).map(PathBuf::from).collect();
-        self
+    #[tokio::test]
+    async fn test_concurrent_fetches_share_errors() {
+        let fetcher = OciFetcher::default();
+        let (first, second) = futures::join!(
+            fetcher.fetch_component("registry/http:latest"),
+            fetcher.fetch_component("REGISTRY/http:latest"),
+        );
+        for res in [first, second] {
+            assert!(matches!(
+                res.as_ref().map_err(OciFetchError::find),
+                Err(Some(OciFetchError::LatestDisallowed))
+            ));
+        }
+        assert!(fetcher
+            .fetches
+            .0
+            .lock()
+            .expect("lock should not be poisoned")
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fetches_are_recorded() {
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<(String, FetchOutcome, u64)>>);
+
+        impl FetchMetrics for Recorder {
+            fn record_fetch(
+                &self,
+                registry: &str,
+                outcome: FetchOutcome,
+                downloaded: u64,
+                _elapsed: Duration,
+            ) {
+                self.0.lock().expect("lock should not be poisoned").push((
+                    registry.to_string(),
+                    outcome,
+                    downloaded,
+                ));
+            }
+        }
+
+        let recorder = Arc::new(Recorder::default());
+        let fetcher = OciFetcher::default().with_metrics(recorder.clone());
+        let res = fetcher
+            .fetch_component("ghcr.io/wasmcloud/http:latest")
+            .await;
+        assert!(res.is_err());
+        assert_eq!(
+            *recorder.0.lock().expect("lock should not be poisoned"),
+            vec![("ghcr.io".to_string(), FetchOutcome::Error, 0)]
+        );
+    }
+
+    fn manifest(config_media_type: &str, layer_media_types: &[&str]) -> OciImageManifest {
+        OciImageManifest {
+            config: OciDescriptor {
+                media_type: config_media_type.into(),
+                ..Default::default()
+            },
+            layers: layer_media_types
+                .iter()
+                .map(|media_type| OciDescriptor {
+                    media_type: (*media_type).into(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_layers() {
+        let accepted = ArtifactKind::Component.default_media_types();
+
+        let wasm = manifest(WASM_MANIFEST_CONFIG_MEDIA_TYPE, &[WASM_LAYER_MEDIA_TYPE]);
+        assert_eq!(
+            select_layers(&wasm, accepted).map(|l| l.len()).ok(),
+            Some(1)
+        );
+        let wasm = manifest(
+            WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+            &[WASM_LAYER_MEDIA_TYPE, WASM_LAYER_MEDIA_TYPE],
+        );
+        assert!(matches!(
+            select_layers(&wasm, accepted),
+            Err(OciFetchError::InvalidArtifact { layers: 2 })
+        ));
+
+        // Sidecar layers next to the component are ignored
+        let wasm = manifest(
+            WASM_MANIFEST_CONFIG_MEDIA_TYPE,
+            &["application/vnd.wasm.wit.v1+wasm", WASM_LAYER_MEDIA_TYPE],
+        );
+        let layers = select_layers(&wasm, accepted).expect("wasm layer should be selected");
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].media_type, WASM_LAYER_MEDIA_TYPE);
+
+        // Container images may carry a component next to regular filesystem layers
+        let image = manifest(
+            oci_client::manifest::IMAGE_DOCKER_CONFIG_MEDIA_TYPE,
+            &[
+                oci_client::manifest::IMAGE_DOCKER_LAYER_GZIP_MEDIA_TYPE,
+                WASM_MEDIA_TYPE,
+            ],
+        );
+        let layers = select_layers(&image, accepted).expect("component layer should be selected");
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].media_type, WASM_MEDIA_TYPE);
+
+        let image = manifest(
+            oci_client::manifest::IMAGE_CONFIG_MEDIA_TYPE,
+            &[oci_client::manifest::IMAGE_LAYER_GZIP_MEDIA_TYPE],
+        );
+        assert!(matches!(
+            select_layers(&image, accepted),
+            Err(OciFetchError::NoMatchingLayer { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_artifact_type() {
+        let mut artifact = manifest(WASM_MANIFEST_CONFIG_MEDIA_TYPE, &[WASM_LAYER_MEDIA_TYPE]);
+        assert!(check_artifact_type(&artifact, &[ArtifactKind::Component]).is_ok());
+
+        artifact.artifact_type = Some(COMPONENT_ARTIFACT_TYPE.into());
+        assert!(check_artifact_type(&artifact, &[ArtifactKind::Component]).is_ok());
+        assert!(matches!(
+            check_artifact_type(&artifact, &[ArtifactKind::Provider]),
+            Err(OciFetchError::UnsupportedArtifactType { expected, .. })
+                if expected == [PROVIDER_ARCHIVE_ARTIFACT_TYPE]
+        ));
+
+        artifact.artifact_type = Some(PROVIDER_ARCHIVE_ARTIFACT_TYPE.into());
+        assert!(check_artifact_type(
+            &artifact,
+            &[ArtifactKind::Component, ArtifactKind::Provider]
+        )
+        .is_ok());
+
+        artifact.artifact_type = Some("application/vnd.dev.cosign.artifact.sig.v1+json".into());
+        assert!(matches!(
+            check_artifact_type(&artifact, &[ArtifactKind::Component]),
+            Err(OciFetchError::UnsupportedArtifactType { artifact_type, .. })
+                if artifact_type == "application/vnd.dev.cosign.artifact.sig.v1+json"
+        ));
+
+        let fetcher = OciFetcher::default();
+        assert_eq!(
+            fetcher.accepted_kinds(&fetcher.media_types(ArtifactKind::Provider)),
+            [ArtifactKind::Provider]
+        );
+        let mut accepted_media_types = fetcher.media_types(ArtifactKind::Component);
+        accepted_media_types.push(PROVIDER_ARCHIVE_MEDIA_TYPE);
+        assert_eq!(
+            fetcher.accepted_kinds(&accepted_media_types),
+            [ArtifactKind::Component, ArtifactKind::Provider]
+        );
+        assert!(fetcher.accepted_kinds(&["text/plain"]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_serves_resolved_tags() -> anyhow::Result<()> {
+        let registry = TestRegistry::default()
+            .with_component("wasmcloud/http:0.1.0", b"component")
+            .serve()
+            .await?;
+        let reference = registry.reference("wasmcloud/http:0.1.0");
+
+        let dir = tempfile::tempdir()?;
+        let fetcher = test_fetcher()
+            .with_cache_dir(dir.path())
+            .with_memory_cache(1024)
+            .with_pull_policy(PullPolicy::IfNotPresent);
+        assert_eq!(
+            fetcher.fetch_component_bytes(&reference).await?,
+            "component"
+        );
+        registry.take_requests();
+        assert_eq!(
+            fetcher.fetch_component_bytes(&reference).await?,
+            "component"
+        );
+        assert_eq!(registry.take_requests(), Vec::<String>::new());
+
+        // Tags are revalidated once the manifest TTL expires
+        let fetcher = test_fetcher()
+            .with_cache_dir(dir.path())
+            .with_memory_cache(1024)
+            .with_manifest_ttl(Duration::ZERO);
+        assert_eq!(
+            fetcher.fetch_component_bytes(&reference).await?,
+            "component"
+        );
+        registry.take_requests();
+        assert_eq!(
+            fetcher.fetch_component_bytes(&reference).await?,
+            "component"
+        );
+        assert_eq!(registry.count_requests("/manifests/"), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_cache_evicts_least_recently_used() {
+        let mut cache = MemoryCache::new(10);
+        cache.insert("a".into(), Bytes::from_static(&[0; 4]));
+        cache.insert("b".into(), Bytes::from_static(&[1; 4]));
+        assert!(cache.get("a").is_some());
+        // `b` is now the least recently used entry and makes room for `c`
+        assert_eq!(
+            cache.insert("c".into(), Bytes::from_static(&[2; 4])),
+            [EvictedEntry {
+                source: EvictionSource::Memory,
+                key: "b".into(),
+                digest: Some("b".into()),
+                bytes: 4,
+            }]
+        );
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.used, 8);
+        // Entries larger than the cache are never stored
+        cache.insert("d".into(), Bytes::from_static(&[3; 11]));
+        assert!(cache.get("d").is_none());
+        assert_eq!(cache.used, 8);
+
+        // Tags are only resolved to cached entries, and forgotten once these are evicted
+        cache.resolve("registry/http:0.1.0".into(), "d".into());
+        assert_eq!(cache.resolved("registry/http:0.1.0", Duration::MAX), None);
+        cache.resolve("registry/http:0.1.0".into(), "a".into());
+        assert_eq!(
+            cache
+                .resolved("registry/http:0.1.0", Duration::MAX)
+                .as_deref(),
+            Some("a")
+        );
+        assert_eq!(cache.resolved("registry/http:0.1.0", Duration::ZERO), None);
+        cache.get("c");
+        cache.insert("e".into(), Bytes::from_static(&[4; 4]));
+        assert_eq!(cache.resolved("registry/http:0.1.0", Duration::MAX), None);
+    }
+
+    #[test]
+    fn test_component_claims() -> anyhow::Result<()> {
+        const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+        let signed = wascap::wasm::sign_buffer_with_claims(
+            "test".into(),
+            EMPTY_MODULE,
+            &nkeys::KeyPair::new_module(),
+            &nkeys::KeyPair::new_account(),
+            None,
+            None,
+            vec![],
+            false,
+            None,
+            None,
+            None,
+        )?;
+
+        let fetcher = OciFetcher::default();
+        assert!(fetcher.component_claims(EMPTY_MODULE)?.is_none());
+        let claims = fetcher
+            .component_claims(&signed)?
+            .expect("signed component should have claims");
+        assert_eq!(
+            claims.claims.metadata.and_then(|m| m.name).as_deref(),
+            Some("test")
+        );
+
+        let fetcher = fetcher.with_require_signed_components(true);
+        assert!(fetcher.component_claims(&signed)?.is_some());
+        let err = fetcher
+            .component_claims(EMPTY_MODULE)
+            .expect_err("unsigned component should be rejected");
+        assert!(matches!(
+            OciFetchError::find(&err),
+            Some(OciFetchError::Unsigned)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_clients_are_reused() -> anyhow::Result<()> {
+        let img = Reference::from_str("ghcr.io/wasmcloud/http:0.1.0")?;
+        let fetcher = OciFetcher::default();
+        let (_, first) = fetcher.client(&img)?;
+        let (_, second) = fetcher.clone().client(&img)?;
+        assert!(Arc::ptr_eq(&first, &second));
+
+        // Changing the client configuration builds a new client
+        let fetcher = fetcher.with_insecure_registries(["ghcr.io"]);
+        let (_, insecure) = fetcher.client(&img)?;
+        assert!(!Arc::ptr_eq(&first, &insecure));
+        Ok(())
+    }
+
+    #[test]
+    fn test_insecure_registries() {
+        let img = |s| Reference::from_str(s).expect("reference should parse");
+        let fetcher = OciFetcher::default().with_insecure_registries(["localhost:5000"]);
+        assert_eq!(
+            fetcher.insecure_registries_for(&img("ghcr.io/wasmcloud/http:0.1.0")),
+            vec!["localhost:5000"]
+        );
+        assert_eq!(
+            fetcher.insecure_registries_for(&img("localhost:5000/http:0.1.0")),
+            vec!["localhost:5000"]
+        );
+        assert!(OciFetcher::default()
+            .insecure_registries_for(&img("ghcr.io/wasmcloud/http:0.1.0"))
+            .is_empty());
+
+        let fetcher = OciFetcher {
+            allow_insecure: true,
+            ..fetcher
+        };
+        let mut insecure = fetcher.insecure_registries_for(&img("registry:5000/http:0.1.0"));
+        insecure.sort();
+        assert_eq!(insecure, vec!["localhost:5000", "registry:5000"]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_probes_only_insecure_registries_over_http() -> anyhow::Result<()> {
+        let mut registries = Vec::new();
+        for _ in 0..2 {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+            registries.push(listener.local_addr()?.to_string());
+            serve(listener, |_| {
+                response("429 Too Many Requests", &[("retry-after", "7")], b"")
+            });
+        }
+        let [insecure, secure] = &registries[..] else {
+            unreachable!()
+        };
+        let fetcher = OciFetcher::default()
+            .with_insecure_registries([insecure.as_str()])
+            // The plaintext server never answers a TLS handshake
+            .with_read_timeout(Duration::from_secs(1));
+        let img = |registry| {
+            Reference::from_str(&format!("{registry}/http:0.1.0")).expect("reference should parse")
+        };
+        assert_eq!(
+            fetcher.probe_retry_after(&img(insecure), &[]).await,
+            Some(Duration::from_secs(7))
+        );
+        // Configuring another registry as insecure does not downgrade this one to plain HTTP
+        assert_eq!(fetcher.probe_retry_after(&img(secure), &[]).await, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_agent() {
+        assert_eq!(
+            OciFetcher::default().user_agent,
+            concat!("wasmcloud/", env!("CARGO_PKG_VERSION"))
+        );
+        let first = OciFetcher::default().with_user_agent("acme-host/1.0");
+        let second = OciFetcher::default().with_user_agent(String::from("acme-host/1.0"));
+        assert_eq!(first.user_agent, "acme-host/1.0");
+        // Identical user agents share a single allocation
+        assert!(std::ptr::eq(first.user_agent, second.user_agent));
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::ZERO));
+        // Dates in the past mean the client may retry immediately
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        let later = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(300));
+        assert!(parse_retry_after(&later).is_some_and(|d| d > Duration::from_secs(200)));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_requests_are_retried() {
+        let fetcher = OciFetcher::default().with_rate_limit_retries(2, Duration::ZERO);
+        let img = Reference::from_str("127.0.0.1:1/wasmcloud/http:0.1.0")
+            .expect("reference should parse");
+        let rate_limited = || OciDistributionError::ServerError {
+            code: 429,
+            url: String::new(),
+            message: String::new(),
+        };
+
+        let mut attempts = 0;
+        let res = fetcher
+            .retry_rate_limited(&img, &[], || {
+                attempts += 1;
+                let res = if attempts < 3 {
+                    Err(rate_limited())
+                } else {
+                    Ok(attempts)
+                };
+                async move { res }
+            })
+            .await;
+        assert!(matches!(res, Ok(3)));
+
+        let mut attempts = 0;
+        let res: Result<(), _> = fetcher
+            .retry_rate_limited(&img, &[], || {
+                attempts += 1;
+                async { Err(rate_limited()) }
+            })
+            .await;
+        assert!(matches!(res, Err(OciFetchError::RateLimited { .. })));
+        assert_eq!(attempts, 3);
     }
 }
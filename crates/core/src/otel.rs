@@ -2,16 +2,16 @@
 //!
 //! [otel]: https://opentelemetry.io
 
-use std::{path::PathBuf, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
-use anyhow::bail;
+use anyhow::{bail, Context as _};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{logging::Level, wit::WitMap};
 
 /// Configuration values for OpenTelemetry
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct OtelConfig {
     /// Determine whether observability should be enabled.
     #[serde(default)]
@@ -37,7 +37,7 @@ pub struct OtelConfig {
     /// Overrides the OpenTelemetry endpoint for logs.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logs_endpoint: Option<String>,
-    /// Determines whether http or grpc will be used for exporting the telemetry.
+    /// Determines whether gRPC, HTTP/protobuf or HTTP/JSON will be used for exporting the telemetry.
     #[serde(default)]
     pub protocol: OtelProtocol,
     /// Additional CAs to include in the OpenTelemetry client configuration
@@ -46,6 +46,105 @@ pub struct OtelConfig {
     /// The level of tracing to enable.
     #[serde(default)]
     pub trace_level: Level,
+    /// The ratio of traces to sample, from 0.0 (none) to 1.0 (all).
+    #[serde(default = "default_trace_sampling_ratio")]
+    pub trace_sampling_ratio: f64,
+    /// Additional attributes of the OpenTelemetry resource of all signals, e.g. the cluster,
+    /// region or environment. Keys in [`RESERVED_RESOURCE_ATTRIBUTES`] are set by wasmCloud itself
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub resource_attributes: HashMap<String, String>,
+}
+
+impl Default for OtelConfig {
+    fn default() -> Self {
+        Self {
+            enable_observability: false,
+            enable_traces: None,
+            enable_metrics: None,
+            enable_logs: None,
+            observability_endpoint: None,
+            traces_endpoint: None,
+            metrics_endpoint: None,
+            logs_endpoint: None,
+            protocol: OtelProtocol::default(),
+            additional_ca_paths: Vec::default(),
+            trace_level: Level::default(),
+            trace_sampling_ratio: default_trace_sampling_ratio(),
+            resource_attributes: HashMap::default(),
+        }
+    }
+}
+
+fn default_trace_sampling_ratio() -> f64 {
+    1.0
+}
+
+/// Returns whether `ratio` is a valid trace sampling ratio, i.e. within 0.0 and 1.0
+#[must_use]
+pub fn is_valid_trace_sampling_ratio(ratio: f64) -> bool {
+    (0.0..=1.0).contains(&ratio)
+}
+
+/// Keys of the OpenTelemetry resource attributes set by wasmCloud itself, which cannot be set in
+/// [`OtelConfig::resource_attributes`]
+pub const RESERVED_RESOURCE_ATTRIBUTES: [&str; 2] = ["service.name", "service.instance.id"];
+
+/// Parses a resource attribute in the `key=value` format of the `OTEL_RESOURCE_ATTRIBUTES`
+/// environment variable, where the value is percent-encoded
+///
+/// # Errors
+///
+/// Returns an error if the attribute is malformed or its key is in [`RESERVED_RESOURCE_ATTRIBUTES`]
+pub fn parse_resource_attribute(attribute: &str) -> anyhow::Result<(String, String)> {
+    let Some((key, value)) = attribute.split_once('=') else {
+        bail!("invalid resource attribute `{attribute}`, expected `key=value`");
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        bail!("invalid resource attribute `{attribute}`, the key is empty");
+    }
+    if RESERVED_RESOURCE_ATTRIBUTES.contains(&key) {
+        bail!("resource attribute `{key}` is reserved");
+    }
+    let value = percent_encoding::percent_decode_str(value.trim())
+        .decode_utf8()
+        .with_context(|| format!("invalid value of resource attribute `{key}`"))?;
+    Ok((key.to_string(), value.into_owned()))
+}
+
+/// Resource attributes set in the `OTEL_RESOURCE_ATTRIBUTES` environment variable
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EnvResourceAttributes {
+    /// The attributes to set on the resource
+    pub attributes: Vec<(String, String)>,
+    /// Keys of the attributes in [`RESERVED_RESOURCE_ATTRIBUTES`] that were dropped
+    pub dropped: Vec<String>,
+}
+
+/// Parses the comma-separated resource attributes of the `OTEL_RESOURCE_ATTRIBUTES` environment
+/// variable.
+///
+/// The variable is commonly set for every service of a deployment, so unlike attributes parsed by
+/// [`parse_resource_attribute`], attributes in [`RESERVED_RESOURCE_ATTRIBUTES`] are dropped rather
+/// than rejected, keeping the values wasmCloud sets for them
+///
+/// # Errors
+///
+/// Returns an error if any other attribute is malformed
+pub fn parse_resource_attributes_env(value: &str) -> anyhow::Result<EnvResourceAttributes> {
+    let mut parsed = EnvResourceAttributes::default();
+    for attribute in value
+        .split(',')
+        .filter(|attribute| !attribute.trim().is_empty())
+    {
+        let key = attribute.split_once('=').map_or("", |(key, _)| key.trim());
+        if RESERVED_RESOURCE_ATTRIBUTES.contains(&key) {
+            parsed.dropped.push(key.to_string());
+        } else {
+            parsed.attributes.push(parse_resource_attribute(attribute)?);
+        }
+    }
+    Ok(parsed)
 }
 
 impl OtelConfig {
@@ -91,7 +190,9 @@ impl OtelConfig {
         if let Some(endpoint) = self.observability_endpoint.clone() {
             return match self.protocol {
                 OtelProtocol::Grpc => self.resolve_grpc_endpoint(endpoint),
-                OtelProtocol::Http => self.resolve_http_endpoint(signal, endpoint),
+                OtelProtocol::Http | OtelProtocol::HttpJson => {
+                    self.resolve_http_endpoint(signal, endpoint)
+                }
             };
         }
 
@@ -100,6 +201,52 @@ impl OtelConfig {
         String::new()
     }
 
+    /// Returns a description of every endpoint of an enabled signal that looks inconsistent with
+    /// the configured protocol, e.g. a gRPC exporter pointed at the conventional OTLP/HTTP port.
+    /// Such endpoints are still used as configured, since collectors may listen anywhere
+    pub fn protocol_mismatches(&self) -> Vec<String> {
+        [
+            (self.traces_enabled(), OtelSignal::Traces),
+            (self.metrics_enabled(), OtelSignal::Metrics),
+            (self.logs_enabled(), OtelSignal::Logs),
+        ]
+        .into_iter()
+        .filter(|(enabled, _)| *enabled)
+        .filter_map(|(_, signal)| {
+            let endpoint = match signal {
+                OtelSignal::Traces => self.traces_endpoint(),
+                OtelSignal::Metrics => self.metrics_endpoint(),
+                OtelSignal::Logs => self.logs_endpoint(),
+            };
+            let url = Url::parse(&endpoint).ok()?;
+            let problem = match (self.protocol, url.scheme(), url.port_or_known_default()) {
+                (OtelProtocol::Grpc, "http" | "https", Some(OTLP_HTTP_PORT)) => {
+                    format!("port {OTLP_HTTP_PORT} is conventionally used for OTLP over HTTP")
+                }
+                (OtelProtocol::Grpc, "http" | "https", _) if url.path() != "/" => {
+                    "gRPC endpoints must not have a path".to_string()
+                }
+                (
+                    OtelProtocol::Http | OtelProtocol::HttpJson,
+                    "http" | "https",
+                    Some(OTLP_GRPC_PORT),
+                ) => {
+                    format!("port {OTLP_GRPC_PORT} is conventionally used for OTLP over gRPC")
+                }
+                (_, "http" | "https", _) => return None,
+                (_, scheme, _) => {
+                    format!("scheme `{scheme}` is not supported, use `http` or `https`")
+                }
+            };
+            Some(format!(
+                "{signal} endpoint `{endpoint}` does not match protocol `{protocol:?}`: {problem}",
+                signal = signal.name(),
+                protocol = self.protocol,
+            ))
+        })
+        .collect()
+    }
+
     // opentelemetry-otlp expects the gRPC endpoint to not have path components
     // configured, so we're just clearing them out and returning the base url.
     fn resolve_grpc_endpoint(&self, endpoint: String) -> String {
@@ -140,10 +287,20 @@ impl OtelConfig {
 pub enum OtelProtocol {
     #[serde(alias = "grpc", alias = "Grpc")]
     Grpc,
-    #[serde(alias = "http", alias = "Http")]
+    /// OTLP over HTTP, encoded as protobuf
+    #[serde(alias = "http", alias = "Http", alias = "http/protobuf")]
     Http,
+    /// OTLP over HTTP, encoded as JSON
+    #[serde(alias = "http/json")]
+    HttpJson,
 }
 
+/// Port conventionally used by collectors for OTLP over gRPC
+const OTLP_GRPC_PORT: u16 = 4317;
+
+/// Port conventionally used by collectors for OTLP over HTTP
+const OTLP_HTTP_PORT: u16 = 4318;
+
 // Represents https://opentelemetry.io/docs/concepts/signals/
 enum OtelSignal {
     Traces,
@@ -151,17 +308,19 @@ enum OtelSignal {
     Logs,
 }
 
+impl OtelSignal {
+    fn name(&self) -> &'static str {
+        match self {
+            OtelSignal::Traces => "traces",
+            OtelSignal::Metrics => "metrics",
+            OtelSignal::Logs => "logs",
+        }
+    }
+}
+
 impl std::fmt::Display for OtelSignal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "/v1/{}",
-            match self {
-                OtelSignal::Traces => "traces",
-                OtelSignal::Metrics => "metrics",
-                OtelSignal::Logs => "logs",
-            }
-        )
+        write!(f, "/v1/{}", self.name())
     }
 }
 
@@ -176,10 +335,11 @@ impl FromStr for OtelProtocol {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "http" => Ok(Self::Http),
+            "http" | "http/protobuf" => Ok(Self::Http),
+            "http/json" => Ok(Self::HttpJson),
             "grpc" => Ok(Self::Grpc),
             protocol => {
-                bail!("unsupported protocol: {protocol:?}, did you mean 'http' or 'grpc'?")
+                bail!("unsupported protocol: {protocol:?}, did you mean 'http', 'http/json' or 'grpc'?")
             }
         }
     }
@@ -190,7 +350,85 @@ pub type TraceContext = WitMap<String>;
 
 #[cfg(test)]
 mod tests {
-    use super::{OtelConfig, OtelProtocol};
+    use super::{
+        is_valid_trace_sampling_ratio, parse_resource_attribute, parse_resource_attributes_env,
+        OtelConfig, OtelProtocol,
+    };
+
+    #[test]
+    fn test_parse_resource_attribute() {
+        assert_eq!(
+            parse_resource_attribute(" deployment.environment = prod%20eu ").ok(),
+            Some(("deployment.environment".into(), "prod eu".into()))
+        );
+        assert!(parse_resource_attribute("region").is_err());
+        assert!(parse_resource_attribute("=eu").is_err());
+        assert!(parse_resource_attribute("service.name=host").is_err());
+    }
+
+    #[test]
+    fn test_parse_resource_attributes_env() {
+        let parsed =
+            parse_resource_attributes_env("service.name=x,region=eu,service.instance.id=y,")
+                .expect("reserved attributes should be dropped");
+        assert_eq!(parsed.attributes, vec![("region".into(), "eu".into())]);
+        assert_eq!(parsed.dropped, vec!["service.name", "service.instance.id"]);
+        assert!(parse_resource_attributes_env("service.name=x,region").is_err());
+    }
+
+    #[test]
+    fn test_protocol_mismatches() {
+        let config = |protocol, endpoint: &str| OtelConfig {
+            enable_observability: true,
+            protocol,
+            observability_endpoint: Some(endpoint.into()),
+            ..Default::default()
+        };
+
+        assert!(config(OtelProtocol::Grpc, "http://localhost:4317")
+            .protocol_mismatches()
+            .is_empty());
+        assert!(config(OtelProtocol::HttpJson, "https://example.com")
+            .protocol_mismatches()
+            .is_empty());
+        assert_eq!(
+            config(OtelProtocol::Grpc, "http://localhost:4318")
+                .protocol_mismatches()
+                .len(),
+            3
+        );
+        assert_eq!(
+            config(OtelProtocol::Http, "http://localhost:4317")
+                .protocol_mismatches()
+                .len(),
+            3
+        );
+        assert_eq!(
+            OtelConfig {
+                enable_traces: Some(true),
+                protocol: OtelProtocol::Http,
+                traces_endpoint: Some("grpc://localhost:4317".into()),
+                ..Default::default()
+            }
+            .protocol_mismatches(),
+            ["traces endpoint `grpc://localhost:4317` does not match protocol `Http`: scheme `grpc` is not supported, use `http` or `https`"]
+        );
+        assert_eq!(
+            "http/json".parse::<OtelProtocol>().ok(),
+            Some(OtelProtocol::HttpJson)
+        );
+    }
+
+    #[test]
+    fn test_trace_sampling_ratio() {
+        assert_eq!(OtelConfig::default().trace_sampling_ratio, 1.0);
+        assert!(is_valid_trace_sampling_ratio(0.0));
+        assert!(is_valid_trace_sampling_ratio(0.25));
+        assert!(is_valid_trace_sampling_ratio(1.0));
+        assert!(!is_valid_trace_sampling_ratio(-0.1));
+        assert!(!is_valid_trace_sampling_ratio(1.5));
+        assert!(!is_valid_trace_sampling_ratio(f64::NAN));
+    }
 
     #[test]
     fn test_grpc_resolves_to_empty_string_without_overrides() {
@@ -1,14 +1,22 @@
+use std::collections::HashSet;
 use std::env::consts::{ARCH, OS};
 use std::env::temp_dir;
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::str;
+use std::time::Duration;
 
 use anyhow::{anyhow, Context, Result};
 use provider_archive::ProviderArchive;
+use sha2::{Digest as _, Sha256};
 use tokio::fs::{self, File, OpenOptions};
 use tokio::io::AsyncWriteExt;
+use tracing::warn;
 use wascap::jwt;
 
+/// Extension of the sidecar file recording the digest of an extracted provider binary
+const INTEGRITY_EXTENSION: &str = "sha256";
+
 fn normalize_for_filename(input: &str) -> String {
     input
         .to_lowercase()
@@ -18,11 +26,18 @@ fn normalize_for_filename(input: &str) -> String {
 /// Whether to use the par file cache
 #[derive(Default, Clone, PartialEq, Eq)]
 pub enum UseParFileCache {
-    /// Use the par file cache
+    /// Ignore the par file cache, always extracting the provider
     Ignore,
     /// Use the par file cache
     #[default]
     Use,
+    /// Use the par file cache, but extract the provider again if the cached extraction is older
+    /// than the given duration
+    Expire(Duration),
+    /// Use the par file cache keyed on the digest of the provider archive instead of the provider
+    /// reference. Extractions are immutable and shared by all references to the same archive, so
+    /// a moved tag never serves a stale extraction
+    Digest,
 }
 
 fn native_target() -> String {
@@ -33,20 +48,115 @@ fn native_target() -> String {
 ///
 /// # Arguments
 /// * `host_id` - The host ID this provider is starting on. Required in order to isolate provider caches
-///            for different hosts
+///   for different hosts
 /// * `provider_ref` - The provider reference, e.g. file or OCI
 pub fn cache_path(host_id: impl AsRef<str>, provider_ref: impl AsRef<str>) -> PathBuf {
     let provider_ref = normalize_for_filename(provider_ref.as_ref());
 
-    let mut cache = temp_dir();
-    cache.push("wasmcloudcache");
-    cache.push(host_id.as_ref());
+    let mut cache = host_cache_dir(host_id);
     cache.push(&provider_ref);
     #[cfg(windows)]
     cache.set_extension("exe");
     cache
 }
 
+/// Returns the path to the cache file for a provider archive with the given `sha256` hex digest,
+/// which is used by [`UseParFileCache::Digest`]
+///
+/// # Arguments
+/// * `host_id` - The host ID this provider is starting on. Required in order to isolate provider caches
+///   for different hosts
+/// * `digest` - The hex-encoded sha256 digest of the provider archive
+pub fn digest_cache_path(host_id: impl AsRef<str>, digest: impl AsRef<str>) -> PathBuf {
+    let mut cache = host_cache_dir(host_id);
+    cache.push(format!(
+        "sha256_{}",
+        normalize_for_filename(digest.as_ref())
+    ));
+    #[cfg(windows)]
+    cache.set_extension("exe");
+    cache
+}
+
+/// Returns the directory providers started on the host with `host_id` are extracted to
+fn host_cache_dir(host_id: impl AsRef<str>) -> PathBuf {
+    let mut cache = temp_dir();
+    cache.push("wasmcloudcache");
+    cache.push(host_id.as_ref());
+    cache
+}
+
+/// Removes all providers extracted for a host, returning the paths of the removed files.
+///
+/// This must only be called once none of the host's providers are running anymore, e.g. on
+/// shutdown, use [`purge_extracted_except`] otherwise
+///
+/// # Arguments
+/// * `host_id` - The host ID the providers were started on
+pub async fn purge_extracted(host_id: impl AsRef<str>) -> Result<Vec<PathBuf>> {
+    purge_extracted_except(host_id, std::iter::empty::<&str>()).await
+}
+
+/// Removes the providers extracted for a host, except for those in `running`, returning the
+/// paths of the removed files
+///
+/// # Arguments
+/// * `host_id` - The host ID the providers were started on
+/// * `running` - The references of the providers currently running on the host, or the paths they
+///   were extracted to, which are kept. Providers extracted with [`UseParFileCache::Digest`] must
+///   be listed by path
+pub async fn purge_extracted_except(
+    host_id: impl AsRef<str>,
+    running: impl IntoIterator<Item = impl AsRef<str>>,
+) -> Result<Vec<PathBuf>> {
+    let host_id = host_id.as_ref();
+    let keep: HashSet<_> = running
+        .into_iter()
+        .flat_map(|provider| {
+            let provider = provider.as_ref();
+            [cache_path(host_id, provider), PathBuf::from(provider)]
+        })
+        .collect();
+    let dir = host_cache_dir(host_id);
+    let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(anyhow!(e).context(format!("failed to read [{}]", dir.display())));
+        }
+    };
+    let mut removed = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .with_context(|| format!("failed to read [{}]", dir.display()))?
+    {
+        let path = entry.path();
+        let is_kept_sidecar = path
+            .extension()
+            .is_some_and(|ext| ext == INTEGRITY_EXTENSION)
+            && keep.contains(&path.with_extension(""));
+        if keep.contains(&path)
+            || is_kept_sidecar
+            || !entry.file_type().await.is_ok_and(|ty| ty.is_file())
+        {
+            continue;
+        }
+        match fs::remove_file(&path).await {
+            Ok(()) => removed.push(path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(anyhow!(e).context(format!("failed to remove [{}]", path.display())));
+            }
+        }
+    }
+    if keep.is_empty() {
+        // Fails if providers were extracted concurrently, in which case the directory is kept
+        let _ = fs::remove_dir(&dir).await;
+    }
+    Ok(removed)
+}
+
 pub(super) async fn create(path: impl AsRef<Path>) -> Result<Option<File>> {
     let path = path.as_ref();
     // Check if the file exists and return
@@ -79,7 +189,7 @@ async fn open_file(path: impl AsRef<Path>) -> Result<Option<File>> {
 /// # Arguments
 /// * `path` - The path to the provider archive
 /// * `host_id` - The host ID this provider is starting on. Required in order to isolate provider caches
-///           for different hosts
+///   for different hosts
 /// * `provider_ref` - The reference to the provider (e.g. file or OCI). Required to cache provider for future fetches
 pub async fn read(
     path: impl AsRef<Path>,
@@ -87,21 +197,84 @@ pub async fn read(
     provider_ref: impl AsRef<str>,
     cache: UseParFileCache,
 ) -> Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+    read_verified(path, host_id, provider_ref, cache, |_| Ok(())).await
+}
+
+/// Reads a provider archive from the given path and writes it to the cache, like [`read`], but
+/// only once `verify` accepted the claims embedded in the archive. Nothing is written if
+/// verification fails
+///
+/// # Arguments
+/// * `path` - The path to the provider archive
+/// * `host_id` - The host ID this provider is starting on. Required in order to isolate provider caches
+///   for different hosts
+/// * `provider_ref` - The reference to the provider (e.g. file or OCI). Required to cache provider for future fetches
+/// * `verify` - Verifies the claims embedded in the provider archive, if any
+pub async fn read_verified(
+    path: impl AsRef<Path>,
+    host_id: impl AsRef<str>,
+    provider_ref: impl AsRef<str>,
+    cache: UseParFileCache,
+    verify: impl FnOnce(Option<&jwt::Token<jwt::CapabilityProvider>>) -> Result<()>,
+) -> Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+    read_verified_with(path, host_id, provider_ref, cache, false, verify).await
+}
+
+/// Reads a provider archive from the given path and writes it to the cache, like
+/// [`read_verified`]. If `verify_integrity` is set, a cached extraction is only used if its
+/// contents still match the digest recorded when it was extracted, and is extracted again
+/// otherwise, e.g. if it was corrupted or modified on disk
+///
+/// # Arguments
+/// * `path` - The path to the provider archive
+/// * `host_id` - The host ID this provider is starting on. Required in order to isolate provider caches
+///   for different hosts
+/// * `provider_ref` - The reference to the provider (e.g. file or OCI). Required to cache provider for future fetches
+/// * `verify_integrity` - Whether to verify the contents of a cached extraction before using it
+/// * `verify` - Verifies the claims embedded in the provider archive, if any
+pub async fn read_verified_with(
+    path: impl AsRef<Path>,
+    host_id: impl AsRef<str>,
+    provider_ref: impl AsRef<str>,
+    cache: UseParFileCache,
+    verify_integrity: bool,
+    verify: impl FnOnce(Option<&jwt::Token<jwt::CapabilityProvider>>) -> Result<()>,
+) -> Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+    let path = path.as_ref();
     let par = ProviderArchive::try_load_target_from_file(path, &native_target())
         .await
         .map_err(|e| anyhow!(e).context("failed to load provider archive"))?;
     let claims = par.claims_token();
-    let exe = cache_path(host_id, provider_ref);
+    verify(claims.as_ref()).context("failed to verify provider archive")?;
+    let exe = if cache == UseParFileCache::Digest {
+        let archive = fs::read(&path)
+            .await
+            .context("failed to read provider archive")?;
+        digest_cache_path(host_id, hex::encode(Sha256::digest(archive)))
+    } else {
+        cache_path(host_id, provider_ref)
+    };
 
     let new_file = create(&exe).await?;
-    let mut file = match (cache, new_file) {
-        (UseParFileCache::Use, None) => {
+    let cached = match (cache, &new_file) {
+        (_, Some(_)) | (UseParFileCache::Ignore, None) => false,
+        (UseParFileCache::Use | UseParFileCache::Digest, None) => true,
+        (UseParFileCache::Expire(ttl), None) => is_fresh(&exe, ttl).await,
+    };
+    if cached {
+        if !verify_integrity || is_intact(&exe).await {
             return Ok((exe, claims));
         }
-        (UseParFileCache::Ignore, None) => open_file(&exe)
+        warn!(
+            path = %exe.display(),
+            "cached provider extraction does not match its recorded digest, extracting again"
+        );
+    }
+    let mut file = match new_file {
+        Some(file) => file,
+        None => open_file(&exe)
             .await?
             .with_context(|| format!("failed to open file [{}]", exe.display()))?,
-        (UseParFileCache::Use, Some(file)) | (UseParFileCache::Ignore, Some(file)) => file,
     };
 
     let target = native_target();
@@ -110,6 +283,176 @@ pub async fn read(
         .with_context(|| format!("target `{target}` not found"))?;
     file.write_all(&buf).await.context("failed to write")?;
     file.flush().await.context("failed to flush")?;
+    fs::write(integrity_path(&exe), hex::encode(Sha256::digest(&buf)))
+        .await
+        .context("failed to record digest of extracted provider")?;
 
     Ok((exe, claims))
 }
+
+/// Returns the path of the sidecar file recording the digest of the extraction at `exe`
+fn integrity_path(exe: &Path) -> PathBuf {
+    let mut path = OsString::from(exe);
+    path.push(".");
+    path.push(INTEGRITY_EXTENSION);
+    path.into()
+}
+
+/// Returns whether the contents of the extraction at `exe` match the digest recorded when it was
+/// extracted. Extractions without a recorded digest are never considered intact
+async fn is_intact(exe: &Path) -> bool {
+    let (Ok(recorded), Ok(contents)) =
+        tokio::join!(fs::read_to_string(integrity_path(exe)), fs::read(exe))
+    else {
+        return false;
+    };
+    recorded.trim() == hex::encode(Sha256::digest(contents))
+}
+
+/// Returns whether the file at `path` was last written less than `ttl` ago
+async fn is_fresh(path: &Path, ttl: Duration) -> bool {
+    fs::metadata(path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age < ttl))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_purge_extracted() -> Result<()> {
+        let host_id = ulid::Ulid::new().to_string();
+        let running = cache_path(&host_id, "registry/running:0.1.0");
+        let stopped = cache_path(&host_id, "registry/stopped:0.1.0");
+        for path in [&running, &stopped] {
+            create(path).await?.context("provider already extracted")?;
+        }
+        assert_eq!(
+            purge_extracted_except(&host_id, ["registry/running:0.1.0"]).await?,
+            std::slice::from_ref(&stopped)
+        );
+        assert!(fs::metadata(&running).await.is_ok());
+        assert!(fs::metadata(&stopped).await.is_err());
+
+        let by_digest = digest_cache_path(&host_id, "abc");
+        create(&by_digest)
+            .await?
+            .context("provider already extracted")?;
+        assert!(purge_extracted_except(
+            &host_id,
+            [
+                "registry/running:0.1.0",
+                by_digest.to_str().context("non-UTF-8 path")?
+            ]
+        )
+        .await?
+        .is_empty());
+        assert_eq!(
+            purge_extracted_except(&host_id, ["registry/running:0.1.0"]).await?,
+            [by_digest]
+        );
+
+        assert_eq!(purge_extracted(&host_id).await?, [running]);
+        assert!(fs::metadata(host_cache_dir(&host_id)).await.is_err());
+        assert!(purge_extracted(&host_id).await?.is_empty());
+        Ok(())
+    }
+
+    async fn write_par(path: &Path, bytes: &[u8]) -> Result<()> {
+        let mut par = ProviderArchive::new("provider", "wasmCloud", None, None);
+        par.add_library(&native_target(), bytes)
+            .map_err(|e| anyhow!("{e}"))?;
+        par.write(
+            path,
+            &nkeys::KeyPair::new_account(),
+            &nkeys::KeyPair::new_service(),
+            false,
+        )
+        .await
+        .map_err(|e| anyhow!("{e}"))
+    }
+
+    #[tokio::test]
+    async fn test_read_cache_modes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let host_id = ulid::Ulid::new().to_string();
+        let first = dir.path().join("first.par");
+        let second = dir.path().join("second.par");
+        write_par(&first, b"first").await?;
+        write_par(&second, b"second").await?;
+
+        // A cached extraction is kept regardless of the archive
+        let (exe, _) = read(&first, &host_id, "provider:latest", UseParFileCache::Use).await?;
+        read(&second, &host_id, "provider:latest", UseParFileCache::Use).await?;
+        assert_eq!(fs::read(&exe).await?, b"first");
+
+        // ...unless it expired
+        read(
+            &second,
+            &host_id,
+            "provider:latest",
+            UseParFileCache::Expire(Duration::from_secs(3600)),
+        )
+        .await?;
+        assert_eq!(fs::read(&exe).await?, b"first");
+        read(
+            &second,
+            &host_id,
+            "provider:latest",
+            UseParFileCache::Expire(Duration::ZERO),
+        )
+        .await?;
+        assert_eq!(fs::read(&exe).await?, b"second");
+
+        // Digest-keyed extractions are shared by references and follow moved tags
+        let (a, _) = read(&first, &host_id, "provider:a", UseParFileCache::Digest).await?;
+        let (b, _) = read(&first, &host_id, "provider:b", UseParFileCache::Digest).await?;
+        let (moved, _) = read(&second, &host_id, "provider:a", UseParFileCache::Digest).await?;
+        assert_eq!(a, b);
+        assert_ne!(a, moved);
+        assert_eq!(fs::read(&a).await?, b"first");
+        assert_eq!(fs::read(&moved).await?, b"second");
+
+        purge_extracted(&host_id).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_verified_integrity() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let host_id = ulid::Ulid::new().to_string();
+        let par = dir.path().join("provider.par");
+        write_par(&par, b"provider").await?;
+        let read_with = |verify_integrity| {
+            read_verified_with(
+                &par,
+                &host_id,
+                "provider:latest",
+                UseParFileCache::Use,
+                verify_integrity,
+                |_| Ok(()),
+            )
+        };
+
+        let (exe, _) = read_with(true).await?;
+        assert!(fs::metadata(integrity_path(&exe)).await.is_ok());
+        fs::write(&exe, b"tampered").await?;
+
+        // Modifications are only detected if verification is enabled
+        read_with(false).await?;
+        assert_eq!(fs::read(&exe).await?, b"tampered");
+        read_with(true).await?;
+        assert_eq!(fs::read(&exe).await?, b"provider");
+
+        // The digest of a running provider is kept along with its extraction
+        assert!(purge_extracted_except(&host_id, ["provider:latest"])
+            .await?
+            .is_empty());
+        assert!(is_intact(&exe).await);
+
+        purge_extracted(&host_id).await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,16 @@
 use std::path::PathBuf;
 
 use anyhow::{Context as _, Result};
+use tracing::warn;
+
+/// Environment variable holding the username used to authenticate with registries by default
+pub const REGISTRY_USER_ENV: &str = "WASMCLOUD_REGISTRY_USER";
+
+/// Environment variable holding the password used to authenticate with registries by default
+pub const REGISTRY_PASSWORD_ENV: &str = "WASMCLOUD_REGISTRY_PASSWORD";
+
+/// Environment variable holding the token used to authenticate with registries by default
+pub const REGISTRY_TOKEN_ENV: &str = "WASMCLOUD_REGISTRY_TOKEN";
 
 /// The type of a registry
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -33,6 +43,33 @@ impl From<(Option<String>, Option<String>)> for RegistryAuth {
     }
 }
 
+impl RegistryAuth {
+    /// Reads the default registry credentials from the environment: a token from
+    /// `WASMCLOUD_REGISTRY_TOKEN`, or a username and password from `WASMCLOUD_REGISTRY_USER` and
+    /// `WASMCLOUD_REGISTRY_PASSWORD`. The token is preferred if both are set, and registries are
+    /// accessed anonymously if neither is
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name| var(name).filter(|value| !value.is_empty());
+        let basic = Self::from((var(REGISTRY_USER_ENV), var(REGISTRY_PASSWORD_ENV)));
+        match var(REGISTRY_TOKEN_ENV) {
+            Some(token) => {
+                if basic != Self::Anonymous {
+                    warn!(
+                        "both `{REGISTRY_TOKEN_ENV}` and `{REGISTRY_USER_ENV}`/`{REGISTRY_PASSWORD_ENV}` are set, using the token"
+                    );
+                }
+                Self::Token(token)
+            }
+            None => basic,
+        }
+    }
+}
+
 #[cfg(feature = "oci")]
 impl From<&RegistryAuth> for oci_client::secrets::RegistryAuth {
     fn from(auth: &crate::RegistryAuth) -> Self {
@@ -168,3 +205,39 @@ impl RegistryConfig {
         self.additional_ca_paths = value;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    #[test]
+    fn test_registry_auth_from_vars() {
+        let from_vars = |vars: &[(&str, &str)]| {
+            let vars: HashMap<_, _> = vars.iter().copied().collect();
+            RegistryAuth::from_vars(|name| vars.get(name).map(ToString::to_string))
+        };
+        assert_eq!(from_vars(&[]), RegistryAuth::Anonymous);
+        assert_eq!(
+            from_vars(&[(REGISTRY_USER_ENV, "user")]),
+            RegistryAuth::Anonymous
+        );
+        assert_eq!(
+            from_vars(&[(REGISTRY_USER_ENV, "user"), (REGISTRY_PASSWORD_ENV, "")]),
+            RegistryAuth::Anonymous
+        );
+        assert_eq!(
+            from_vars(&[(REGISTRY_USER_ENV, "user"), (REGISTRY_PASSWORD_ENV, "pass")]),
+            RegistryAuth::Basic("user".into(), "pass".into())
+        );
+        assert_eq!(
+            from_vars(&[
+                (REGISTRY_USER_ENV, "user"),
+                (REGISTRY_PASSWORD_ENV, "pass"),
+                (REGISTRY_TOKEN_ENV, "token"),
+            ]),
+            RegistryAuth::Token("token".into())
+        );
+    }
+}
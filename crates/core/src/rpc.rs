@@ -66,3 +66,12 @@ pub fn shutdown_subject(lattice: &str, provider_key: &str, link_name: &str) -> S
 pub fn provider_config_update_subject(lattice: &str, provider_key: &str) -> String {
     format!("wasmbus.rpc.{lattice}.{provider_key}.config.update")
 }
+
+/// Generate the wasmbus RPC subject for changing the log level of a given provider
+///
+/// When messages are published on this subject, providers replace the level of their active
+/// logging filter with the JSON-encoded [`Level`](crate::logging::Level) in the message body.
+#[must_use]
+pub fn provider_log_level_subject(lattice: &str, provider_key: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.log_level")
+}
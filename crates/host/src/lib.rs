@@ -24,16 +24,22 @@ pub(crate) mod metrics;
 pub use metrics::HostMetrics;
 pub use oci::Config as OciConfig;
 pub use policy::{
-    HostInfo as PolicyHostInfo, Manager as PolicyManager, Response as PolicyResponse,
+    Decision as PolicyDecision, HostInfo as PolicyHostInfo, Manager as PolicyManager,
+    Response as PolicyResponse,
+};
+pub use secrets::{Manager as SecretsManager, TopicPrefix as SecretsTopicPrefix};
+pub use wasmbus::{
+    Host as WasmbusHost, HostConfig as WasmbusHostConfig,
+    HostConfigBuilder as WasmbusHostConfigBuilder,
+};
+pub use wasmcloud_core::{
+    FetchedProvider, OciFetcher, PullPolicy, RegistryAuth, RegistryConfig, RegistryType,
 };
-pub use secrets::Manager as SecretsManager;
-pub use wasmbus::{Host as WasmbusHost, HostConfig as WasmbusHostConfig};
-pub use wasmcloud_core::{OciFetcher, RegistryAuth, RegistryConfig, RegistryType};
 
 pub use url;
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, bail, ensure, Context as _};
 use tokio::fs;
@@ -51,6 +57,12 @@ impl<'a> TryFrom<&'a str> for ResourceRef<'a> {
     type Error = anyhow::Error;
 
     fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        // Bare paths, e.g. of a component just built locally, are loaded directly, same as
+        // `file://` URLs. Only references that are clearly paths are treated as such, since
+        // relative paths are indistinguishable from OCI references otherwise
+        if Path::new(s).is_absolute() || s.starts_with("./") || s.starts_with("../") {
+            return Ok(Self::File(s.into()));
+        }
         match Url::parse(s) {
             Ok(url) => {
                 match url.scheme() {
@@ -102,13 +114,43 @@ impl ResourceRef<'_> {
     }
 }
 
+/// Options for fetching components and providers from OCI registries, see [`fetch_component`]
+/// and [`fetch_provider`]
+#[derive(Clone, Debug, Default)]
+pub struct FetchOptions {
+    /// Maximum size of fetched components in bytes, components larger than this are not
+    /// downloaded. Unlimited if unset
+    pub max_component_size: Option<u64>,
+    /// Maximum size of fetched provider archives in bytes, archives larger than this are not
+    /// downloaded. Unlimited if unset
+    pub max_provider_size: Option<u64>,
+    /// When to contact OCI registries for artifacts that may already be cached
+    pub pull_policy: PullPolicy,
+    /// Registry prepended to OCI references that do not name one
+    pub default_registry: Option<String>,
+}
+
+/// Prepends `default_registry`, if any, to an OCI reference that does not name a registry, so
+/// that the configuration of the default registry applies to it
+fn qualify_oci_ref(oci_ref: &str, default_registry: Option<&str>) -> String {
+    match default_registry {
+        Some(registry) => wasmcloud_core::qualify_reference(oci_ref, registry),
+        None => oci_ref.to_string(),
+    }
+}
+
 /// Fetch an component from a reference.
+///
+/// References may also be `file://` URLs, absolute paths or paths starting with `./` or `../`,
+/// which are read directly if `allow_file_load` is set. OCI references are fetched according to
+/// `options`.
 #[instrument(level = "debug", skip(allow_file_load, registry_config))]
 pub async fn fetch_component(
     component_ref: &str,
     allow_file_load: bool,
     additional_ca_paths: &Vec<PathBuf>,
     registry_config: &HashMap<String, RegistryConfig>,
+    options: &FetchOptions,
 ) -> anyhow::Result<Vec<u8>> {
     match ResourceRef::try_from(component_ref)? {
         ResourceRef::File(component_ref) => {
@@ -120,53 +162,109 @@ pub async fn fetch_component(
                 .await
                 .context("failed to read component")
         }
-        ref oci_ref @ ResourceRef::Oci(component_ref) => oci_ref
-            .authority()
-            .and_then(|authority| registry_config.get(authority))
-            .map(OciFetcher::from)
-            .unwrap_or_default()
-            .with_additional_ca_paths(additional_ca_paths)
-            .fetch_component(component_ref)
-            .await
-            .with_context(|| {
-                format!("failed to fetch component under OCI reference `{component_ref}`")
-            }),
+        ResourceRef::Oci(component_ref) => {
+            let component_ref = qualify_oci_ref(component_ref, options.default_registry.as_deref());
+            let fetcher = ResourceRef::Oci(&component_ref)
+                .authority()
+                .and_then(|authority| registry_config.get(authority))
+                .map(OciFetcher::from)
+                .unwrap_or_default()
+                .with_additional_ca_paths(additional_ca_paths)
+                .with_pull_policy(options.pull_policy);
+            let fetcher = match options.max_component_size {
+                Some(max) => fetcher.with_max_artifact_bytes(max),
+                None => fetcher,
+            };
+            fetcher
+                .fetch_component(&component_ref)
+                .await
+                .with_context(|| {
+                    format!("failed to fetch component under OCI reference `{component_ref}`")
+                })
+        }
     }
 }
 
+/// Fetch a component from a reference along with its embedded wascap claims, if any.
+///
+/// Claims are extracted from the fetched bytes, so checking signatures does not require reading
+/// the artifact a second time. See [`fetch_component`] for how references are resolved.
+///
+/// # Errors
+///
+/// Returns an error if fetching fails or the component embeds claims, which are invalid
+#[instrument(level = "debug", skip(allow_file_load, registry_config))]
+pub async fn fetch_component_signed(
+    component_ref: &str,
+    allow_file_load: bool,
+    additional_ca_paths: &Vec<PathBuf>,
+    registry_config: &HashMap<String, RegistryConfig>,
+    options: &FetchOptions,
+) -> anyhow::Result<(Vec<u8>, Option<jwt::Token<jwt::Component>>)> {
+    let wasm = fetch_component(
+        component_ref,
+        allow_file_load,
+        additional_ca_paths,
+        registry_config,
+        options,
+    )
+    .await?;
+    let claims = wasmcloud_runtime::component::claims_token(&wasm)?;
+    Ok((wasm, claims))
+}
+
 /// Fetch a provider from a reference.
+///
+/// References may also be `file://` URLs, absolute paths or paths starting with `./` or `../`,
+/// which are read directly if `allow_file_load` is set. OCI references are fetched according to
+/// `options`, with `max_provider_size` limiting the size of provider archives.
 #[instrument(skip(registry_config, host_id), fields(provider_ref = %provider_ref.as_ref()))]
 pub async fn fetch_provider(
     provider_ref: impl AsRef<str>,
     host_id: impl AsRef<str>,
     allow_file_load: bool,
     registry_config: &HashMap<String, RegistryConfig>,
-) -> anyhow::Result<(PathBuf, Option<jwt::Token<jwt::CapabilityProvider>>)> {
+    options: &FetchOptions,
+) -> anyhow::Result<FetchedProvider> {
     match ResourceRef::try_from(provider_ref.as_ref())? {
         ResourceRef::File(provider_path) => {
             ensure!(
                 allow_file_load,
                 "unable to start provider from file, file loading is disabled"
             );
-            wasmcloud_core::par::read(
+            let (path, claims) = wasmcloud_core::par::read(
                 provider_path,
                 host_id,
                 provider_ref,
                 wasmcloud_core::par::UseParFileCache::Ignore,
             )
             .await
-            .context("failed to read provider")
+            .context("failed to read provider")?;
+            Ok(FetchedProvider {
+                path,
+                claims,
+                digest: None,
+            })
+        }
+        ResourceRef::Oci(provider_ref) => {
+            let provider_ref = qualify_oci_ref(provider_ref, options.default_registry.as_deref());
+            let fetcher = ResourceRef::Oci(&provider_ref)
+                .authority()
+                .and_then(|authority| registry_config.get(authority))
+                .map(OciFetcher::from)
+                .unwrap_or_default()
+                .with_pull_policy(options.pull_policy);
+            let fetcher = match options.max_provider_size {
+                Some(max) => fetcher.with_max_artifact_bytes(max),
+                None => fetcher,
+            };
+            fetcher
+                .fetch_provider_with_metadata(&provider_ref, host_id)
+                .await
+                .with_context(|| {
+                    format!("failed to fetch provider under OCI reference `{provider_ref}`")
+                })
         }
-        ref oci_ref @ ResourceRef::Oci(provider_ref) => oci_ref
-            .authority()
-            .and_then(|authority| registry_config.get(authority))
-            .map(OciFetcher::from)
-            .unwrap_or_default()
-            .fetch_provider(&provider_ref, host_id)
-            .await
-            .with_context(|| {
-                format!("failed to fetch provider under OCI reference `{provider_ref}`")
-            }),
     }
 }
 
@@ -215,5 +313,33 @@ fn parse_references() -> anyhow::Result<()> {
         "container reference should be parsed as OCI and left intact"
     );
 
+    // absolute path
+    let path = std::env::current_exe().context("failed to get test binary path")?;
+    ensure!(
+        ResourceRef::try_from(path.to_str().context("test binary path is not UTF-8")?)
+            .expect("failed to parse")
+            == ResourceRef::File(path.clone()),
+        "absolute path should be parsed as file"
+    );
+
+    // relative paths
+    ensure!(
+        ResourceRef::try_from("./build/foo_s.wasm").expect("failed to parse")
+            == ResourceRef::File("./build/foo_s.wasm".into()),
+        "path starting with `./` should be parsed as file"
+    );
+    ensure!(
+        ResourceRef::try_from("../build/foo_s.wasm").expect("failed to parse")
+            == ResourceRef::File("../build/foo_s.wasm".into()),
+        "path starting with `../` should be parsed as file"
+    );
+
+    // OCI reference that could also be a relative path
+    ensure!(
+        ResourceRef::try_from("ghcr.io/org/comp:1.0").expect("failed to parse")
+            == ResourceRef::Oci("ghcr.io/org/comp:1.0"),
+        "reference without a path prefix should be parsed as OCI"
+    );
+
     Ok(())
 }
@@ -10,6 +10,8 @@ pub struct HostMetrics {
     pub component_invocations: Counter<u64>,
     /// The count of the number of times an component invocation resulted in an error.
     pub component_errors: Counter<u64>,
+    /// The count of the number of times the default policy decision was applied because the policy service did not respond.
+    pub policy_default_decisions: Counter<u64>,
 
     /// The host's ID.
     // TODO this is actually configured as an InstrumentationScope attribute on the global meter,
@@ -42,10 +44,16 @@ impl HostMetrics {
             .with_description("Number of component errors")
             .init();
 
+        let policy_default_decision_count = meter
+            .u64_counter("wasmcloud_host.policy.default_decisions")
+            .with_description("Number of policy requests answered with the default decision")
+            .init();
+
         Self {
             handle_rpc_message_duration_ns: wasmcloud_host_handle_rpc_message_duration_ns,
             component_invocations: component_invocation_count,
             component_errors: component_error_count,
+            policy_default_decisions: policy_default_decision_count,
             host_id,
             lattice_id,
         }
@@ -65,4 +73,9 @@ impl HostMetrics {
             self.component_errors.add(1, attributes);
         }
     }
+
+    /// Record that the default policy decision was applied to a request.
+    pub(crate) fn record_policy_default_decision(&self, attributes: &[KeyValue]) {
+        self.policy_default_decisions.add(1, attributes);
+    }
 }
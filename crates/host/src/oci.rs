@@ -4,9 +4,11 @@
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use wasmcloud_core::PullPolicy;
 
 /// Configuration options for OCI operations.
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
 pub struct Config {
     /// Additional CAs to include in the OCI client configuration
     pub additional_ca_paths: Vec<PathBuf>,
@@ -20,4 +22,9 @@ pub struct Config {
     pub oci_user: Option<String>,
     /// Password for the OCI registry specified by `oci_registry`.
     pub oci_password: Option<String>,
+    /// When to contact OCI registries for artifacts that may already be cached
+    pub pull_policy: PullPolicy,
+    /// Registry prepended to OCI references that do not name one, such as
+    /// `myteam/mycomponent:1.0`, instead of fetching them from Docker Hub
+    pub default_registry: Option<String>,
 }
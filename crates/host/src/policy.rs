@@ -2,26 +2,36 @@ use core::time::Duration;
 
 use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+use async_nats::RequestErrorKind;
+use bytes::Bytes;
 use futures::{
     stream::{AbortHandle, Abortable},
     StreamExt,
 };
 use serde::{Deserialize, Serialize};
 use tokio::spawn;
-use tokio::sync::RwLock;
+use tokio::sync::{oneshot, RwLock};
+use tokio::time::sleep;
 use tracing::{debug, error, instrument, trace, warn};
 use ulid::Ulid;
 use uuid::Uuid;
 use wascap::jwt;
+use wasmcloud_tracing::KeyValue;
+
+use crate::HostMetrics;
 
 // NOTE: All requests will be v1 until the schema changes, at which point we can change the version
 // per-request type
 const POLICY_TYPE_VERSION: &str = "v1";
 
+/// How long a batch of policy requests is held open for more requests before it is sent
+const POLICY_BATCH_WINDOW: Duration = Duration::from_millis(5);
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Hash)]
 /// Claims associated with a policy request, if embedded inside the component or provider
 pub struct PolicyClaims {
@@ -97,6 +107,40 @@ pub struct HostInfo {
     pub labels: HashMap<String, String>,
 }
 
+/// The decision applied when the policy service does not answer a request
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    /// Permit the request
+    Allow,
+    /// Deny the request
+    #[default]
+    Deny,
+}
+
+impl Decision {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl FromStr for Decision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "deny" => Ok(Self::Deny),
+            decision => {
+                bail!("unsupported policy decision: {decision:?}, did you mean 'allow' or 'deny'?")
+            }
+        }
+    }
+}
+
 /// The action being requested
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Hash)]
 pub enum RequestKind {
@@ -128,34 +172,39 @@ pub enum RequestBody {
     Unknown,
 }
 
+impl RequestKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::PerformInvocation => "performInvocation",
+            Self::StartComponent => "startComponent",
+            Self::StartProvider => "startProvider",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<&RequestBody> for RequestKind {
+    fn from(val: &RequestBody) -> RequestKind {
+        match val {
+            RequestBody::StartComponent(_) => RequestKind::StartComponent,
+            RequestBody::StartProvider(_) => RequestKind::StartProvider,
+            RequestBody::PerformInvocation(_) => RequestKind::PerformInvocation,
+            RequestBody::Unknown => RequestKind::Unknown,
+        }
+    }
+}
+
 impl From<&RequestBody> for RequestKey {
     fn from(val: &RequestBody) -> RequestKey {
-        match val {
-            RequestBody::StartComponent(ref req) => RequestKey {
-                kind: RequestKind::StartComponent,
-                cache_key: format!("{}_{}", req.component_id, req.image_ref),
-            },
-            RequestBody::StartProvider(ref req) => RequestKey {
-                kind: RequestKind::StartProvider,
-                cache_key: format!("{}_{}", req.provider_id, req.image_ref),
-            },
-            RequestBody::PerformInvocation(ref req) => RequestKey {
-                kind: RequestKind::PerformInvocation,
-                cache_key: format!(
-                    "{}_{}_{}_{}",
-                    req.target.component_id, req.target.image_ref, req.interface, req.function
-                ),
-            },
-            RequestBody::Unknown => RequestKey {
-                kind: RequestKind::Unknown,
-                cache_key: String::new(),
-            },
+        RequestKey {
+            kind: val.into(),
+            request: val.clone(),
         }
     }
 }
 
 /// A request for a policy decision
-#[derive(Serialize)]
+#[derive(Clone, Debug, Serialize)]
 struct Request {
     /// A unique request id. This value is returned in the response
     #[serde(rename = "requestId")]
@@ -171,15 +220,79 @@ struct Request {
     host: HostInfo,
 }
 
+/// Several requests for policy decisions, sent in a single message on the batch topic
+#[derive(Serialize)]
+struct BatchRequest<'a> {
+    requests: &'a [Request],
+}
+
+/// Policy decisions for a [`BatchRequest`]. Responses are matched to requests by request ID, so
+/// they may come in any order. Requests without a response are retried individually on the
+/// policy topic
+#[derive(Deserialize)]
+struct BatchResponse {
+    responses: Vec<Response>,
+}
+
+/// A request waiting for its batch to be sent, along with where to send its response. `None` is
+/// sent if the batch did not produce a response for the request
+type PendingRequest = (Request, oneshot::Sender<Option<Response>>);
+
+/// Held by the request sending a batch while it waits for more requests to join. If that request
+/// is cancelled, the pending requests are dropped, so that they fall back to individual requests
+/// and later requests start a new batch
+struct BatchGuard<'a>(Option<&'a Mutex<Vec<PendingRequest>>>);
+
+impl BatchGuard<'_> {
+    /// Takes the pending requests to send them
+    fn take(mut self) -> Vec<PendingRequest> {
+        self.0.take().map(take_pending).unwrap_or_default()
+    }
+}
+
+impl Drop for BatchGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(pending) = self.0.take() {
+            take_pending(pending);
+        }
+    }
+}
+
+fn take_pending(pending: &Mutex<Vec<PendingRequest>>) -> Vec<PendingRequest> {
+    std::mem::take(&mut *pending.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
 struct RequestKey {
     /// The kind of request being made
     kind: RequestKind,
-    /// Information about this request combined to form a unique string.
-    /// For example, a StartComponent request can be uniquely cached based
-    /// on the component_id and image_ref, so this cache_key is a concatenation
-    /// of those values
-    cache_key: String,
+    /// The full contents of the request. Decisions may depend on any of the request fields,
+    /// including annotations and claims, so all of them form part of the key
+    request: RequestBody,
+}
+
+/// A cached policy decision
+#[derive(Clone, Debug)]
+struct CachedDecision {
+    /// The decision returned by the policy service
+    response: Response,
+    /// When the decision was received
+    received_at: Instant,
+}
+
+impl CachedDecision {
+    fn new(response: Response) -> Self {
+        Self {
+            response,
+            received_at: Instant::now(),
+        }
+    }
+
+    /// Whether the decision is still fresh given the configured TTL. Without a TTL, decisions
+    /// stay valid until overridden on the policy changes topic
+    fn is_fresh(&self, ttl: Option<Duration>) -> bool {
+        ttl.map_or(true, |ttl| self.received_at.elapsed() < ttl)
+    }
 }
 
 /// A policy decision response
@@ -233,8 +346,16 @@ pub struct Manager {
     nats: async_nats::Client,
     host_info: HostInfo,
     policy_topic: Option<String>,
+    policy_batch_topic: Option<String>,
+    /// Requests waiting for the current batch to be sent
+    pending_batch: Mutex<Vec<PendingRequest>>,
     policy_timeout: Duration,
-    decision_cache: Arc<RwLock<HashMap<RequestKey, Response>>>,
+    retries: u32,
+    retry_backoff: Duration,
+    decision_ttl: Option<Duration>,
+    default_decision: Decision,
+    metrics: Arc<HostMetrics>,
+    decision_cache: Arc<RwLock<HashMap<RequestKey, CachedDecision>>>,
     request_to_key: Arc<RwLock<HashMap<String, RequestKey>>>,
     /// An abort handle for the policy changes subscription
     pub policy_changes: AbortHandle,
@@ -242,15 +363,33 @@ pub struct Manager {
 
 impl Manager {
     /// Construct a new policy manager. Can fail if policy_changes_topic is set but we fail to subscribe to it
-    #[instrument(skip(nats))]
+    ///
+    /// Decisions are cached for `policy_decision_ttl` if set, otherwise until they are overridden
+    /// on `policy_changes_topic`. Requests that time out or find no responders are retried up to
+    /// `policy_retries` times, doubling `policy_retry_backoff` between attempts. If the policy
+    /// service still does not answer, `policy_default_decision` is applied instead.
+    ///
+    /// If `policy_batch_topic` is set, requests made at about the same time are first sent
+    /// together in a single message on that topic, falling back to individual requests on
+    /// `policy_topic` for any request the batch did not produce a decision for
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(nats, metrics))]
     pub async fn new(
         nats: async_nats::Client,
         host_info: HostInfo,
         policy_topic: Option<String>,
         policy_timeout: Option<Duration>,
+        policy_retries: Option<u32>,
+        policy_retry_backoff: Option<Duration>,
         policy_changes_topic: Option<String>,
+        policy_batch_topic: Option<String>,
+        policy_decision_ttl: Option<Duration>,
+        policy_default_decision: Decision,
+        metrics: Arc<HostMetrics>,
     ) -> anyhow::Result<Arc<Self>> {
         const DEFAULT_POLICY_TIMEOUT: Duration = Duration::from_secs(1);
+        const DEFAULT_POLICY_RETRIES: u32 = 2;
+        const DEFAULT_POLICY_RETRY_BACKOFF: Duration = Duration::from_millis(100);
 
         let (policy_changes_abort, policy_changes_abort_reg) = AbortHandle::new_pair();
 
@@ -258,7 +397,14 @@ impl Manager {
             nats: nats.clone(),
             host_info,
             policy_topic,
+            policy_batch_topic,
+            pending_batch: Mutex::default(),
             policy_timeout: policy_timeout.unwrap_or(DEFAULT_POLICY_TIMEOUT),
+            retries: policy_retries.unwrap_or(DEFAULT_POLICY_RETRIES),
+            retry_backoff: policy_retry_backoff.unwrap_or(DEFAULT_POLICY_RETRY_BACKOFF),
+            decision_ttl: policy_decision_ttl,
+            default_decision: policy_default_decision,
+            metrics,
             decision_cache: Arc::default(),
             request_to_key: Arc::default(),
             policy_changes: policy_changes_abort,
@@ -365,78 +511,251 @@ impl Manager {
             });
         };
 
-        let kind = match request {
-            RequestBody::StartComponent(_) => RequestKind::StartComponent,
-            RequestBody::StartProvider(_) => RequestKind::StartProvider,
-            RequestBody::PerformInvocation(_) => RequestKind::PerformInvocation,
-            RequestBody::Unknown => RequestKind::Unknown,
-        };
+        let kind: RequestKind = (&request).into();
         let cache_key = (&request).into();
-        if let Some(entry) = self.decision_cache.read().await.get(&cache_key) {
+        if let Some(entry) = self.cached_decision(&cache_key).await {
             trace!(?cache_key, ?entry, "using cached policy decision");
-            return Ok(entry.clone());
+            return Ok(entry);
         }
 
         let request_id = Uuid::from_u128(Ulid::new().into()).to_string();
         trace!(?cache_key, "requesting policy decision");
-        let payload = serde_json::to_vec(&Request {
+        let request = Request {
             request_id: request_id.clone(),
             request,
             kind,
             version: POLICY_TYPE_VERSION.to_string(),
             host: self.host_info.clone(),
-        })
-        .context("failed to serialize policy request")?;
-        let request = async_nats::Request::new()
-            .payload(payload.into())
-            .timeout(Some(self.policy_timeout));
-        let res = self
-            .nats
-            .send_request(policy_topic, request)
-            .await
-            .context("policy request failed")?;
+        };
+        if let Some(decision) = self.request_batched(&request).await {
+            self.cache_decision(request_id, cache_key, &decision).await;
+            return Ok(decision);
+        }
+        let payload: Bytes = serde_json::to_vec(&request)
+            .context("failed to serialize policy request")?
+            .into();
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        let res = loop {
+            let request = async_nats::Request::new()
+                .payload(payload.clone())
+                .timeout(Some(self.policy_timeout));
+            match self.nats.send_request(policy_topic.clone(), request).await {
+                Ok(res) => break res,
+                // Only retry when the reply may have been lost in transit, other failures are
+                // unlikely to resolve themselves
+                Err(err)
+                    if attempt < self.retries
+                        && matches!(
+                            err.kind(),
+                            RequestErrorKind::TimedOut | RequestErrorKind::NoResponders
+                        ) =>
+                {
+                    attempt += 1;
+                    debug!(
+                        request_id,
+                        attempt,
+                        ?backoff,
+                        error = %err,
+                        "policy request failed, retrying"
+                    );
+                    sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(err) => return Ok(self.apply_default_decision(request_id, kind, &err)),
+            }
+        };
         let decision = serde_json::from_slice::<Response>(&res.payload)
             .context("failed to deserialize policy response")?;
+        self.cache_decision(request_id, cache_key, &decision).await;
+        Ok(decision)
+    }
 
+    /// Caches `decision` for requests matching `key`, remembering the ID of the request it was
+    /// made for so that it can be overridden on the policy changes topic
+    async fn cache_decision(&self, request_id: String, key: RequestKey, decision: &Response) {
         self.decision_cache
             .write()
             .await
-            .insert(cache_key.clone(), decision.clone()); // cache policy decision
-        self.request_to_key
-            .write()
-            .await
-            .insert(request_id, cache_key); // cache request id -> decision key
-        Ok(decision)
+            .insert(key.clone(), CachedDecision::new(decision.clone()));
+        self.request_to_key.write().await.insert(request_id, key);
+    }
+
+    /// Requests a decision for `request` as part of a batch on the batch topic, if configured.
+    /// Returns `None` if the batch did not produce a decision for the request, e.g. because the
+    /// policy service does not support batches or only answered some of the requests.
+    ///
+    /// The first request of a batch waits for [`POLICY_BATCH_WINDOW`] for more requests to join,
+    /// then sends the batch and hands out the responses. If it is cancelled in the meantime, the
+    /// other requests of the batch fall back to individual requests.
+    async fn request_batched(&self, request: &Request) -> Option<Response> {
+        let batch_topic = self.policy_batch_topic.as_ref()?;
+        let (tx, rx) = oneshot::channel();
+        let first = {
+            let mut pending = self
+                .pending_batch
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            pending.push((request.clone(), tx));
+            pending.len() == 1
+        };
+        if first {
+            let guard = BatchGuard(Some(&self.pending_batch));
+            sleep(POLICY_BATCH_WINDOW).await;
+            self.send_batch(batch_topic, guard.take()).await;
+        }
+        rx.await.ok().flatten()
+    }
+
+    /// Sends a batch of requests on `batch_topic` and hands out the responses to the requests.
+    /// Requests without a response are handed `None`
+    #[instrument(level = "trace", skip_all, fields(requests = batch.len()))]
+    async fn send_batch(&self, batch_topic: &str, batch: Vec<PendingRequest>) {
+        let (requests, mut senders): (Vec<_>, HashMap<_, _>) = batch
+            .into_iter()
+            .map(|(request, tx)| {
+                let id = request.request_id.clone();
+                (request, (id, tx))
+            })
+            .unzip();
+        let res = async {
+            let payload = serde_json::to_vec(&BatchRequest {
+                requests: &requests,
+            })
+            .context("failed to serialize policy batch request")?;
+            let request = async_nats::Request::new()
+                .payload(payload.into())
+                .timeout(Some(self.policy_timeout));
+            let res = self
+                .nats
+                .send_request(batch_topic.to_string(), request)
+                .await
+                .context("policy batch request failed")?;
+            serde_json::from_slice::<BatchResponse>(&res.payload)
+                .context("failed to deserialize policy batch response")
+        }
+        .await;
+        match res {
+            Ok(BatchResponse { responses }) => {
+                for response in responses {
+                    if let Some(tx) = senders.remove(&response.request_id) {
+                        let _ = tx.send(Some(response));
+                    }
+                }
+                if !senders.is_empty() {
+                    debug!(
+                        missing = senders.len(),
+                        "policy batch response is missing decisions, requesting them individually"
+                    );
+                }
+            }
+            Err(err) => {
+                debug!(
+                    ?err,
+                    "policy batch failed, requesting decisions individually"
+                );
+            }
+        }
+        for (_, tx) in senders {
+            let _ = tx.send(None);
+        }
+    }
+
+    /// Returns the configured default decision for a request the policy service did not answer.
+    /// Default decisions are not cached, so the policy service is consulted again on the next
+    /// request
+    fn apply_default_decision(
+        &self,
+        request_id: String,
+        kind: RequestKind,
+        err: &async_nats::RequestError,
+    ) -> Response {
+        let decision = self.default_decision;
+        warn!(
+            request_id,
+            kind = kind.as_str(),
+            ?decision,
+            error = %err,
+            "policy request failed, applying default policy decision"
+        );
+        self.metrics.record_policy_default_decision(&[
+            KeyValue::new("lattice", self.metrics.lattice_id.clone()),
+            KeyValue::new("host", self.metrics.host_id.clone()),
+            KeyValue::new("kind", kind.as_str()),
+            KeyValue::new("decision", decision.as_str()),
+        ]);
+        Response {
+            request_id,
+            permitted: decision == Decision::Allow,
+            message: Some(format!(
+                "policy service did not respond ({err}), applied default decision `{}`",
+                decision.as_str()
+            )),
+        }
+    }
+
+    /// Returns the cached decision for `key`, if any, evicting it if it has expired
+    async fn cached_decision(&self, key: &RequestKey) -> Option<Response> {
+        {
+            let decision_cache = self.decision_cache.read().await;
+            let entry = decision_cache.get(key)?;
+            if entry.is_fresh(self.decision_ttl) {
+                return Some(entry.response.clone());
+            }
+        }
+        let mut decision_cache = self.decision_cache.write().await;
+        if decision_cache
+            .get(key)
+            .is_some_and(|entry| !entry.is_fresh(self.decision_ttl))
+        {
+            trace!(?key, "evicting expired policy decision");
+            decision_cache.remove(key);
+            self.request_to_key
+                .write()
+                .await
+                .retain(|_, cached| cached != key);
+        }
+        None
+    }
+
+    /// Drops all cached decisions, forcing subsequent requests to consult the policy service
+    async fn invalidate_decisions(&self) {
+        self.decision_cache.write().await.clear();
+        self.request_to_key.write().await.clear();
     }
 
     #[instrument(skip(self))]
     async fn override_decision(&self, msg: async_nats::Message) -> anyhow::Result<()> {
-        let Response {
+        let Ok(Response {
             request_id,
             permitted,
             message,
-        } = serde_json::from_slice(&msg.payload)
-            .context("failed to deserialize policy decision override")?;
+        }) = serde_json::from_slice(&msg.payload)
+        else {
+            // Not an override of a specific decision, so any cached decision may be stale
+            debug!("received policy change, invalidating cached policy decisions");
+            self.invalidate_decisions().await;
+            return Ok(());
+        };
 
         debug!(request_id, "received policy decision override");
 
-        let mut decision_cache = self.decision_cache.write().await;
-        let request_to_key = self.request_to_key.read().await;
-
-        if let Some(key) = request_to_key.get(&request_id) {
-            decision_cache.insert(
-                key.clone(),
-                Response {
+        let key = self.request_to_key.read().await.get(&request_id).cloned();
+        if let Some(key) = key {
+            self.decision_cache.write().await.insert(
+                key,
+                CachedDecision::new(Response {
                     request_id: request_id.clone(),
                     permitted,
                     message,
-                },
+                }),
             );
         } else {
             warn!(
                 request_id,
-                "received policy decision override for unknown request id"
+                "received policy decision override for unknown request id, invalidating cached policy decisions"
             );
+            self.invalidate_decisions().await;
         }
 
         Ok(())
@@ -1,6 +1,7 @@
 //! Module with structs for use in managing and accessing secrets in a wasmCloud lattice
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, ensure, Context as _};
 use async_nats::{jetstream::kv::Store, Client};
@@ -8,51 +9,289 @@ use futures::stream;
 use futures::stream::{StreamExt, TryStreamExt};
 use secrecy::Secret;
 use tokio::sync::RwLock;
-use tracing::instrument;
+use tokio::time::timeout;
+use tracing::{debug, instrument, trace};
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_secrets_client::Client as WasmcloudSecretsClient;
 use wasmcloud_secrets_types::{Secret as WasmcloudSecret, SecretConfig};
 
+/// Topic prefixes of the secrets backends a host can fetch secrets from.
+///
+/// Secret references are routed by the scheme of their backend, that is the part of the backend
+/// name before the first `:` (e.g. `vault` for `vault:team-a`), or the whole name if it contains
+/// no `:`. References whose scheme has no topic prefix of its own use the default one.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TopicPrefix {
+    /// The topic prefix for secret references that do not match any scheme
+    pub default: Option<String>,
+    /// Topic prefixes keyed by secret reference scheme, e.g. `vault` or `k8s`
+    pub schemes: HashMap<String, String>,
+}
+
+impl TopicPrefix {
+    /// Sets the topic prefix used for secret references with the given scheme. A trailing `:` on
+    /// the scheme is ignored, so `vault:` and `vault` are equivalent
+    #[must_use]
+    pub fn with_scheme(mut self, scheme: impl AsRef<str>, prefix: impl Into<String>) -> Self {
+        self.schemes.insert(
+            scheme.as_ref().trim_end_matches(':').to_string(),
+            prefix.into(),
+        );
+        self
+    }
+
+    /// Returns whether any secrets backend is configured
+    #[must_use]
+    pub fn is_configured(&self) -> bool {
+        self.default.is_some() || !self.schemes.is_empty()
+    }
+
+    /// Returns all configured topic prefixes
+    pub fn prefixes(&self) -> impl Iterator<Item = &str> {
+        self.default
+            .iter()
+            .chain(self.schemes.values())
+            .map(String::as_str)
+    }
+
+    /// Returns the topic prefix to use for the given secrets backend, if any
+    #[must_use]
+    pub fn for_backend(&self, backend: &str) -> Option<&str> {
+        let scheme = backend
+            .split_once(':')
+            .map_or(backend, |(scheme, _)| scheme);
+        self.schemes
+            .get(scheme)
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+}
+
+impl From<String> for TopicPrefix {
+    fn from(prefix: String) -> Self {
+        Self {
+            default: Some(prefix),
+            schemes: HashMap::default(),
+        }
+    }
+}
+
+impl From<&str> for TopicPrefix {
+    fn from(prefix: &str) -> Self {
+        prefix.to_string().into()
+    }
+}
+
+impl From<Option<String>> for TopicPrefix {
+    fn from(prefix: Option<String>) -> Self {
+        prefix.map(Self::from).unwrap_or_default()
+    }
+}
+
+/// Identifies a secret fetched for a particular entity. Backends may decide access to a secret
+/// based on the requesting entity, so secrets are never shared between entities
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct CacheKey {
+    /// The backend the secret was fetched from
+    backend: String,
+    /// The serialized secret reference
+    reference: String,
+    /// The JWT of the entity the secret was fetched for
+    entity_jwt: String,
+    /// The application the entity is a part of, if any
+    application: Option<String>,
+}
+
+/// A cached secret. The secret value is zeroized when the entry is dropped
+#[derive(Debug)]
+struct CachedSecret {
+    secret: Secret<SecretValue>,
+    fetched_at: Instant,
+}
+
+/// An in-memory cache of resolved secrets, bounded in both age and size of its entries
+#[derive(Debug)]
+struct Cache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: RwLock<HashMap<CacheKey, CachedSecret>>,
+}
+
+impl Cache {
+    async fn get(&self, key: &CacheKey) -> Option<Secret<SecretValue>> {
+        {
+            let entries = self.entries.read().await;
+            let entry = entries.get(key)?;
+            if entry.fetched_at.elapsed() < self.ttl {
+                return Some(entry.secret.clone());
+            }
+        }
+        let mut entries = self.entries.write().await;
+        if entries
+            .get(key)
+            .is_some_and(|entry| entry.fetched_at.elapsed() >= self.ttl)
+        {
+            trace!(backend = key.backend, "evicting expired secret");
+            entries.remove(key);
+        }
+        None
+    }
+
+    async fn insert(&self, key: CacheKey, secret: Secret<SecretValue>) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.write().await;
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.fetched_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.fetched_at)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key,
+            CachedSecret {
+                secret,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    async fn invalidate(&self, backend: Option<&str>) -> usize {
+        let mut entries = self.entries.write().await;
+        let before = entries.len();
+        match backend {
+            Some(backend) => entries.retain(|key, _| key.backend != backend),
+            None => entries.clear(),
+        }
+        before - entries.len()
+    }
+}
+
 #[derive(Debug)]
 /// A manager for fetching secrets from a secret store, caching secrets clients for efficiency.
 pub struct Manager {
     config_store: Store,
-    /// The topic to use for configuring clients to fetch secrets from the secret store.
-    secret_store_topic: Option<String>,
+    /// The topics to use for configuring clients to fetch secrets from the secret stores.
+    secret_store_topic: TopicPrefix,
     nats_client: Client,
     /// A map of backend names, e.g. nats-kv or vault, to secrets clients, used to cache clients for efficiency.
     backend_clients: Arc<RwLock<HashMap<String, Arc<WasmcloudSecretsClient>>>>,
+    /// Resolved secrets, if caching is enabled
+    cache: Option<Cache>,
+    /// How long a request to a secrets backend may take
+    timeout: Duration,
 }
 
 impl Manager {
     /// Create a new secret manager with the given configuration store, secret store topic, and NATS client.
     ///
     /// All secret references will be fetched from this configuration store and the actual secrets will be
-    /// fetched by sending requests to the configured topic. If the provided secret_store_topic is None, this manager
+    /// fetched by sending requests to the topic configured for their backend. If no topic is configured, this manager
     /// will always return an error if [`Self::fetch_secrets`] is called with a list of secrets.
+    ///
+    /// If `cache_ttl` is set, resolved secrets are cached for that long, keeping at most
+    /// `cache_max_entries` secrets in memory. Requests to secrets backends fail if they take
+    /// longer than `timeout`.
     pub fn new(
         config_store: &Store,
-        secret_store_topic: Option<&String>,
+        secret_store_topic: &TopicPrefix,
         nats_client: &Client,
+        cache_ttl: Option<Duration>,
+        cache_max_entries: usize,
+        timeout: Duration,
     ) -> Self {
         Self {
             config_store: config_store.clone(),
-            secret_store_topic: secret_store_topic.cloned(),
+            secret_store_topic: secret_store_topic.clone(),
             nats_client: nats_client.clone(),
             backend_clients: Arc::new(RwLock::new(HashMap::new())),
+            cache: cache_ttl.map(|ttl| Cache {
+                ttl,
+                max_entries: cache_max_entries,
+                entries: RwLock::default(),
+            }),
+            timeout,
+        }
+    }
+
+    /// Checks that the secrets backends are reachable, by requesting the public key each known
+    /// backend encrypts secrets with. Backends are known if they have a topic prefix of their
+    /// own, or secrets were fetched from them before. Clients of reachable backends are replaced,
+    /// so that rotated backend keys are picked up.
+    ///
+    /// If only a default topic prefix is configured and no secrets were fetched yet, no backend is
+    /// known, so only the connection to NATS is checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first backend that did not respond within the timeout, or
+    /// responded with an invalid key
+    #[instrument(level = "debug", skip(self))]
+    pub async fn check_backends(&self) -> anyhow::Result<()> {
+        if !self.secret_store_topic.is_configured() {
+            return Ok(());
+        }
+        let mut backends: BTreeSet<String> =
+            self.secret_store_topic.schemes.keys().cloned().collect();
+        backends.extend(self.backend_clients.read().await.keys().cloned());
+        if backends.is_empty() {
+            return timeout(self.timeout, self.nats_client.flush())
+                .await
+                .context("timed out checking connection to secrets backends")?
+                .context("failed to check connection to secrets backends");
+        }
+        for backend in backends {
+            let prefix = self
+                .secret_store_topic
+                .for_backend(&backend)
+                .context("secret store not configured for backend")?;
+            let client = timeout(
+                self.timeout,
+                WasmcloudSecretsClient::new(&backend, prefix, self.nats_client.clone()),
+            )
+            .await
+            .with_context(|| format!("timed out checking secrets backend `{backend}`"))?
+            .with_context(|| format!("secrets backend `{backend}` is not reachable"))?;
+            self.backend_clients
+                .write()
+                .await
+                .insert(backend, Arc::new(client));
         }
+        Ok(())
+    }
+
+    /// Drops cached secrets, e.g. after they were rotated, so they are fetched from the secret
+    /// store again the next time they are requested. If `backend` is set, only secrets fetched
+    /// from that backend are dropped.
+    ///
+    /// Returns the number of secrets that were dropped.
+    #[instrument(level = "debug", skip(self))]
+    pub async fn invalidate_secrets(&self, backend: Option<&str>) -> usize {
+        let Some(cache) = &self.cache else {
+            return 0;
+        };
+        let invalidated = cache.invalidate(backend).await;
+        debug!(invalidated, "invalidated cached secrets");
+        invalidated
     }
 
     /// Get the secrets client for the provided backend, creating a new client if one does not already exist.
     ///
-    /// Returns an error if the secret store topic is not configured, or if the client could not be created.
+    /// Returns an error if no secret store topic is configured for the backend, or if the client could not be created.
     async fn get_or_create_secrets_client(
         &self,
         backend: &str,
     ) -> anyhow::Result<Arc<WasmcloudSecretsClient>> {
-        let Some(secret_store_topic) = self.secret_store_topic.as_ref() else {
+        let Some(secret_store_topic) = self.secret_store_topic.for_backend(backend) else {
             return Err(anyhow::anyhow!(
-                "secret store not configured, could not create secrets client"
+                "secret store not configured for backend `{backend}`, could not create secrets client"
             ));
         };
 
@@ -62,12 +301,16 @@ impl Manager {
             match self.backend_clients.read().await.get(backend) {
                 Some(existing) => return Ok(existing.clone()),
                 None => Arc::new(
-                    WasmcloudSecretsClient::new(
-                        backend,
-                        secret_store_topic,
-                        self.nats_client.clone(),
+                    timeout(
+                        self.timeout,
+                        WasmcloudSecretsClient::new(
+                            backend,
+                            secret_store_topic,
+                            self.nats_client.clone(),
+                        ),
                     )
                     .await
+                    .context("timed out creating secrets client")?
                     .context("failed to create secrets client")?,
                 ),
             }
@@ -109,7 +352,7 @@ impl Manager {
 
         // Attempting to fetch secrets without a secret store topic is always an error
         ensure!(
-            self.secret_store_topic.is_some(),
+            self.secret_store_topic.is_configured(),
             "secret store not configured, could not fetch secrets"
         );
 
@@ -128,45 +371,58 @@ impl Manager {
                     Err(e) => bail!(e),
                 }
             })
-            // Retrieve the actual secret from the secrets backend
+            // Retrieve the actual secret from the cache or the secrets backend
             .and_then(|secret_config| async move {
+                let secret_name = secret_config.name.clone();
+                let cache_key = match &self.cache {
+                    Some(cache) => {
+                        let key = CacheKey {
+                            backend: secret_config.backend.clone(),
+                            reference: serde_json::to_string(&secret_config)
+                                .context("failed to serialize secret reference")?,
+                            entity_jwt: entity_jwt.clone(),
+                            application: application.cloned(),
+                        };
+                        if let Some(secret) = cache.get(&key).await {
+                            trace!(secret_name, "using cached secret");
+                            return Ok((secret_name, secret));
+                        }
+                        Some(key)
+                    }
+                    None => None,
+                };
                 let secrets_client = self
                     .get_or_create_secrets_client(&secret_config.backend)
                     .await?;
-                let secret_name = secret_config.name.clone();
                 let request = secret_config.try_into_request(entity_jwt, host_jwt, application).context("failed to create secret request")?;
-                secrets_client
-                    .get(request, nkeys::XKey::new())
+                let secret = timeout(self.timeout, secrets_client.get(request, nkeys::XKey::new()))
                     .await
-                    .map(|secret| (secret_name, secret))
-                    .map_err(|e| anyhow::anyhow!(e))
-            })
-            // Build the map of secrets depending on if the secret is a string or bytes
-            .try_fold(HashMap::new(), |mut secrets, (secret_name, secret_result)| async move {
-                match secret_result {
-                    // NOTE(brooksmtownsend): We create this map using the `secret_name` passed in on from the secret reference
-                    // because that's the name that the component/provider will use to look up the secret.
+                    .with_context(|| format!("timed out fetching secret {secret_name}"))?
+                    .map_err(|e| anyhow::anyhow!(e))?;
+                // Build the secret depending on if the secret is a string or bytes
+                let secret = match secret {
                     WasmcloudSecret {
                         string_secret: Some(string_secret),
                         ..
-                    } => secrets.insert(
-                        secret_name,
-                        Secret::new(SecretValue::String(string_secret)),
-                    ),
+                    } => Secret::new(SecretValue::String(string_secret)),
                     WasmcloudSecret {
                         binary_secret: Some(binary_secret),
                         ..
-                    } => {
-                        secrets.insert(secret_name, Secret::new(SecretValue::Bytes(binary_secret)))
-                    }
+                    } => Secret::new(SecretValue::Bytes(binary_secret)),
                     WasmcloudSecret {
                         string_secret: None,
                         binary_secret: None,
                         ..
                     } => bail!("secret {secret_name} did not contain a value"),
                 };
-                Ok(secrets)
+                if let (Some(cache), Some(key)) = (&self.cache, cache_key) {
+                    cache.insert(key, secret.clone()).await;
+                }
+                Ok((secret_name, secret))
             })
+            // NOTE(brooksmtownsend): We create this map using the `secret_name` passed in on from the secret reference
+            // because that's the name that the component/provider will use to look up the secret.
+            .try_collect()
             .await?;
 
         Ok(secrets)
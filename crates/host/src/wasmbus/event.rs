@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
 use anyhow::Context;
 use cloudevents::{EventBuilder, EventBuilderV10};
@@ -201,6 +202,18 @@ pub fn provider_start_failed(
     })
 }
 
+pub fn provider_start_timed_out(
+    provider_ref: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+    timeout: Duration,
+) -> serde_json::Value {
+    json!({
+        "provider_ref": provider_ref.as_ref(),
+        "provider_id": provider_id.as_ref(),
+        "timeout_ms": u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX),
+    })
+}
+
 pub fn provider_stopped(
     annotations: &BTreeMap<String, String>,
     host_id: impl AsRef<str>,
@@ -3,7 +3,7 @@ use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
-use anyhow::{bail, Context as _};
+use anyhow::{bail, ensure, Context as _};
 use async_trait::async_trait;
 use bytes::Bytes;
 use secrecy::Secret;
@@ -58,6 +58,8 @@ pub struct Handler {
     pub instance_links: Arc<RwLock<HashMap<Box<str>, HashMap<Box<str>, Box<str>>>>>,
 
     pub invocation_timeout: Duration,
+    /// Maximum size in bytes of the parameters of an invocation
+    pub max_payload: Option<usize>,
 }
 
 impl Handler {
@@ -74,6 +76,7 @@ impl Handler {
             trace_ctx: Arc::default(),
             instance_links: self.instance_links.clone(),
             invocation_timeout: self.invocation_timeout,
+            max_payload: self.max_payload,
         }
     }
 }
@@ -169,6 +172,14 @@ impl wrpc_transport::Invoke for Handler {
     where
         P: AsRef<[Option<usize>]> + Send + Sync,
     {
+        if let Some(max_payload) = self.max_payload {
+            ensure!(
+                params.len() <= max_payload,
+                "parameters of `{func}` in instance `{instance}` are {} bytes, exceeding the maximum RPC payload of {max_payload} bytes",
+                params.len()
+            );
+        }
+
         // Reading a trace context should _never_ block because writing happens once at the beginning of a component
         // invocation. If it does block here, it's a bug in the runtime, and it's better to deal with a
         // disconnected trace than to block on the invocation for an extended period of time.
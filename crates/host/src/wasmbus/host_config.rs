@@ -1,49 +1,132 @@
-use crate::OciConfig;
+use crate::{OciConfig, PolicyDecision, SecretsTopicPrefix};
 
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use anyhow::{bail, Context as _};
 use nkeys::KeyPair;
 use url::Url;
-use wasmcloud_core::{logging::Level as LogLevel, OtelConfig};
+use wasmcloud_core::{
+    is_valid_trace_sampling_ratio, logging::Level as LogLevel, OtelConfig,
+    RESERVED_RESOURCE_ATTRIBUTES,
+};
 use wasmcloud_runtime::{MAX_COMPONENTS, MAX_COMPONENT_SIZE, MAX_LINEAR_MEMORY};
 
 /// wasmCloud Host configuration
+///
+/// With the `config` feature enabled, the configuration can be read from a file using
+/// [`Host::from_file`], with durations given as human-friendly strings like `"2s"` and key pairs
+/// as nkey seeds. Settings that are not present in the file take their default values.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, Debug)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct Host {
     /// NATS URL to connect to for control interface connection
     pub ctl_nats_url: Url,
+    /// Additional NATS URLs of the same cluster to fail over to, in order, if `ctl_nats_url` is
+    /// unreachable
+    pub ctl_nats_urls: Vec<Url>,
     /// Authentication JWT for control interface connection, must be specified with `ctl_key`
     pub ctl_jwt: Option<String>,
     /// Authentication key pair for control interface connection, must be specified with `ctl_jwt`
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::key_pair"))]
     pub ctl_key: Option<Arc<KeyPair>>,
     /// Whether to require TLS for control interface connection
     pub ctl_tls: bool,
+    /// CA certificates to trust for the control interface connection, in addition to the system
+    /// roots
+    pub ctl_tls_ca_path: Option<PathBuf>,
+    /// Client certificate to present to the NATS server of the control interface connection, must
+    /// be specified with `ctl_tls_client_key`
+    pub ctl_tls_client_cert: Option<PathBuf>,
+    /// Private key of `ctl_tls_client_cert`
+    pub ctl_tls_client_key: Option<PathBuf>,
     /// The topic prefix to use for control interface subscriptions, defaults to `wasmbus.ctl`
     pub ctl_topic_prefix: String,
+    /// The NATS queue group of the lattice-wide control interface subscriptions, i.e. links,
+    /// claims and configuration.
+    ///
+    /// Hosts in the same queue group share these requests, so each one is handled by exactly one
+    /// of them. Hosts in different queue groups each handle every request, which duplicates the
+    /// (idempotent) writes to the lattice data, but lets a group keep serving the control
+    /// interface independently of other groups. Host-specific requests, auctions and pings are
+    /// always delivered to every host. If unset, all hosts of a lattice share one queue group per
+    /// kind of request
+    pub ctl_queue_group: Option<String>,
     /// NATS URL to connect to for component RPC
     pub rpc_nats_url: Url,
+    /// Additional NATS URLs of the same cluster to fail over to, in order, if `rpc_nats_url` is
+    /// unreachable
+    pub rpc_nats_urls: Vec<Url>,
     /// Timeout period for all RPC calls
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::duration"))]
     pub rpc_timeout: Duration,
+    /// Maximum size in bytes of the parameters of an RPC call made by a component. Larger calls
+    /// fail before anything is sent. Only the NATS server limit applies if unset
+    pub rpc_max_payload: Option<usize>,
     /// Authentication JWT for RPC connection, must be specified with `rpc_seed`
     pub rpc_jwt: Option<String>,
     /// Authentication key pair for RPC connection, must be specified with `rpc_jwt`
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::key_pair"))]
     pub rpc_key: Option<Arc<KeyPair>>,
     /// Whether to require TLS for RPC connection
     pub rpc_tls: bool,
+    /// CA certificates to trust for the RPC connection, in addition to the system roots
+    pub rpc_tls_ca_path: Option<PathBuf>,
+    /// Client certificate to present to the NATS server of the RPC connection, must be specified
+    /// with `rpc_tls_client_key`
+    pub rpc_tls_client_cert: Option<PathBuf>,
+    /// Private key of `rpc_tls_client_cert`
+    pub rpc_tls_client_key: Option<PathBuf>,
     /// The lattice the host belongs to
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::arc_str"))]
     pub lattice: Arc<str>,
+    /// Settings overriding the top-level ones for specific lattices, keyed by lattice name.
+    /// The override of the lattice the host joins is applied when the host connects, see
+    /// [`Host::for_lattice`]
+    pub lattice_overrides: HashMap<String, LatticeConfig>,
     /// The domain to use for host Jetstream operations
     pub js_domain: Option<String>,
+    /// Name of the control interface and RPC NATS connections, as shown by the NATS server.
+    /// Defaults to `wasmcloud-host-<host public key>`
+    pub nats_connection_name: Option<String>,
+    /// Number of operations, e.g. published messages, the control interface and RPC NATS clients
+    /// each buffer while disconnected, after which they wait for the connection to be restored.
+    /// Buffered messages are kept in memory, so a large buffer may hold up to this many times the
+    /// maximum NATS payload per client. Defaults to the NATS client default of 2048
+    pub nats_reconnect_buffer_size: Option<usize>,
+    /// Number of consecutive attempts to reconnect to NATS before giving up. Unlimited if unset
+    pub nats_max_reconnects: Option<usize>,
+    /// Delay between attempts to reconnect to NATS. Defaults to the NATS client's exponential
+    /// backoff, capped at 4 seconds
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub nats_reconnect_delay: Option<Duration>,
     /// Labels (key-value pairs) to add to the host
     pub labels: HashMap<String, String>,
+    /// File to read additional labels from, with one `key=value` pair per line. The file is
+    /// watched for changes while the host runs. Labels from the file take precedence over
+    /// `labels`, and a label removed from the file reverts to its value in `labels`, if any
+    pub labels_file: Option<PathBuf>,
     /// The server key pair used by this host to generate its public key
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::key_pair"))]
     pub host_key: Option<Arc<KeyPair>>,
     /// The amount of time to wait for a provider to gracefully shut down before terminating it
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
     pub provider_shutdown_delay: Option<Duration>,
+    /// The amount of time to wait for a started provider to answer its first health check, after
+    /// which the provider is terminated and reported as failed. Defaults to 60 seconds. Providers
+    /// are not waited for if unset, which was the default before this timeout was introduced
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub provider_startup_timeout: Option<Duration>,
     /// Configuration for downloading artifacts from OCI registries
+    #[cfg_attr(feature = "config", serde(rename = "oci", alias = "oci_opts"))]
     pub oci_opts: OciConfig,
     /// Whether to allow loading component or provider components from the filesystem
     pub allow_file_load: bool,
@@ -54,35 +137,287 @@ pub struct Host {
     /// Whether to enable loading supplemental configuration
     pub config_service_enabled: bool,
     /// configuration for OpenTelemetry tracing
+    #[cfg_attr(feature = "config", serde(rename = "otel", alias = "otel_config"))]
     pub otel_config: OtelConfig,
     /// configuration for wasmCloud policy service
+    #[cfg_attr(
+        feature = "config",
+        serde(rename = "policy", alias = "policy_service_config")
+    )]
     pub policy_service_config: PolicyService,
-    /// topic for wasmCloud secrets backend
-    pub secrets_topic_prefix: Option<String>,
+    /// topic prefixes for wasmCloud secrets backends, either a single prefix or a mapping from
+    /// secret reference scheme to prefix
+    #[cfg_attr(
+        feature = "config",
+        serde(deserialize_with = "de::secrets_topic_prefix")
+    )]
+    pub secrets_topic_prefix: SecretsTopicPrefix,
+    /// How long resolved secrets are cached for. If unset, secrets are fetched from the secrets
+    /// backend every time they are requested
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub secrets_cache_ttl: Option<Duration>,
+    /// The maximum number of resolved secrets to cache
+    pub secrets_cache_max_entries: usize,
+    /// How long a request to a secrets backend may take, both when fetching secrets and when
+    /// checking that the backend is reachable
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::duration"))]
+    pub secrets_timeout: Duration,
+    /// The interval at which the host checks that the configured secrets backends are reachable,
+    /// see [`super::Host::check_secrets_backend`]. They are only checked at startup if unset
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub secrets_health_check_interval: Option<Duration>,
     /// The semver version of the host. This is used by a consumer of this crate to indicate the
     /// host version (which may differ from the crate version)
     pub version: String,
     /// The maximum execution time for a component instance
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::duration"))]
     pub max_execution_time: Duration,
     /// The maximum linear memory that a component instance can allocate
     pub max_linear_memory: u64,
     /// The maximum size of a component binary that can be loaded
     pub max_component_size: u64,
+    /// The maximum size of a provider archive that can be downloaded. Unlimited if unset
+    pub max_provider_size: Option<u64>,
     /// The maximum number of components that can be run simultaneously
     pub max_components: u32,
+    /// The number of pre-instantiated instances kept warm for each component, capped to the
+    /// component's maximum number of instances, so that invocations after a scale-up do not wait
+    /// for instantiation. Only `wasi:http/incoming-handler` and `wasmcloud:messaging/handler`
+    /// invocations use warm instances, so none are kept for components exporting neither. Warm
+    /// instances count towards `max_components` and use linear memory like running ones, so
+    /// across all components at most half of `max_components` are kept warm
+    pub warm_instances: u32,
+    /// Limits of specific components, keyed by component ID or image reference, with the
+    /// component ID taking precedence. The host-wide limits above are the ceiling of these, so
+    /// overrides can only tighten them, see [`Host::component_limits_for`]
+    pub component_limits: HashMap<String, ComponentLimits>,
+    /// The maximum number of components and providers that are downloaded at the same time
+    pub max_concurrent_downloads: usize,
     /// The interval at which the Host will send heartbeats
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
     pub heartbeat_interval: Option<Duration>,
+    /// Upper bound of a random delay added to the first heartbeat and to every interval after it,
+    /// so that hosts started at the same time do not heartbeat in lockstep. No jitter is applied
+    /// if unset
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub heartbeat_jitter: Option<Duration>,
+    /// The interval at which the trusted root certificates used to fetch OCI artifacts are
+    /// reloaded from the system and `oci_opts.additional_ca_paths`. They are only loaded at startup
+    /// if unset
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub trust_refresh_interval: Option<Duration>,
+}
+
+/// A problem with a [`Host`] configuration, as reported by [`Host::validate`]
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// `ctl_jwt` is set without `ctl_key`
+    #[error("`ctl_jwt` is set, but `ctl_key` is not")]
+    CtlJwtWithoutKey,
+    /// `ctl_key` is set without `ctl_jwt`
+    #[error("`ctl_key` is set, but `ctl_jwt` is not")]
+    CtlKeyWithoutJwt,
+    /// `rpc_jwt` is set without `rpc_key`
+    #[error("`rpc_jwt` is set, but `rpc_key` is not")]
+    RpcJwtWithoutKey,
+    /// `rpc_key` is set without `rpc_jwt`
+    #[error("`rpc_key` is set, but `rpc_jwt` is not")]
+    RpcKeyWithoutJwt,
+    /// The lattice is empty
+    #[error("`lattice` must not be empty")]
+    EmptyLattice,
+    /// `max_components` is zero, so no component could ever be started
+    #[error("`max_components` must be greater than zero")]
+    ZeroMaxComponents,
+    /// `max_concurrent_downloads` is zero, so no artifact could ever be downloaded
+    #[error("`max_concurrent_downloads` must be greater than zero")]
+    ZeroMaxConcurrentDownloads,
+    /// `ctl_tls_client_key` is set without `ctl_tls_client_cert`
+    #[error("`ctl_tls_client_key` is set, but `ctl_tls_client_cert` is not")]
+    CtlTlsKeyWithoutCert,
+    /// `ctl_tls_client_cert` is set without `ctl_tls_client_key`
+    #[error("`ctl_tls_client_cert` is set, but `ctl_tls_client_key` is not")]
+    CtlTlsCertWithoutKey,
+    /// `rpc_tls_client_key` is set without `rpc_tls_client_cert`
+    #[error("`rpc_tls_client_key` is set, but `rpc_tls_client_cert` is not")]
+    RpcTlsKeyWithoutCert,
+    /// `rpc_tls_client_cert` is set without `rpc_tls_client_key`
+    #[error("`rpc_tls_client_cert` is set, but `rpc_tls_client_key` is not")]
+    RpcTlsCertWithoutKey,
+    /// No NATS URL was given for the control interface connection
+    #[error("at least one control interface NATS URL is required")]
+    NoCtlNatsUrls,
+    /// `secrets_cache_ttl` is set, but `secrets_cache_max_entries` is zero, so no secret could
+    /// ever be cached
+    #[error("`secrets_cache_ttl` is set, but `secrets_cache_max_entries` is zero")]
+    ZeroSecretsCacheMaxEntries,
+    /// `rpc_max_payload` is zero, so no RPC call could ever be made
+    #[error("`rpc_max_payload` must be greater than zero")]
+    ZeroRpcMaxPayload,
+    /// `secrets_timeout` is zero, so no request to a secrets backend could ever succeed
+    #[error("`secrets_timeout` must be greater than zero")]
+    ZeroSecretsTimeout,
+    /// `nats_reconnect_buffer_size` is zero, so the NATS clients could not send anything
+    #[error("`nats_reconnect_buffer_size` must be greater than zero")]
+    ZeroNatsReconnectBufferSize,
+    /// A label is invalid
+    #[error("invalid label `{key}`: {reason}")]
+    InvalidLabel {
+        /// The key of the label
+        key: String,
+        /// Why the label is invalid
+        reason: String,
+    },
+    /// No NATS URL was given for the RPC connection
+    #[error("at least one RPC NATS URL is required")]
+    NoRpcNatsUrls,
+    /// The OpenTelemetry trace sampling ratio is not within 0.0 and 1.0
+    #[error("`otel.trace_sampling_ratio` must be within 0.0 and 1.0")]
+    InvalidTraceSamplingRatio,
+    /// An OpenTelemetry resource attribute that is set by wasmCloud itself is configured
+    #[error("`otel.resource_attributes` must not set the reserved attribute `{0}`")]
+    ReservedResourceAttribute(String),
+    /// `provider_startup_timeout` is zero, so every started provider would time out immediately
+    #[error(
+        "`provider_startup_timeout` must be greater than zero, unset it to not wait for providers"
+    )]
+    ZeroProviderStartupTimeout,
+    /// `secrets_health_check_interval` is zero, which cannot be used as the period of the checks
+    #[error(
+        "`secrets_health_check_interval` must be greater than zero, unset it to disable periodic checks"
+    )]
+    ZeroSecretsHealthCheckInterval,
+    /// `trust_refresh_interval` is zero, which cannot be used as the period of the refresh
+    #[error("`trust_refresh_interval` must be greater than zero, unset it to disable refreshes")]
+    ZeroTrustRefreshInterval,
+}
+
+/// Resource limits of a component, overriding the host-wide limits of a [`Host`] configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ComponentLimits {
+    /// The maximum execution time for an instance of the component
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub max_execution_time: Option<Duration>,
+    /// The maximum linear memory that an instance of the component can allocate
+    pub max_linear_memory: Option<u64>,
+    /// The maximum size of the component binary
+    pub max_component_size: Option<u64>,
+    /// The maximum number of instances the component can be scaled to. Unlimited by default, in
+    /// which case only `max_components` applies
+    pub max_instances: Option<u32>,
+}
+
+/// Settings of a [`Host`] configuration that can be overridden per lattice. Settings that are
+/// not overridden take the value of the top-level configuration
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct LatticeConfig {
+    /// Configuration for downloading artifacts from OCI registries, replacing `oci_opts`
+    #[cfg_attr(feature = "config", serde(rename = "oci", alias = "oci_opts"))]
+    pub oci_opts: Option<OciConfig>,
+    /// Configuration for the wasmCloud policy service, replacing `policy_service_config`
+    #[cfg_attr(
+        feature = "config",
+        serde(rename = "policy", alias = "policy_service_config")
+    )]
+    pub policy_service_config: Option<PolicyService>,
+    /// Labels to add to the host in addition to `labels`, taking precedence over labels with the
+    /// same key
+    pub labels: HashMap<String, String>,
+}
+
+/// Prefix of label keys reserved for labels set by wasmCloud itself
+pub const RESERVED_LABEL_PREFIX: &str = "wasmcloud.";
+
+/// Label set to `true` by [`crate::wasmbus::Host::drain`] to mark a host that no longer accepts new
+/// work, so that schedulers place workloads elsewhere
+pub const UNSCHEDULABLE_LABEL: &str = "wasmcloud.unschedulable";
+
+/// Keys of the labels every host sets from the platform it runs on: the CPU architecture, the
+/// operating system and the operating system family, as named by [`std::env::consts`]. They
+/// cannot be set, changed or removed by users
+pub const BUILTIN_LABELS: [&str; 3] = ["hostcore.arch", "hostcore.os", "hostcore.osfamily"];
+
+/// Checks that a host label has a non-empty key made of ASCII letters, digits, `-`, `_`, `.` and
+/// `/` that does not start with [`RESERVED_LABEL_PREFIX`] and is not one of [`BUILTIN_LABELS`],
+/// and a non-empty value without control characters
+///
+/// # Errors
+///
+/// Returns [`ConfigError::InvalidLabel`] describing why the label is invalid
+pub fn validate_label(key: &str, value: &str) -> Result<(), ConfigError> {
+    let reason = if key.is_empty() {
+        "the key is empty"
+    } else if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+    {
+        "the key may only contain ASCII letters, digits, `-`, `_`, `.` and `/`"
+    } else if key.starts_with(RESERVED_LABEL_PREFIX) {
+        "keys starting with `wasmcloud.` are reserved"
+    } else if BUILTIN_LABELS.contains(&key) {
+        "the label is set by the host"
+    } else if value.is_empty() {
+        "the value is empty"
+    } else if value.chars().any(char::is_control) {
+        "the value contains control characters"
+    } else {
+        return Ok(());
+    };
+    Err(ConfigError::InvalidLabel {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    })
 }
 
 /// Configuration for wasmCloud policy service
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(
+    feature = "config",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct PolicyService {
     /// The topic to request policy decisions on
     pub policy_topic: Option<String>,
     /// An optional topic to receive updated policy decisions on
     pub policy_changes_topic: Option<String>,
+    /// An optional topic to request several policy decisions on in a single message. Decisions
+    /// requested at about the same time, e.g. while scaling many components, are batched if set
+    pub policy_batch_topic: Option<String>,
     /// The timeout for policy requests
+    #[cfg_attr(
+        feature = "config",
+        serde(
+            rename = "policy_timeout",
+            alias = "policy_timeout_ms",
+            deserialize_with = "de::optional_duration"
+        )
+    )]
     pub policy_timeout_ms: Option<Duration>,
+    /// How many times to retry policy requests that time out or find no responders. Defaults to 2
+    pub policy_retries: Option<u32>,
+    /// The delay before the first retry of a policy request, doubled for every subsequent retry.
+    /// Defaults to 100 milliseconds
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub policy_retry_backoff: Option<Duration>,
+    /// How long policy decisions are cached for. If unset, decisions are cached until they are
+    /// overridden on `policy_changes_topic`
+    #[cfg_attr(feature = "config", serde(deserialize_with = "de::optional_duration"))]
+    pub policy_decision_ttl: Option<Duration>,
+    /// The decision applied when the policy service does not respond in time or is unavailable.
+    /// Defaults to [`PolicyDecision::Deny`]
+    pub policy_default_decision: PolicyDecision,
 }
 
 impl Default for Host {
@@ -90,21 +425,38 @@ impl Default for Host {
         Self {
             ctl_nats_url: Url::parse("nats://localhost:4222")
                 .expect("failed to parse control NATS URL"),
+            ctl_nats_urls: Vec::default(),
             ctl_jwt: None,
             ctl_key: None,
             ctl_tls: false,
+            ctl_tls_ca_path: None,
+            ctl_tls_client_cert: None,
+            ctl_tls_client_key: None,
             ctl_topic_prefix: "wasmbus.ctl".to_string(),
+            ctl_queue_group: None,
             rpc_nats_url: Url::parse("nats://localhost:4222")
                 .expect("failed to parse RPC NATS URL"),
+            rpc_nats_urls: Vec::default(),
             rpc_timeout: Duration::from_millis(2000),
+            rpc_max_payload: None,
             rpc_jwt: None,
             rpc_key: None,
             rpc_tls: false,
+            rpc_tls_ca_path: None,
+            rpc_tls_client_cert: None,
+            rpc_tls_client_key: None,
             lattice: "default".into(),
+            lattice_overrides: HashMap::default(),
             js_domain: None,
+            nats_connection_name: None,
+            nats_reconnect_buffer_size: None,
+            nats_max_reconnects: None,
+            nats_reconnect_delay: None,
             labels: HashMap::default(),
+            labels_file: None,
             host_key: None,
             provider_shutdown_delay: None,
+            provider_startup_timeout: Some(Duration::from_secs(60)),
             oci_opts: OciConfig::default(),
             allow_file_load: false,
             enable_structured_logging: false,
@@ -112,15 +464,1669 @@ impl Default for Host {
             config_service_enabled: false,
             otel_config: OtelConfig::default(),
             policy_service_config: PolicyService::default(),
-            secrets_topic_prefix: None,
+            secrets_topic_prefix: SecretsTopicPrefix::default(),
+            secrets_cache_ttl: None,
+            secrets_cache_max_entries: 1024,
+            secrets_timeout: Duration::from_secs(10),
+            secrets_health_check_interval: Some(Duration::from_secs(30)),
             version: env!("CARGO_PKG_VERSION").to_string(),
             max_execution_time: Duration::from_millis(10 * 60 * 1000),
             // 10 MB
             max_linear_memory: MAX_LINEAR_MEMORY,
             // 50 MB
             max_component_size: MAX_COMPONENT_SIZE,
+            max_provider_size: None,
             max_components: MAX_COMPONENTS,
+            warm_instances: 0,
+            component_limits: HashMap::default(),
+            max_concurrent_downloads: 4,
             heartbeat_interval: None,
+            heartbeat_jitter: None,
+            trust_refresh_interval: None,
+        }
+    }
+}
+
+impl Host {
+    /// Returns a [`HostBuilder`] to configure a host, starting from the defaults
+    #[must_use]
+    pub fn builder() -> HostBuilder {
+        HostBuilder::default()
+    }
+
+    /// Reads a host configuration from the TOML or JSON file at `path`, depending on its
+    /// extension, and validates it. Unknown settings are rejected, so typos are caught early.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed, or if the configuration is invalid
+    #[cfg(feature = "config")]
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read `{}`", path.display()))?;
+        let config: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .with_context(|| format!("failed to parse `{}` as TOML", path.display()))?,
+            Some("json") => serde_json::from_str(&content)
+                .with_context(|| format!("failed to parse `{}` as JSON", path.display()))?,
+            _ => bail!(
+                "unsupported configuration file `{}`, expected a `.toml` or `.json` file",
+                path.display()
+            ),
+        };
+        if let Err(errors) = config.validate() {
+            let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+            bail!(
+                "invalid host configuration in `{}`: {}",
+                path.display(),
+                errors.join(", ")
+            );
+        }
+        Ok(config)
+    }
+
+    /// Reads a host configuration from `WASMCLOUD_`-prefixed environment variables, using the
+    /// same variables as the `wasmcloud` binary. Settings without a variable set keep their
+    /// default values. The recognized variables are listed below and returned by
+    /// [`Host::env_var_names`]:
+    ///
+    /// | Variable | Setting |
+    /// |----------|---------|
+    /// | `WASMCLOUD_NATS_HOST`, `WASMCLOUD_NATS_PORT` | Default host and port of both NATS connections |
+    /// | `WASMCLOUD_CTL_HOST`, `WASMCLOUD_CTL_PORT` | `ctl_nats_url` |
+    /// | `WASMCLOUD_CTL_JWT`, `WASMCLOUD_CTL_SEED` | `ctl_jwt`, `ctl_key` |
+    /// | `WASMCLOUD_CTL_TLS` | `ctl_tls` |
+    /// | `WASMCLOUD_CTL_TLS_CA_PATH` | `ctl_tls_ca_path` |
+    /// | `WASMCLOUD_CTL_TLS_CLIENT_CERT`, `WASMCLOUD_CTL_TLS_CLIENT_KEY` | `ctl_tls_client_cert`, `ctl_tls_client_key` |
+    /// | `WASMCLOUD_CTL_TOPIC_PREFIX` | `ctl_topic_prefix` |
+    /// | `WASMCLOUD_CTL_QUEUE_GROUP` | `ctl_queue_group` |
+    /// | `WASMCLOUD_RPC_HOST`, `WASMCLOUD_RPC_PORT` | `rpc_nats_url` |
+    /// | `WASMCLOUD_RPC_JWT`, `WASMCLOUD_RPC_SEED` | `rpc_jwt`, `rpc_key` |
+    /// | `WASMCLOUD_RPC_TLS` | `rpc_tls` |
+    /// | `WASMCLOUD_RPC_TLS_CA_PATH` | `rpc_tls_ca_path` |
+    /// | `WASMCLOUD_RPC_TLS_CLIENT_CERT`, `WASMCLOUD_RPC_TLS_CLIENT_KEY` | `rpc_tls_client_cert`, `rpc_tls_client_key` |
+    /// | `WASMCLOUD_RPC_TIMEOUT_MS` | `rpc_timeout` |
+    /// | `WASMCLOUD_RPC_MAX_PAYLOAD` | `rpc_max_payload`, in bytes |
+    /// | `WASMCLOUD_LATTICE` | `lattice` |
+    /// | `WASMCLOUD_JS_DOMAIN` | `js_domain` |
+    /// | `WASMCLOUD_NATS_CONNECTION_NAME` | `nats_connection_name` |
+    /// | `WASMCLOUD_NATS_RECONNECT_BUFFER_SIZE` | `nats_reconnect_buffer_size` |
+    /// | `WASMCLOUD_NATS_MAX_RECONNECTS` | `nats_max_reconnects` |
+    /// | `WASMCLOUD_NATS_RECONNECT_DELAY_MS` | `nats_reconnect_delay` |
+    /// | `WASMCLOUD_LABEL_<key>` | `labels`, one variable per label |
+    /// | `WASMCLOUD_LABELS_FILE` | `labels_file` |
+    /// | `WASMCLOUD_HOST_SEED` | `host_key` |
+    /// | `WASMCLOUD_PROV_SHUTDOWN_DELAY_MS` | `provider_shutdown_delay` |
+    /// | `WASMCLOUD_PROV_STARTUP_TIMEOUT_MS` | `provider_startup_timeout` |
+    /// | `WASMCLOUD_OCI_ALLOW_LATEST` | `oci_opts.allow_latest` |
+    /// | `WASMCLOUD_OCI_ALLOWED_INSECURE` | `oci_opts.allowed_insecure`, comma-separated |
+    /// | `WASMCLOUD_OCI_PULL_POLICY` | `oci_opts.pull_policy` |
+    /// | `WASMCLOUD_OCI_DEFAULT_REGISTRY` | `oci_opts.default_registry` |
+    /// | `WASMCLOUD_OCI_REGISTRY` | `oci_opts.oci_registry` |
+    /// | `WASMCLOUD_OCI_REGISTRY_USER` | `oci_opts.oci_user` |
+    /// | `WASMCLOUD_OCI_REGISTRY_PASSWORD` | `oci_opts.oci_password` |
+    /// | `WASMCLOUD_ALLOW_FILE_LOAD` | `allow_file_load` |
+    /// | `WASMCLOUD_STRUCTURED_LOGGING_ENABLED` | `enable_structured_logging` |
+    /// | `WASMCLOUD_LOG_LEVEL` | `log_level` |
+    /// | `WASMCLOUD_CONFIG_SERVICE` | `config_service_enabled` |
+    /// | `WASMCLOUD_POLICY_TOPIC` | `policy_service_config.policy_topic` |
+    /// | `WASMCLOUD_POLICY_CHANGES_TOPIC` | `policy_service_config.policy_changes_topic` |
+    /// | `WASMCLOUD_POLICY_BATCH_TOPIC` | `policy_service_config.policy_batch_topic` |
+    /// | `WASMCLOUD_POLICY_TIMEOUT` | `policy_service_config.policy_timeout_ms`, in milliseconds |
+    /// | `WASMCLOUD_POLICY_RETRIES` | `policy_service_config.policy_retries` |
+    /// | `WASMCLOUD_POLICY_RETRY_BACKOFF_MS` | `policy_service_config.policy_retry_backoff`, in milliseconds |
+    /// | `WASMCLOUD_POLICY_DECISION_TTL_MS` | `policy_service_config.policy_decision_ttl`, in milliseconds |
+    /// | `WASMCLOUD_POLICY_DEFAULT_DECISION` | `policy_service_config.policy_default_decision`, `allow` or `deny` |
+    /// | `WASMCLOUD_SECRETS_TOPIC` | `secrets_topic_prefix.default` |
+    /// | `WASMCLOUD_SECRETS_BACKEND_TOPICS` | `secrets_topic_prefix.schemes`, comma-separated `scheme=prefix` pairs |
+    /// | `WASMCLOUD_SECRETS_CACHE_TTL_MS` | `secrets_cache_ttl`, in milliseconds |
+    /// | `WASMCLOUD_SECRETS_CACHE_MAX_ENTRIES` | `secrets_cache_max_entries` |
+    /// | `WASMCLOUD_SECRETS_TIMEOUT_MS` | `secrets_timeout` |
+    /// | `WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL` | `secrets_health_check_interval`, in seconds, `0` disables periodic checks |
+    /// | `WASMCLOUD_MAX_EXECUTION_TIME_MS` | `max_execution_time` |
+    /// | `WASMCLOUD_MAX_LINEAR_MEMORY` | `max_linear_memory` |
+    /// | `WASMCLOUD_MAX_COMPONENT_SIZE` | `max_component_size` |
+    /// | `WASMCLOUD_MAX_PROVIDER_SIZE` | `max_provider_size` |
+    /// | `WASMCLOUD_MAX_COMPONENTS` | `max_components` |
+    /// | `WASMCLOUD_WARM_INSTANCES` | `warm_instances` |
+    /// | `WASMCLOUD_MAX_CONCURRENT_DOWNLOADS` | `max_concurrent_downloads` |
+    /// | `WASMCLOUD_HEARTBEAT_INTERVAL` | `heartbeat_interval`, in seconds |
+    /// | `WASMCLOUD_HEARTBEAT_JITTER_MS` | `heartbeat_jitter` |
+    /// | `WASMCLOUD_TRUST_REFRESH_INTERVAL` | `trust_refresh_interval`, in seconds |
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the variable if a variable cannot be parsed, or if the
+    /// configuration is invalid
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Returns the environment variables read by [`Host::from_env`] as pairs of setting and
+    /// variable, in the order of the settings. Settings read from several variables appear once
+    /// per variable, settings that cannot be set from the environment do not appear
+    #[must_use]
+    pub fn env_var_names() -> Vec<(&'static str, &'static str)> {
+        ENV_VARS
+            .iter()
+            .flat_map(|(setting, vars)| vars.iter().map(move |var| (*setting, *var)))
+            .collect()
+    }
+
+    /// Reads a host configuration from the given environment variables, see [`Host::from_env`]
+    fn from_vars(vars: impl IntoIterator<Item = (String, String)>) -> anyhow::Result<Self> {
+        let mut labels = HashMap::new();
+        let mut vars: HashMap<_, _> = vars
+            .into_iter()
+            .filter_map(|(key, value)| {
+                if let Some(label) = key.strip_prefix("WASMCLOUD_LABEL_") {
+                    labels.insert(label.to_string(), value);
+                    return None;
+                }
+                key.starts_with("WASMCLOUD_").then_some((key, value))
+            })
+            .collect();
+        let mut var = |name: &str| {
+            debug_assert!(
+                ENV_VARS.iter().any(|(_, vars)| vars.contains(&name)),
+                "`{name}` is missing from `ENV_VARS`"
+            );
+            vars.remove(name).filter(|value| !value.is_empty())
+        };
+        fn parse<T>(name: &str, value: Option<String>) -> anyhow::Result<Option<T>>
+        where
+            T: FromStr,
+            T::Err: std::fmt::Display,
+        {
+            value
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|e| anyhow::anyhow!("{e}"))
+                        .with_context(|| format!("invalid value `{value}` for `{name}`"))
+                })
+                .transpose()
+        }
+        fn flag(name: &str, value: Option<String>) -> anyhow::Result<Option<bool>> {
+            value
+                .map(|value| match value.to_ascii_lowercase().as_str() {
+                    "true" | "1" | "yes" | "on" => Ok(true),
+                    "false" | "0" | "no" | "off" => Ok(false),
+                    _ => bail!("invalid value `{value}` for `{name}`, expected a boolean"),
+                })
+                .transpose()
+        }
+        fn key(name: &str, value: Option<String>) -> anyhow::Result<Option<Arc<KeyPair>>> {
+            value
+                .map(|seed| {
+                    KeyPair::from_seed(&seed)
+                        .map(Arc::new)
+                        .with_context(|| format!("invalid nkey seed in `{name}`"))
+                })
+                .transpose()
+        }
+        fn millis(name: &str, value: Option<String>) -> anyhow::Result<Option<Duration>> {
+            Ok(parse(name, value)?.map(Duration::from_millis))
+        }
+
+        let mut config = Self::default();
+        let nats_host = var("WASMCLOUD_NATS_HOST");
+        let nats_port: Option<u16> = parse("WASMCLOUD_NATS_PORT", var("WASMCLOUD_NATS_PORT"))?;
+        for (connection, url) in [
+            ("CTL", &mut config.ctl_nats_url),
+            ("RPC", &mut config.rpc_nats_url),
+        ] {
+            let host_var = format!("WASMCLOUD_{connection}_HOST");
+            let port_var = format!("WASMCLOUD_{connection}_PORT");
+            let host = var(&host_var).or_else(|| nats_host.clone());
+            let port: Option<u16> = parse(&port_var, var(&port_var))?.or(nats_port);
+            if host.is_some() || port.is_some() {
+                let host = host.as_deref().unwrap_or("localhost");
+                let port = port.unwrap_or(4222);
+                *url = Url::parse(&format!("nats://{host}:{port}")).with_context(|| {
+                    format!("invalid NATS URL from `{host_var}` and `{port_var}`")
+                })?;
+            }
+        }
+        config.ctl_jwt = var("WASMCLOUD_CTL_JWT");
+        config.ctl_key = key("WASMCLOUD_CTL_SEED", var("WASMCLOUD_CTL_SEED"))?;
+        if let Some(tls) = flag("WASMCLOUD_CTL_TLS", var("WASMCLOUD_CTL_TLS"))? {
+            config.ctl_tls = tls;
+        }
+        config.ctl_tls_ca_path = var("WASMCLOUD_CTL_TLS_CA_PATH").map(PathBuf::from);
+        config.ctl_tls_client_cert = var("WASMCLOUD_CTL_TLS_CLIENT_CERT").map(PathBuf::from);
+        config.ctl_tls_client_key = var("WASMCLOUD_CTL_TLS_CLIENT_KEY").map(PathBuf::from);
+        if let Some(prefix) = var("WASMCLOUD_CTL_TOPIC_PREFIX") {
+            config.ctl_topic_prefix = prefix;
+        }
+        config.ctl_queue_group = var("WASMCLOUD_CTL_QUEUE_GROUP");
+        config.rpc_jwt = var("WASMCLOUD_RPC_JWT");
+        config.rpc_key = key("WASMCLOUD_RPC_SEED", var("WASMCLOUD_RPC_SEED"))?;
+        if let Some(tls) = flag("WASMCLOUD_RPC_TLS", var("WASMCLOUD_RPC_TLS"))? {
+            config.rpc_tls = tls;
+        }
+        config.rpc_tls_ca_path = var("WASMCLOUD_RPC_TLS_CA_PATH").map(PathBuf::from);
+        config.rpc_tls_client_cert = var("WASMCLOUD_RPC_TLS_CLIENT_CERT").map(PathBuf::from);
+        config.rpc_tls_client_key = var("WASMCLOUD_RPC_TLS_CLIENT_KEY").map(PathBuf::from);
+        if let Some(timeout) = millis("WASMCLOUD_RPC_TIMEOUT_MS", var("WASMCLOUD_RPC_TIMEOUT_MS"))?
+        {
+            config.rpc_timeout = timeout;
+        }
+        config.rpc_max_payload = parse(
+            "WASMCLOUD_RPC_MAX_PAYLOAD",
+            var("WASMCLOUD_RPC_MAX_PAYLOAD"),
+        )?;
+        if let Some(lattice) = var("WASMCLOUD_LATTICE") {
+            config.lattice = lattice.into();
+        }
+        config.js_domain = var("WASMCLOUD_JS_DOMAIN");
+        config.nats_connection_name = var("WASMCLOUD_NATS_CONNECTION_NAME");
+        config.nats_reconnect_buffer_size = parse(
+            "WASMCLOUD_NATS_RECONNECT_BUFFER_SIZE",
+            var("WASMCLOUD_NATS_RECONNECT_BUFFER_SIZE"),
+        )?;
+        config.nats_max_reconnects = parse(
+            "WASMCLOUD_NATS_MAX_RECONNECTS",
+            var("WASMCLOUD_NATS_MAX_RECONNECTS"),
+        )?;
+        config.nats_reconnect_delay = millis(
+            "WASMCLOUD_NATS_RECONNECT_DELAY_MS",
+            var("WASMCLOUD_NATS_RECONNECT_DELAY_MS"),
+        )?;
+        config.labels = labels;
+        config.labels_file = var("WASMCLOUD_LABELS_FILE").map(PathBuf::from);
+        config.host_key = key("WASMCLOUD_HOST_SEED", var("WASMCLOUD_HOST_SEED"))?;
+        config.provider_shutdown_delay = millis(
+            "WASMCLOUD_PROV_SHUTDOWN_DELAY_MS",
+            var("WASMCLOUD_PROV_SHUTDOWN_DELAY_MS"),
+        )?;
+        if let Some(timeout) = millis(
+            "WASMCLOUD_PROV_STARTUP_TIMEOUT_MS",
+            var("WASMCLOUD_PROV_STARTUP_TIMEOUT_MS"),
+        )? {
+            config.provider_startup_timeout = Some(timeout);
+        }
+        if let Some(allow) = flag(
+            "WASMCLOUD_OCI_ALLOW_LATEST",
+            var("WASMCLOUD_OCI_ALLOW_LATEST"),
+        )? {
+            config.oci_opts.allow_latest = allow;
+        }
+        if let Some(insecure) = var("WASMCLOUD_OCI_ALLOWED_INSECURE") {
+            config.oci_opts.allowed_insecure = insecure
+                .split(',')
+                .map(str::trim)
+                .filter(|registry| !registry.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+        if let Some(policy) = parse(
+            "WASMCLOUD_OCI_PULL_POLICY",
+            var("WASMCLOUD_OCI_PULL_POLICY"),
+        )? {
+            config.oci_opts.pull_policy = policy;
+        }
+        config.oci_opts.default_registry = var("WASMCLOUD_OCI_DEFAULT_REGISTRY");
+        config.oci_opts.oci_registry = var("WASMCLOUD_OCI_REGISTRY");
+        config.oci_opts.oci_user = var("WASMCLOUD_OCI_REGISTRY_USER");
+        config.oci_opts.oci_password = var("WASMCLOUD_OCI_REGISTRY_PASSWORD");
+        if let Some(allow) = flag(
+            "WASMCLOUD_ALLOW_FILE_LOAD",
+            var("WASMCLOUD_ALLOW_FILE_LOAD"),
+        )? {
+            config.allow_file_load = allow;
+        }
+        if let Some(enable) = flag(
+            "WASMCLOUD_STRUCTURED_LOGGING_ENABLED",
+            var("WASMCLOUD_STRUCTURED_LOGGING_ENABLED"),
+        )? {
+            config.enable_structured_logging = enable;
+        }
+        if let Some(level) =
+            parse::<tracing::Level>("WASMCLOUD_LOG_LEVEL", var("WASMCLOUD_LOG_LEVEL"))?
+        {
+            config.log_level = level.into();
+        }
+        if let Some(enabled) = flag("WASMCLOUD_CONFIG_SERVICE", var("WASMCLOUD_CONFIG_SERVICE"))? {
+            config.config_service_enabled = enabled;
+        }
+        config.policy_service_config = PolicyService {
+            policy_topic: var("WASMCLOUD_POLICY_TOPIC"),
+            policy_changes_topic: var("WASMCLOUD_POLICY_CHANGES_TOPIC"),
+            policy_batch_topic: var("WASMCLOUD_POLICY_BATCH_TOPIC"),
+            policy_timeout_ms: millis("WASMCLOUD_POLICY_TIMEOUT", var("WASMCLOUD_POLICY_TIMEOUT"))?,
+            policy_retries: parse("WASMCLOUD_POLICY_RETRIES", var("WASMCLOUD_POLICY_RETRIES"))?,
+            policy_retry_backoff: millis(
+                "WASMCLOUD_POLICY_RETRY_BACKOFF_MS",
+                var("WASMCLOUD_POLICY_RETRY_BACKOFF_MS"),
+            )?,
+            policy_decision_ttl: millis(
+                "WASMCLOUD_POLICY_DECISION_TTL_MS",
+                var("WASMCLOUD_POLICY_DECISION_TTL_MS"),
+            )?,
+            policy_default_decision: parse(
+                "WASMCLOUD_POLICY_DEFAULT_DECISION",
+                var("WASMCLOUD_POLICY_DEFAULT_DECISION"),
+            )?
+            .unwrap_or_default(),
+        };
+        config.secrets_topic_prefix = var("WASMCLOUD_SECRETS_TOPIC").into();
+        if let Some(topics) = var("WASMCLOUD_SECRETS_BACKEND_TOPICS") {
+            for pair in topics
+                .split(',')
+                .map(str::trim)
+                .filter(|pair| !pair.is_empty())
+            {
+                let (scheme, prefix) = pair.split_once('=').with_context(|| {
+                    format!(
+                        "invalid value `{pair}` for `WASMCLOUD_SECRETS_BACKEND_TOPICS`, expected `scheme=prefix`"
+                    )
+                })?;
+                config.secrets_topic_prefix = config
+                    .secrets_topic_prefix
+                    .with_scheme(scheme.trim(), prefix.trim());
+            }
+        }
+        config.secrets_cache_ttl = millis(
+            "WASMCLOUD_SECRETS_CACHE_TTL_MS",
+            var("WASMCLOUD_SECRETS_CACHE_TTL_MS"),
+        )?;
+        if let Some(max) = parse(
+            "WASMCLOUD_SECRETS_CACHE_MAX_ENTRIES",
+            var("WASMCLOUD_SECRETS_CACHE_MAX_ENTRIES"),
+        )? {
+            config.secrets_cache_max_entries = max;
+        }
+        if let Some(timeout) = millis(
+            "WASMCLOUD_SECRETS_TIMEOUT_MS",
+            var("WASMCLOUD_SECRETS_TIMEOUT_MS"),
+        )? {
+            config.secrets_timeout = timeout;
+        }
+        if let Some(interval) = parse(
+            "WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL",
+            var("WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL"),
+        )? {
+            config.secrets_health_check_interval =
+                (interval > 0).then(|| Duration::from_secs(interval));
+        }
+        if let Some(max) = millis(
+            "WASMCLOUD_MAX_EXECUTION_TIME_MS",
+            var("WASMCLOUD_MAX_EXECUTION_TIME_MS"),
+        )? {
+            config.max_execution_time = max;
+        }
+        if let Some(max) = parse(
+            "WASMCLOUD_MAX_LINEAR_MEMORY",
+            var("WASMCLOUD_MAX_LINEAR_MEMORY"),
+        )? {
+            config.max_linear_memory = max;
+        }
+        if let Some(max) = parse(
+            "WASMCLOUD_MAX_COMPONENT_SIZE",
+            var("WASMCLOUD_MAX_COMPONENT_SIZE"),
+        )? {
+            config.max_component_size = max;
+        }
+        if let Some(max) = parse(
+            "WASMCLOUD_MAX_PROVIDER_SIZE",
+            var("WASMCLOUD_MAX_PROVIDER_SIZE"),
+        )? {
+            config.max_provider_size = Some(max);
+        }
+        if let Some(max) = parse("WASMCLOUD_MAX_COMPONENTS", var("WASMCLOUD_MAX_COMPONENTS"))? {
+            config.max_components = max;
+        }
+        if let Some(warm) = parse("WASMCLOUD_WARM_INSTANCES", var("WASMCLOUD_WARM_INSTANCES"))? {
+            config.warm_instances = warm;
+        }
+        if let Some(max) = parse(
+            "WASMCLOUD_MAX_CONCURRENT_DOWNLOADS",
+            var("WASMCLOUD_MAX_CONCURRENT_DOWNLOADS"),
+        )? {
+            config.max_concurrent_downloads = max;
+        }
+        config.heartbeat_interval = parse(
+            "WASMCLOUD_HEARTBEAT_INTERVAL",
+            var("WASMCLOUD_HEARTBEAT_INTERVAL"),
+        )?
+        .map(Duration::from_secs);
+        config.heartbeat_jitter = millis(
+            "WASMCLOUD_HEARTBEAT_JITTER_MS",
+            var("WASMCLOUD_HEARTBEAT_JITTER_MS"),
+        )?;
+        config.trust_refresh_interval = parse(
+            "WASMCLOUD_TRUST_REFRESH_INTERVAL",
+            var("WASMCLOUD_TRUST_REFRESH_INTERVAL"),
+        )?
+        .map(Duration::from_secs);
+        debug_assert!(
+            !vars.keys().any(|name| ENV_VARS
+                .iter()
+                .any(|(_, listed)| listed.contains(&name.as_str()))),
+            "variables listed in `ENV_VARS` were not read"
+        );
+        if let Err(errors) = config.validate() {
+            let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+            bail!("invalid host configuration: {}", errors.join(", "));
+        }
+        Ok(config)
+    }
+
+    /// Returns the NATS URLs to connect to for the control interface connection, in the order
+    /// they should be tried
+    #[must_use]
+    pub fn ctl_nats_servers(&self) -> Vec<Url> {
+        nats_servers(&self.ctl_nats_url, &self.ctl_nats_urls)
+    }
+
+    /// Returns the effective configuration for `lattice`, which is this configuration joining
+    /// `lattice` with its entry in `lattice_overrides` applied, if any
+    #[must_use]
+    pub fn for_lattice(&self, lattice: &str) -> Self {
+        let mut config = self.clone();
+        config.lattice = lattice.into();
+        if let Some(LatticeConfig {
+            oci_opts,
+            policy_service_config,
+            labels,
+        }) = self.lattice_overrides.get(lattice)
+        {
+            if let Some(oci_opts) = oci_opts {
+                config.oci_opts = oci_opts.clone();
+            }
+            if let Some(policy_service_config) = policy_service_config {
+                config.policy_service_config = policy_service_config.clone();
+            }
+            config.labels.extend(labels.clone());
+        }
+        config
+    }
+
+    /// Returns the limits of the component `component_id` started from `image_reference`, with
+    /// every limit that has a host-wide counterpart set. Limits not overridden in
+    /// `component_limits` are the host-wide limits, and overrides exceeding the host-wide limits
+    /// are lowered to them. `max_instances` is only set if overridden
+    #[must_use]
+    pub fn component_limits_for(
+        &self,
+        component_id: &str,
+        image_reference: &str,
+    ) -> ComponentLimits {
+        let ComponentLimits {
+            max_execution_time,
+            max_linear_memory,
+            max_component_size,
+            max_instances,
+        } = self
+            .component_limits
+            .get(component_id)
+            .or_else(|| self.component_limits.get(image_reference))
+            .cloned()
+            .unwrap_or_default();
+        ComponentLimits {
+            max_execution_time: Some(max_execution_time.map_or(self.max_execution_time, |max| {
+                max.min(self.max_execution_time)
+            })),
+            max_linear_memory: Some(max_linear_memory.map_or(self.max_linear_memory, |max| {
+                max.min(self.max_linear_memory)
+            })),
+            max_component_size: Some(max_component_size.map_or(self.max_component_size, |max| {
+                max.min(self.max_component_size)
+            })),
+            max_instances,
+        }
+    }
+
+    /// Returns the NATS URLs to connect to for component RPC, in the order they should be tried
+    #[must_use]
+    pub fn rpc_nats_servers(&self) -> Vec<Url> {
+        nats_servers(&self.rpc_nats_url, &self.rpc_nats_urls)
+    }
+
+    /// Checks this configuration for settings that would prevent the host from working, like
+    /// a JWT without its key pair. Embedders constructing the configuration directly should call
+    /// this before starting the host, [`HostBuilder::build`] does so already.
+    ///
+    /// # Errors
+    ///
+    /// Returns all problems found, not just the first
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        match (&self.ctl_jwt, &self.ctl_key) {
+            (Some(_), None) => errors.push(ConfigError::CtlJwtWithoutKey),
+            (None, Some(_)) => errors.push(ConfigError::CtlKeyWithoutJwt),
+            _ => {}
+        }
+        match (&self.rpc_jwt, &self.rpc_key) {
+            (Some(_), None) => errors.push(ConfigError::RpcJwtWithoutKey),
+            (None, Some(_)) => errors.push(ConfigError::RpcKeyWithoutJwt),
+            _ => {}
+        }
+        match (&self.ctl_tls_client_cert, &self.ctl_tls_client_key) {
+            (None, Some(_)) => errors.push(ConfigError::CtlTlsKeyWithoutCert),
+            (Some(_), None) => errors.push(ConfigError::CtlTlsCertWithoutKey),
+            _ => {}
+        }
+        match (&self.rpc_tls_client_cert, &self.rpc_tls_client_key) {
+            (None, Some(_)) => errors.push(ConfigError::RpcTlsKeyWithoutCert),
+            (Some(_), None) => errors.push(ConfigError::RpcTlsCertWithoutKey),
+            _ => {}
+        }
+        if self.lattice.is_empty() {
+            errors.push(ConfigError::EmptyLattice);
+        }
+        let mut labels: Vec<_> = self.labels.iter().collect();
+        labels.sort();
+        errors.extend(
+            labels
+                .into_iter()
+                .filter_map(|(key, value)| validate_label(key, value).err()),
+        );
+        if self.max_components == 0 {
+            errors.push(ConfigError::ZeroMaxComponents);
+        }
+        if self.rpc_max_payload == Some(0) {
+            errors.push(ConfigError::ZeroRpcMaxPayload);
+        }
+        if self.nats_reconnect_buffer_size == Some(0) {
+            errors.push(ConfigError::ZeroNatsReconnectBufferSize);
+        }
+        if self.max_concurrent_downloads == 0 {
+            errors.push(ConfigError::ZeroMaxConcurrentDownloads);
+        }
+        if self.secrets_cache_ttl.is_some() && self.secrets_cache_max_entries == 0 {
+            errors.push(ConfigError::ZeroSecretsCacheMaxEntries);
+        }
+        if self.secrets_timeout.is_zero() {
+            errors.push(ConfigError::ZeroSecretsTimeout);
+        }
+        if !is_valid_trace_sampling_ratio(self.otel_config.trace_sampling_ratio) {
+            errors.push(ConfigError::InvalidTraceSamplingRatio);
+        }
+        errors.extend(
+            RESERVED_RESOURCE_ATTRIBUTES
+                .into_iter()
+                .filter(|key| self.otel_config.resource_attributes.contains_key(*key))
+                .map(|key| ConfigError::ReservedResourceAttribute(key.into())),
+        );
+        if self.provider_startup_timeout == Some(Duration::ZERO) {
+            errors.push(ConfigError::ZeroProviderStartupTimeout);
+        }
+        if self.secrets_health_check_interval == Some(Duration::ZERO) {
+            errors.push(ConfigError::ZeroSecretsHealthCheckInterval);
+        }
+        if self.trust_refresh_interval == Some(Duration::ZERO) {
+            errors.push(ConfigError::ZeroTrustRefreshInterval);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Declares the environment variables read by [`Host::from_env`] for every setting of [`Host`].
+/// Every setting must be listed, with no variables if it cannot be set from the environment, so
+/// that adding a setting without deciding on its variables fails to compile
+macro_rules! env_vars {
+    ($($setting:ident => [$($var:literal),* $(,)?],)*) => {
+        /// Environment variables of every setting, see [`Host::env_var_names`]
+        const ENV_VARS: &[(&str, &[&str])] = &[$((stringify!($setting), &[$($var),*])),*];
+
+        /// Fails to compile if a setting is missing from `ENV_VARS`
+        #[allow(dead_code)]
+        fn env_vars_are_exhaustive(config: &Host) {
+            let Host { $($setting: _),* } = config;
+        }
+    };
+}
+
+env_vars! {
+    ctl_nats_url => [
+        "WASMCLOUD_NATS_HOST",
+        "WASMCLOUD_NATS_PORT",
+        "WASMCLOUD_CTL_HOST",
+        "WASMCLOUD_CTL_PORT",
+    ],
+    ctl_nats_urls => [],
+    ctl_jwt => ["WASMCLOUD_CTL_JWT"],
+    ctl_key => ["WASMCLOUD_CTL_SEED"],
+    ctl_tls => ["WASMCLOUD_CTL_TLS"],
+    ctl_tls_ca_path => ["WASMCLOUD_CTL_TLS_CA_PATH"],
+    ctl_tls_client_cert => ["WASMCLOUD_CTL_TLS_CLIENT_CERT"],
+    ctl_tls_client_key => ["WASMCLOUD_CTL_TLS_CLIENT_KEY"],
+    ctl_topic_prefix => ["WASMCLOUD_CTL_TOPIC_PREFIX"],
+    ctl_queue_group => ["WASMCLOUD_CTL_QUEUE_GROUP"],
+    rpc_nats_url => [
+        "WASMCLOUD_NATS_HOST",
+        "WASMCLOUD_NATS_PORT",
+        "WASMCLOUD_RPC_HOST",
+        "WASMCLOUD_RPC_PORT",
+    ],
+    rpc_nats_urls => [],
+    rpc_timeout => ["WASMCLOUD_RPC_TIMEOUT_MS"],
+    rpc_max_payload => ["WASMCLOUD_RPC_MAX_PAYLOAD"],
+    rpc_jwt => ["WASMCLOUD_RPC_JWT"],
+    rpc_key => ["WASMCLOUD_RPC_SEED"],
+    rpc_tls => ["WASMCLOUD_RPC_TLS"],
+    rpc_tls_ca_path => ["WASMCLOUD_RPC_TLS_CA_PATH"],
+    rpc_tls_client_cert => ["WASMCLOUD_RPC_TLS_CLIENT_CERT"],
+    rpc_tls_client_key => ["WASMCLOUD_RPC_TLS_CLIENT_KEY"],
+    lattice => ["WASMCLOUD_LATTICE"],
+    lattice_overrides => [],
+    js_domain => ["WASMCLOUD_JS_DOMAIN"],
+    nats_connection_name => ["WASMCLOUD_NATS_CONNECTION_NAME"],
+    nats_reconnect_buffer_size => ["WASMCLOUD_NATS_RECONNECT_BUFFER_SIZE"],
+    nats_max_reconnects => ["WASMCLOUD_NATS_MAX_RECONNECTS"],
+    nats_reconnect_delay => ["WASMCLOUD_NATS_RECONNECT_DELAY_MS"],
+    labels => ["WASMCLOUD_LABEL_<key>"],
+    labels_file => ["WASMCLOUD_LABELS_FILE"],
+    host_key => ["WASMCLOUD_HOST_SEED"],
+    provider_shutdown_delay => ["WASMCLOUD_PROV_SHUTDOWN_DELAY_MS"],
+    provider_startup_timeout => ["WASMCLOUD_PROV_STARTUP_TIMEOUT_MS"],
+    oci_opts => [
+        "WASMCLOUD_OCI_ALLOW_LATEST",
+        "WASMCLOUD_OCI_ALLOWED_INSECURE",
+        "WASMCLOUD_OCI_PULL_POLICY",
+        "WASMCLOUD_OCI_DEFAULT_REGISTRY",
+        "WASMCLOUD_OCI_REGISTRY",
+        "WASMCLOUD_OCI_REGISTRY_USER",
+        "WASMCLOUD_OCI_REGISTRY_PASSWORD",
+    ],
+    allow_file_load => ["WASMCLOUD_ALLOW_FILE_LOAD"],
+    enable_structured_logging => ["WASMCLOUD_STRUCTURED_LOGGING_ENABLED"],
+    log_level => ["WASMCLOUD_LOG_LEVEL"],
+    config_service_enabled => ["WASMCLOUD_CONFIG_SERVICE"],
+    otel_config => [],
+    policy_service_config => [
+        "WASMCLOUD_POLICY_TOPIC",
+        "WASMCLOUD_POLICY_CHANGES_TOPIC",
+        "WASMCLOUD_POLICY_BATCH_TOPIC",
+        "WASMCLOUD_POLICY_TIMEOUT",
+        "WASMCLOUD_POLICY_RETRIES",
+        "WASMCLOUD_POLICY_RETRY_BACKOFF_MS",
+        "WASMCLOUD_POLICY_DECISION_TTL_MS",
+        "WASMCLOUD_POLICY_DEFAULT_DECISION",
+    ],
+    secrets_topic_prefix => ["WASMCLOUD_SECRETS_TOPIC", "WASMCLOUD_SECRETS_BACKEND_TOPICS"],
+    secrets_cache_ttl => ["WASMCLOUD_SECRETS_CACHE_TTL_MS"],
+    secrets_cache_max_entries => ["WASMCLOUD_SECRETS_CACHE_MAX_ENTRIES"],
+    secrets_timeout => ["WASMCLOUD_SECRETS_TIMEOUT_MS"],
+    secrets_health_check_interval => ["WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL"],
+    version => [],
+    max_execution_time => ["WASMCLOUD_MAX_EXECUTION_TIME_MS"],
+    max_linear_memory => ["WASMCLOUD_MAX_LINEAR_MEMORY"],
+    max_component_size => ["WASMCLOUD_MAX_COMPONENT_SIZE"],
+    max_provider_size => ["WASMCLOUD_MAX_PROVIDER_SIZE"],
+    max_components => ["WASMCLOUD_MAX_COMPONENTS"],
+    warm_instances => ["WASMCLOUD_WARM_INSTANCES"],
+    component_limits => [],
+    max_concurrent_downloads => ["WASMCLOUD_MAX_CONCURRENT_DOWNLOADS"],
+    heartbeat_interval => ["WASMCLOUD_HEARTBEAT_INTERVAL"],
+    heartbeat_jitter => ["WASMCLOUD_HEARTBEAT_JITTER_MS"],
+    trust_refresh_interval => ["WASMCLOUD_TRUST_REFRESH_INTERVAL"],
+}
+
+/// Deduplicates `url` followed by `additional`
+fn nats_servers(url: &Url, additional: &[Url]) -> Vec<Url> {
+    let mut servers = vec![url.clone()];
+    for url in additional {
+        if !servers.contains(url) {
+            servers.push(url.clone());
+        }
+    }
+    servers
+}
+
+/// [`HostBuilder`] used to configure and build a [`Host`] configuration. Any setting that is
+/// not set explicitly is taken from [`Host::default`]
+#[derive(Clone, Debug, Default)]
+pub struct HostBuilder {
+    config: Host,
+    /// Problems with settings that cannot be represented in [`Host`], reported by `build`
+    errors: Vec<ConfigError>,
+}
+
+impl HostBuilder {
+    /// Sets the NATS URL to connect to for the control interface
+    #[must_use]
+    pub fn ctl_nats_url(mut self, url: Url) -> Self {
+        self.config.ctl_nats_url = url;
+        self.config.ctl_nats_urls.clear();
+        self
+    }
+
+    /// Sets the NATS URLs of the cluster to connect to for the control interface. The first
+    /// reachable URL is connected to, and the NATS client fails over among them afterwards
+    #[must_use]
+    pub fn ctl_nats_urls(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        let mut urls = urls.into_iter();
+        match urls.next() {
+            Some(url) => {
+                self.config.ctl_nats_url = url;
+                self.config.ctl_nats_urls = urls.collect();
+            }
+            None => self.errors.push(ConfigError::NoCtlNatsUrls),
+        }
+        self
+    }
+
+    /// Sets the JWT and key pair to authenticate the control interface connection with
+    #[must_use]
+    pub fn ctl_credentials(mut self, jwt: impl Into<String>, key: Arc<KeyPair>) -> Self {
+        self.config.ctl_jwt = Some(jwt.into());
+        self.config.ctl_key = Some(key);
+        self
+    }
+
+    /// Sets whether to require TLS for the control interface connection
+    #[must_use]
+    pub fn ctl_tls(mut self, tls: bool) -> Self {
+        self.config.ctl_tls = tls;
+        self
+    }
+
+    /// Sets additional CA certificates to trust for the control interface connection
+    #[must_use]
+    pub fn ctl_tls_ca_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.ctl_tls_ca_path = Some(path.into());
+        self
+    }
+
+    /// Sets the client certificate and its private key to present to the NATS server of the
+    /// control interface connection
+    #[must_use]
+    pub fn ctl_tls_client_cert(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.config.ctl_tls_client_cert = Some(cert.into());
+        self.config.ctl_tls_client_key = Some(key.into());
+        self
+    }
+
+    /// Sets the topic prefix to use for control interface subscriptions. Defaults to `wasmbus.ctl`
+    #[must_use]
+    pub fn ctl_topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.ctl_topic_prefix = prefix.into();
+        self
+    }
+
+    /// Sets the NATS queue group of the lattice-wide control interface subscriptions, see
+    /// [`Host::ctl_queue_group`]
+    #[must_use]
+    pub fn ctl_queue_group(mut self, group: impl Into<String>) -> Self {
+        self.config.ctl_queue_group = Some(group.into());
+        self
+    }
+
+    /// Sets the NATS URL to connect to for component RPC
+    #[must_use]
+    pub fn rpc_nats_url(mut self, url: Url) -> Self {
+        self.config.rpc_nats_url = url;
+        self.config.rpc_nats_urls.clear();
+        self
+    }
+
+    /// Sets the NATS URLs of the cluster to connect to for component RPC. The first reachable
+    /// URL is connected to, and the NATS client fails over among them afterwards
+    #[must_use]
+    pub fn rpc_nats_urls(mut self, urls: impl IntoIterator<Item = Url>) -> Self {
+        let mut urls = urls.into_iter();
+        match urls.next() {
+            Some(url) => {
+                self.config.rpc_nats_url = url;
+                self.config.rpc_nats_urls = urls.collect();
+            }
+            None => self.errors.push(ConfigError::NoRpcNatsUrls),
+        }
+        self
+    }
+
+    /// Sets the timeout period for all RPC calls. Defaults to 2 seconds
+    #[must_use]
+    pub fn rpc_timeout(mut self, timeout: Duration) -> Self {
+        self.config.rpc_timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum size in bytes of the parameters of an RPC call made by a component
+    #[must_use]
+    pub fn rpc_max_payload(mut self, max_payload: usize) -> Self {
+        self.config.rpc_max_payload = Some(max_payload);
+        self
+    }
+
+    /// Sets the JWT and key pair to authenticate the RPC connection with
+    #[must_use]
+    pub fn rpc_credentials(mut self, jwt: impl Into<String>, key: Arc<KeyPair>) -> Self {
+        self.config.rpc_jwt = Some(jwt.into());
+        self.config.rpc_key = Some(key);
+        self
+    }
+
+    /// Sets whether to require TLS for the RPC connection
+    #[must_use]
+    pub fn rpc_tls(mut self, tls: bool) -> Self {
+        self.config.rpc_tls = tls;
+        self
+    }
+
+    /// Sets additional CA certificates to trust for the RPC connection
+    #[must_use]
+    pub fn rpc_tls_ca_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.rpc_tls_ca_path = Some(path.into());
+        self
+    }
+
+    /// Sets the client certificate and its private key to present to the NATS server of the RPC
+    /// connection
+    #[must_use]
+    pub fn rpc_tls_client_cert(
+        mut self,
+        cert: impl Into<PathBuf>,
+        key: impl Into<PathBuf>,
+    ) -> Self {
+        self.config.rpc_tls_client_cert = Some(cert.into());
+        self.config.rpc_tls_client_key = Some(key.into());
+        self
+    }
+
+    /// Sets the lattice the host belongs to. Defaults to `default`
+    #[must_use]
+    pub fn lattice(mut self, lattice: impl Into<Arc<str>>) -> Self {
+        self.config.lattice = lattice.into();
+        self
+    }
+
+    /// Overrides settings for `lattice`, replacing any override set for it before
+    #[must_use]
+    pub fn lattice_override(mut self, lattice: impl Into<String>, config: LatticeConfig) -> Self {
+        self.config.lattice_overrides.insert(lattice.into(), config);
+        self
+    }
+
+    /// Sets the domain to use for host Jetstream operations
+    #[must_use]
+    pub fn js_domain(mut self, domain: impl Into<String>) -> Self {
+        self.config.js_domain = Some(domain.into());
+        self
+    }
+
+    /// Sets the name of the NATS connections of the host, as shown by the NATS server
+    #[must_use]
+    pub fn nats_connection_name(mut self, name: impl Into<String>) -> Self {
+        self.config.nats_connection_name = Some(name.into());
+        self
+    }
+
+    /// Sets the number of operations the NATS clients each buffer while disconnected. Buffered
+    /// messages are kept in memory, so a large buffer may use up to this many times the maximum
+    /// NATS payload per client
+    #[must_use]
+    pub fn nats_reconnect_buffer_size(mut self, size: usize) -> Self {
+        self.config.nats_reconnect_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the number of consecutive attempts to reconnect to NATS before giving up
+    #[must_use]
+    pub fn nats_max_reconnects(mut self, max_reconnects: usize) -> Self {
+        self.config.nats_max_reconnects = Some(max_reconnects);
+        self
+    }
+
+    /// Sets the delay between attempts to reconnect to NATS
+    #[must_use]
+    pub fn nats_reconnect_delay(mut self, delay: Duration) -> Self {
+        self.config.nats_reconnect_delay = Some(delay);
+        self
+    }
+
+    /// Sets the labels to add to the host, replacing any labels set before
+    #[must_use]
+    pub fn labels(mut self, labels: HashMap<String, String>) -> Self {
+        self.config.labels = labels;
+        self
+    }
+
+    /// Adds a label to the host
+    #[must_use]
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config.labels.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets a file to read additional labels from, which is watched for changes while the host runs
+    #[must_use]
+    pub fn labels_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.labels_file = Some(path.into());
+        self
+    }
+
+    /// Sets the server key pair used by the host to generate its public key. A new key pair is
+    /// generated if unset
+    #[must_use]
+    pub fn host_key(mut self, key: Arc<KeyPair>) -> Self {
+        self.config.host_key = Some(key);
+        self
+    }
+
+    /// Sets the amount of time to wait for a provider to gracefully shut down before terminating it
+    #[must_use]
+    pub fn provider_shutdown_delay(mut self, delay: Duration) -> Self {
+        self.config.provider_shutdown_delay = Some(delay);
+        self
+    }
+
+    /// Sets the amount of time to wait for a started provider to answer its first health check,
+    /// or disables waiting with `None`. Defaults to 60 seconds
+    #[must_use]
+    pub fn provider_startup_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.provider_startup_timeout = timeout;
+        self
+    }
+
+    /// Sets the configuration for downloading artifacts from OCI registries
+    #[must_use]
+    pub fn oci_opts(mut self, oci_opts: OciConfig) -> Self {
+        self.config.oci_opts = oci_opts;
+        self
+    }
+
+    /// Sets whether to allow loading components and providers from the filesystem
+    #[must_use]
+    pub fn allow_file_load(mut self, allow: bool) -> Self {
+        self.config.allow_file_load = allow;
+        self
+    }
+
+    /// Sets whether structured logging is enabled
+    #[must_use]
+    pub fn enable_structured_logging(mut self, enable: bool) -> Self {
+        self.config.enable_structured_logging = enable;
+        self
+    }
+
+    /// Sets the log level to pass to capability providers. Defaults to `info`
+    #[must_use]
+    pub fn log_level(mut self, level: LogLevel) -> Self {
+        self.config.log_level = level;
+        self
+    }
+
+    /// Sets whether to enable loading supplemental configuration
+    #[must_use]
+    pub fn config_service_enabled(mut self, enabled: bool) -> Self {
+        self.config.config_service_enabled = enabled;
+        self
+    }
+
+    /// Sets the configuration for OpenTelemetry tracing
+    #[must_use]
+    pub fn otel_config(mut self, otel_config: OtelConfig) -> Self {
+        self.config.otel_config = otel_config;
+        self
+    }
+
+    /// Sets the configuration for the wasmCloud policy service
+    #[must_use]
+    pub fn policy_service_config(mut self, policy_service_config: PolicyService) -> Self {
+        self.config.policy_service_config = policy_service_config;
+        self
+    }
+
+    /// Sets the default topic prefix for the wasmCloud secrets backend
+    #[must_use]
+    pub fn secrets_topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.config.secrets_topic_prefix.default = Some(prefix.into());
+        self
+    }
+
+    /// Sets the topic prefix for the wasmCloud secrets backend used by secret references with the
+    /// given scheme, e.g. `vault`
+    #[must_use]
+    pub fn secrets_backend_topic_prefix(
+        mut self,
+        scheme: impl AsRef<str>,
+        prefix: impl Into<String>,
+    ) -> Self {
+        self.config.secrets_topic_prefix =
+            self.config.secrets_topic_prefix.with_scheme(scheme, prefix);
+        self
+    }
+
+    /// Enables caching resolved secrets for the given duration
+    #[must_use]
+    pub fn secrets_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.config.secrets_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the maximum number of resolved secrets to cache. Defaults to 1024
+    #[must_use]
+    pub fn secrets_cache_max_entries(mut self, max_entries: usize) -> Self {
+        self.config.secrets_cache_max_entries = max_entries;
+        self
+    }
+
+    /// Sets how long a request to a secrets backend may take. Defaults to 10 seconds
+    #[must_use]
+    pub fn secrets_timeout(mut self, timeout: Duration) -> Self {
+        self.config.secrets_timeout = timeout;
+        self
+    }
+
+    /// Sets the interval at which the secrets backends are checked to be reachable, or disables
+    /// periodic checks if `None`. Defaults to 30 seconds
+    #[must_use]
+    pub fn secrets_health_check_interval(mut self, interval: Option<Duration>) -> Self {
+        self.config.secrets_health_check_interval = interval;
+        self
+    }
+
+    /// Sets the semver version of the host. Defaults to the version of this crate
+    #[must_use]
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.config.version = version.into();
+        self
+    }
+
+    /// Sets the maximum execution time for a component instance. Defaults to 10 minutes
+    #[must_use]
+    pub fn max_execution_time(mut self, max_execution_time: Duration) -> Self {
+        self.config.max_execution_time = max_execution_time;
+        self
+    }
+
+    /// Sets the maximum linear memory that a component instance can allocate
+    #[must_use]
+    pub fn max_linear_memory(mut self, max_linear_memory: u64) -> Self {
+        self.config.max_linear_memory = max_linear_memory;
+        self
+    }
+
+    /// Sets the maximum size of a component binary that can be loaded
+    #[must_use]
+    pub fn max_component_size(mut self, max_component_size: u64) -> Self {
+        self.config.max_component_size = max_component_size;
+        self
+    }
+
+    /// Sets the maximum size of a provider archive that can be downloaded. Unlimited by default
+    #[must_use]
+    pub fn max_provider_size(mut self, max_provider_size: u64) -> Self {
+        self.config.max_provider_size = Some(max_provider_size);
+        self
+    }
+
+    /// Sets the maximum number of components that can be run simultaneously
+    #[must_use]
+    pub fn max_components(mut self, max_components: u32) -> Self {
+        self.config.max_components = max_components;
+        self
+    }
+
+    /// Sets the number of pre-instantiated instances kept warm for each component. Defaults to 0
+    #[must_use]
+    pub fn warm_instances(mut self, warm_instances: u32) -> Self {
+        self.config.warm_instances = warm_instances;
+        self
+    }
+
+    /// Overrides the limits of the component with the given ID or image reference
+    #[must_use]
+    pub fn component_limits(
+        mut self,
+        component: impl Into<String>,
+        limits: ComponentLimits,
+    ) -> Self {
+        self.config
+            .component_limits
+            .insert(component.into(), limits);
+        self
+    }
+
+    /// Sets the maximum number of components and providers that are downloaded at the same time.
+    /// Defaults to 4
+    #[must_use]
+    pub fn max_concurrent_downloads(mut self, max_concurrent_downloads: usize) -> Self {
+        self.config.max_concurrent_downloads = max_concurrent_downloads;
+        self
+    }
+
+    /// Sets the interval at which the host sends heartbeats
+    #[must_use]
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.config.heartbeat_interval = Some(interval);
+        self
+    }
+
+    /// Sets the upper bound of the random delay added to every heartbeat interval
+    #[must_use]
+    pub fn heartbeat_jitter(mut self, jitter: Duration) -> Self {
+        self.config.heartbeat_jitter = Some(jitter);
+        self
+    }
+
+    /// Sets the interval at which trusted root certificates are reloaded
+    #[must_use]
+    pub fn trust_refresh_interval(mut self, interval: Duration) -> Self {
+        self.config.trust_refresh_interval = Some(interval);
+        self
+    }
+
+    /// Turns this builder into a [`Host`] configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns all problems found by [`Host::validate`] if the configuration is not valid
+    pub fn build(self) -> Result<Host, Vec<ConfigError>> {
+        let mut errors = self.errors;
+        if let Err(invalid) = self.config.validate() {
+            errors.extend(invalid);
         }
+        if errors.is_empty() {
+            Ok(self.config)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Deserialization of settings that have no suitable serde representation of their own
+#[cfg(feature = "config")]
+mod de {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use nkeys::KeyPair;
+    use serde::de::Error as _;
+    use serde::{Deserialize as _, Deserializer};
+
+    use crate::SecretsTopicPrefix;
+
+    /// Deserializes a duration from a human-friendly string like `"2s"` or `"10m"`
+    pub fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        humantime::parse_duration(&s).map_err(D::Error::custom)
+    }
+
+    /// Deserializes an optional duration from a human-friendly string like `"2s"` or `"10m"`
+    pub fn optional_duration<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        duration(deserializer).map(Some)
+    }
+
+    /// Deserializes a key pair from an nkey seed
+    pub fn key_pair<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Arc<KeyPair>>, D::Error> {
+        let seed = String::deserialize(deserializer)?;
+        KeyPair::from_seed(&seed)
+            .map(|key| Some(Arc::new(key)))
+            .map_err(|_| D::Error::custom("invalid nkey seed"))
+    }
+
+    /// Deserializes a shared string
+    pub fn arc_str<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<str>, D::Error> {
+        String::deserialize(deserializer).map(Into::into)
+    }
+
+    /// Deserializes secrets topic prefixes from either a single prefix or a table with an optional
+    /// `default` prefix and `schemes` mapping secret reference schemes to prefixes
+    pub fn secrets_topic_prefix<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SecretsTopicPrefix, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Prefix {
+            Default(String),
+            Schemes {
+                #[serde(default)]
+                default: Option<String>,
+                #[serde(default)]
+                schemes: HashMap<String, String>,
+            },
+        }
+        Ok(match Prefix::deserialize(deserializer)? {
+            Prefix::Default(prefix) => prefix.into(),
+            Prefix::Schemes { default, schemes } => schemes.into_iter().fold(
+                SecretsTopicPrefix {
+                    default,
+                    ..Default::default()
+                },
+                |prefix, (scheme, topic)| prefix.with_scheme(scheme, topic),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let key = Arc::new(KeyPair::new_user());
+        assert!(Host::builder()
+            .ctl_credentials("jwt", Arc::clone(&key))
+            .rpc_credentials("jwt", key)
+            .build()
+            .is_ok());
+        let config = Host {
+            ctl_jwt: Some("jwt".into()),
+            rpc_key: Some(Arc::new(KeyPair::new_user())),
+            ctl_tls_client_key: Some("client.key".into()),
+            lattice: "".into(),
+            max_components: 0,
+            max_concurrent_downloads: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            config.validate(),
+            Err(vec![
+                ConfigError::CtlJwtWithoutKey,
+                ConfigError::RpcKeyWithoutJwt,
+                ConfigError::CtlTlsKeyWithoutCert,
+                ConfigError::EmptyLattice,
+                ConfigError::ZeroMaxComponents,
+                ConfigError::ZeroMaxConcurrentDownloads,
+            ])
+        );
+        assert_eq!(
+            Host::builder().lattice("").build().map(|_| ()),
+            Err(vec![ConfigError::EmptyLattice])
+        );
+        assert_eq!(
+            Host::builder()
+                .secrets_cache_ttl(Duration::from_secs(60))
+                .secrets_cache_max_entries(0)
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroSecretsCacheMaxEntries])
+        );
+        assert_eq!(
+            Host::builder()
+                .secrets_timeout(Duration::ZERO)
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroSecretsTimeout])
+        );
+        assert_eq!(
+            Host::builder()
+                .nats_reconnect_buffer_size(0)
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroNatsReconnectBufferSize])
+        );
+        assert_eq!(
+            Host::builder()
+                .otel_config(OtelConfig {
+                    trace_sampling_ratio: 1.5,
+                    ..Default::default()
+                })
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::InvalidTraceSamplingRatio])
+        );
+        assert_eq!(
+            Host::builder()
+                .otel_config(OtelConfig {
+                    resource_attributes: HashMap::from([
+                        ("service.name".into(), "host".into()),
+                        ("region".into(), "eu".into()),
+                    ]),
+                    ..Default::default()
+                })
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ReservedResourceAttribute(
+                "service.name".into()
+            )])
+        );
+        assert_eq!(
+            Host::builder()
+                .provider_startup_timeout(Some(Duration::ZERO))
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroProviderStartupTimeout])
+        );
+        assert_eq!(
+            Host::builder()
+                .secrets_health_check_interval(Some(Duration::ZERO))
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroSecretsHealthCheckInterval])
+        );
+        assert_eq!(
+            Host::builder()
+                .trust_refresh_interval(Duration::ZERO)
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::ZeroTrustRefreshInterval])
+        );
+    }
+
+    #[test]
+    fn for_lattice() {
+        let config = Host::builder()
+            .lattice("production")
+            .label("region", "eu")
+            .label("tier", "default")
+            .lattice_override(
+                "production",
+                LatticeConfig {
+                    oci_opts: Some(OciConfig {
+                        allow_latest: true,
+                        ..Default::default()
+                    }),
+                    labels: HashMap::from([("tier".to_string(), "gold".to_string())]),
+                    ..Default::default()
+                },
+            )
+            .build()
+            .expect("configuration should be valid");
+
+        let production = config.for_lattice("production");
+        assert!(production.oci_opts.allow_latest);
+        assert_eq!(
+            production.labels,
+            HashMap::from([
+                ("region".to_string(), "eu".to_string()),
+                ("tier".to_string(), "gold".to_string()),
+            ])
+        );
+
+        let staging = config.for_lattice("staging");
+        assert_eq!(&*staging.lattice, "staging");
+        assert!(!staging.oci_opts.allow_latest);
+        assert_eq!(staging.labels, config.labels);
+    }
+
+    #[test]
+    fn component_limits_for() {
+        let config = Host::builder()
+            .max_linear_memory(1024)
+            .component_limits(
+                "batch",
+                ComponentLimits {
+                    max_execution_time: Some(Duration::from_secs(60)),
+                    max_linear_memory: Some(4096),
+                    max_instances: Some(10),
+                    ..Default::default()
+                },
+            )
+            .component_limits(
+                "ghcr.io/wasmcloud/batch:0.1.0",
+                ComponentLimits {
+                    max_component_size: Some(1),
+                    ..Default::default()
+                },
+            )
+            .build()
+            .expect("configuration should be valid");
+        assert_eq!(
+            config.component_limits_for("batch", "ghcr.io/wasmcloud/batch:0.1.0"),
+            ComponentLimits {
+                max_execution_time: Some(Duration::from_secs(60)),
+                max_linear_memory: Some(1024),
+                max_component_size: Some(config.max_component_size),
+                max_instances: Some(10),
+            }
+        );
+        assert_eq!(
+            config.component_limits_for("other", "ghcr.io/wasmcloud/batch:0.1.0"),
+            ComponentLimits {
+                max_execution_time: Some(config.max_execution_time),
+                max_linear_memory: Some(1024),
+                max_component_size: Some(1),
+                max_instances: None,
+            }
+        );
+    }
+
+    #[test]
+    fn labels() {
+        assert!(validate_label("zone", "eu-west-1a").is_ok());
+        assert!(validate_label("app.example.com/tier", "gold").is_ok());
+        assert_eq!(
+            Host::builder()
+                .label("", "value")
+                .label("zone", "")
+                .label("wasmcloud.dev/zone", "eu")
+                .label("zone.>", "eu")
+                .label("hostcore.os", "plan9")
+                .build()
+                .map(|_| ())
+                .map_err(|errors| errors
+                    .into_iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>()),
+            Err(vec![
+                "invalid label ``: the key is empty".to_string(),
+                "invalid label `hostcore.os`: the label is set by the host".to_string(),
+                "invalid label `wasmcloud.dev/zone`: keys starting with `wasmcloud.` are reserved"
+                    .to_string(),
+                "invalid label `zone`: the value is empty".to_string(),
+                "invalid label `zone.>`: the key may only contain ASCII letters, digits, `-`, `_`, `.` and `/`"
+                    .to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn nats_urls() -> anyhow::Result<()> {
+        let a = Url::parse("nats://a:4222")?;
+        let b = Url::parse("nats://b:4222")?;
+        let config = Host::builder()
+            .ctl_nats_urls([a.clone(), b.clone(), a.clone()])
+            .rpc_nats_url(b.clone())
+            .build()
+            .expect("configuration should be valid");
+        assert_eq!(config.ctl_nats_url, a);
+        assert_eq!(config.ctl_nats_servers(), [a, b.clone()]);
+        assert_eq!(config.rpc_nats_servers(), [b]);
+        assert_eq!(
+            Host::builder()
+                .ctl_nats_urls([])
+                .rpc_nats_urls([])
+                .build()
+                .map(|_| ()),
+            Err(vec![ConfigError::NoCtlNatsUrls, ConfigError::NoRpcNatsUrls])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn from_vars() -> anyhow::Result<()> {
+        let vars = |vars: &[(&str, &str)]| {
+            Host::from_vars(
+                vars.iter()
+                    .map(|(key, value)| (key.to_string(), value.to_string())),
+            )
+        };
+        let config = vars(&[
+            ("WASMCLOUD_NATS_HOST", "nats"),
+            ("WASMCLOUD_RPC_PORT", "4223"),
+            ("WASMCLOUD_RPC_TIMEOUT_MS", "500"),
+            ("WASMCLOUD_NATS_RECONNECT_DELAY_MS", "1500"),
+            ("WASMCLOUD_LATTICE", "production"),
+            ("WASMCLOUD_LABEL_region", "eu"),
+            ("WASMCLOUD_ALLOW_FILE_LOAD", "true"),
+            (
+                "WASMCLOUD_OCI_ALLOWED_INSECURE",
+                "localhost:5000, registry:5000",
+            ),
+            ("WASMCLOUD_OCI_PULL_POLICY", "if-not-present"),
+            ("WASMCLOUD_OCI_DEFAULT_REGISTRY", "registry.example.com"),
+            ("WASMCLOUD_LOG_LEVEL", "debug"),
+            ("WASMCLOUD_HEARTBEAT_JITTER_MS", "250"),
+            ("WASMCLOUD_MAX_PROVIDER_SIZE", "104857600"),
+            ("WASMCLOUD_SECRETS_TOPIC", "wasmcloud.secrets"),
+            (
+                "WASMCLOUD_SECRETS_BACKEND_TOPICS",
+                "vault:=wasmcloud.secrets.vault, k8s=wasmcloud.secrets.k8s",
+            ),
+            ("WASMCLOUD_SECRETS_TIMEOUT_MS", "2500"),
+            ("WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL", "0"),
+            ("HOME", "/root"),
+        ])?;
+        assert_eq!(config.ctl_nats_url.as_str(), "nats://nats:4222");
+        assert_eq!(config.rpc_nats_url.as_str(), "nats://nats:4223");
+        assert_eq!(config.rpc_timeout, Duration::from_millis(500));
+        assert_eq!(
+            config.nats_reconnect_delay,
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(&*config.lattice, "production");
+        assert_eq!(
+            config.labels,
+            HashMap::from([("region".to_string(), "eu".to_string())])
+        );
+        assert!(config.allow_file_load);
+        assert_eq!(
+            config.oci_opts.allowed_insecure,
+            ["localhost:5000", "registry:5000"]
+        );
+        assert_eq!(
+            config.oci_opts.pull_policy,
+            wasmcloud_core::PullPolicy::IfNotPresent
+        );
+        assert_eq!(
+            config.oci_opts.default_registry.as_deref(),
+            Some("registry.example.com")
+        );
+        assert!(matches!(config.log_level, LogLevel::Debug));
+        assert_eq!(config.heartbeat_jitter, Some(Duration::from_millis(250)));
+        assert_eq!(config.max_provider_size, Some(100 << 20));
+        assert_eq!(
+            config.secrets_topic_prefix.for_backend("vault:team-a"),
+            Some("wasmcloud.secrets.vault")
+        );
+        assert_eq!(
+            config.secrets_topic_prefix.for_backend("k8s"),
+            Some("wasmcloud.secrets.k8s")
+        );
+        assert_eq!(config.secrets_timeout, Duration::from_millis(2500));
+        assert_eq!(config.secrets_health_check_interval, None);
+        assert_eq!(
+            config.secrets_topic_prefix.for_backend("nats-kv"),
+            Some("wasmcloud.secrets")
+        );
+        // Unset settings keep their defaults
+        assert_eq!(config.max_components, Host::default().max_components);
+        assert_eq!(config.ctl_topic_prefix, Host::default().ctl_topic_prefix);
+
+        // Every listed variable is read, which is checked by `from_vars` in debug builds, and empty
+        // variables are ignored
+        let all: Vec<_> = Host::env_var_names()
+            .into_iter()
+            .filter(|(_, var)| !var.ends_with("<key>"))
+            .map(|(_, var)| (var, ""))
+            .collect();
+        assert_eq!(vars(&all)?.lattice, Host::default().lattice);
+        assert!(Host::env_var_names().contains(&("labels", "WASMCLOUD_LABEL_<key>")));
+
+        let err = vars(&[("WASMCLOUD_RPC_TIMEOUT_MS", "2s")]).expect_err("invalid timeout");
+        assert!(format!("{err:#}").contains("WASMCLOUD_RPC_TIMEOUT_MS"));
+        let err = vars(&[("WASMCLOUD_CTL_HOST", "nats host")]).expect_err("invalid URL");
+        assert!(format!("{err:#}").contains("WASMCLOUD_CTL_HOST"));
+        assert!(vars(&[("WASMCLOUD_CTL_JWT", "jwt")]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_file() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let key = KeyPair::new_server();
+        let toml = dir.path().join("host.toml");
+        std::fs::write(
+            &toml,
+            format!(
+                r#"
+ctl_nats_url = "nats://nats:4222"
+lattice = "production"
+rpc_timeout = "2s"
+heartbeat_interval = "10m"
+host_key = "{}"
+secrets_topic_prefix = "wasmcloud.secrets"
+
+[labels]
+region = "eu"
+
+[oci]
+allow_latest = true
+
+[policy]
+policy_timeout = "500ms"
+policy_default_decision = "allow"
+
+[lattice_overrides.staging.policy]
+policy_topic = "wasmcloud.policy.staging"
+"#,
+                key.seed()?
+            ),
+        )?;
+        let config = Host::from_file(&toml)?;
+        assert_eq!(config.ctl_nats_url.as_str(), "nats://nats:4222");
+        assert_eq!(&*config.lattice, "production");
+        assert_eq!(config.rpc_timeout, Duration::from_secs(2));
+        assert_eq!(config.heartbeat_interval, Some(Duration::from_secs(600)));
+        assert_eq!(
+            config.host_key.as_ref().map(|key| key.public_key()),
+            Some(key.public_key())
+        );
+        assert_eq!(config.labels.get("region").map(String::as_str), Some("eu"));
+        assert!(config.oci_opts.allow_latest);
+        assert_eq!(
+            config.secrets_topic_prefix,
+            SecretsTopicPrefix::from("wasmcloud.secrets")
+        );
+        assert_eq!(
+            config.policy_service_config.policy_timeout_ms,
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            config.policy_service_config.policy_default_decision,
+            PolicyDecision::Allow
+        );
+        assert_eq!(
+            config
+                .for_lattice("staging")
+                .policy_service_config
+                .policy_topic
+                .as_deref(),
+            Some("wasmcloud.policy.staging")
+        );
+        // Unset settings keep their defaults
+        assert_eq!(config.max_components, Host::default().max_components);
+
+        let json = dir.path().join("host.json");
+        std::fs::write(&json, r#"{"lattice": "production", "max_component": 1}"#)?;
+        assert!(
+            Host::from_file(&json).is_err(),
+            "unknown field was accepted"
+        );
+        std::fs::write(&json, r#"{"lattice": "production"}"#)?;
+        assert_eq!(&*Host::from_file(&json)?.lattice, "production");
+        std::fs::write(
+            &json,
+            r#"{"secrets_topic_prefix": {"schemes": {"vault:": "wasmcloud.secrets.vault"}}}"#,
+        )?;
+        let secrets_topic_prefix = Host::from_file(&json)?.secrets_topic_prefix;
+        assert_eq!(
+            secrets_topic_prefix.for_backend("vault:team-a"),
+            Some("wasmcloud.secrets.vault")
+        );
+        assert_eq!(secrets_topic_prefix.for_backend("nats-kv"), None);
+        Ok(())
     }
 }
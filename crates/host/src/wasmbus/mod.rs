@@ -6,6 +6,7 @@ use std::env::consts::{ARCH, FAMILY, OS};
 use std::future::Future;
 use std::num::NonZeroUsize;
 use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::process::Stdio;
 use std::str::FromStr;
@@ -23,16 +24,17 @@ use futures::future::Either;
 use futures::stream::{AbortHandle, Abortable, SelectAll};
 use futures::{join, stream, try_join, Stream, StreamExt, TryFutureExt, TryStreamExt};
 use nkeys::{KeyPair, KeyPairType, XKey};
+use rand::Rng as _;
 use secrecy::Secret;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::io::{AsyncWrite, AsyncWriteExt};
 use tokio::sync::{broadcast, mpsc, watch, RwLock, Semaphore};
-use tokio::task::{JoinHandle, JoinSet};
-use tokio::time::{interval_at, Instant};
+use tokio::task::{spawn_blocking, JoinHandle, JoinSet};
+use tokio::time::{sleep, sleep_until, Instant};
 use tokio::{process, select, spawn};
-use tokio_stream::wrappers::IntervalStream;
 use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
+use url::Url;
 use uuid::Uuid;
 use wascap::{jwt, prelude::ClaimsBuilder};
 use wasmcloud_control_interface::{
@@ -41,9 +43,10 @@ use wasmcloud_control_interface::{
     ProviderAuctionRequest, ProviderDescription, RegistryCredential, ScaleComponentCommand,
     StartProviderCommand, StopHostCommand, StopProviderCommand, UpdateComponentCommand,
 };
+use wasmcloud_core::logging::Level as LogLevel;
 use wasmcloud_core::{
-    provider_config_update_subject, ComponentId, HealthCheckResponse, HostData, OtelConfig,
-    CTL_API_VERSION_1,
+    health_subject, provider_config_update_subject, provider_log_level_subject, tls, ComponentId,
+    FetchedProvider, HealthCheckResponse, HostData, OtelConfig, CTL_API_VERSION_1,
 };
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_runtime::component::WrpcServeEvent;
@@ -54,8 +57,8 @@ use wasmcloud_tracing::{global, KeyValue};
 
 use crate::registry::RegistryCredentialExt;
 use crate::{
-    fetch_component, HostMetrics, OciConfig, PolicyHostInfo, PolicyManager, PolicyResponse,
-    RegistryAuth, RegistryConfig, RegistryType, SecretsManager,
+    fetch_component_signed, FetchOptions, HostMetrics, OciConfig, PolicyHostInfo, PolicyManager,
+    PolicyResponse, RegistryAuth, RegistryConfig, RegistryType, SecretsManager,
 };
 
 mod event;
@@ -65,7 +68,9 @@ pub mod config;
 /// wasmCloud host configuration
 pub mod host_config;
 
-pub use self::host_config::Host as HostConfig;
+pub use self::host_config::{
+    ConfigError as HostConfigError, Host as HostConfig, HostBuilder as HostConfigBuilder,
+};
 
 use self::config::{BundleGenerator, ConfigBundle};
 use self::handler::Handler;
@@ -133,9 +138,16 @@ impl Queue {
         nats: &async_nats::Client,
         topic_prefix: &str,
         lattice: &str,
+        queue_group: Option<&str>,
         host_key: &KeyPair,
     ) -> anyhow::Result<Self> {
         let host_id = host_key.public_key();
+        let queue_group = |kind: &str| {
+            queue_group.map_or_else(
+                || format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.{kind}"),
+                ToString::to_string,
+            )
+        };
         let streams = futures::future::join_all([
             Either::Left(nats.subscribe(format!(
                 "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.registry.put",
@@ -143,16 +155,19 @@ impl Queue {
             Either::Left(nats.subscribe(format!(
                 "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.host.ping",
             ))),
+            Either::Left(nats.subscribe(format!(
+                "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.secrets.invalidate",
+            ))),
             Either::Left(nats.subscribe(format!(
                 "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.*.auction",
             ))),
             Either::Right(nats.queue_subscribe(
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.link.*"),
-                format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.link",),
+                queue_group("link"),
             )),
             Either::Right(nats.queue_subscribe(
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.claims.get"),
-                format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.claims"),
+                queue_group("claims"),
             )),
             Either::Left(nats.subscribe(format!(
                 "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.component.*.{host_id}"
@@ -168,7 +183,7 @@ impl Queue {
             ))),
             Either::Right(nats.queue_subscribe(
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.config.>"),
-                format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.config"),
+                queue_group("config"),
             )),
         ])
         .await
@@ -193,9 +208,21 @@ struct Component {
     annotations: Annotations,
     /// Maximum number of instances of this component that can be running at once
     max_instances: NonZeroUsize,
+    /// Permits held by the invocations being handled, one per running instance
+    permits: Arc<Semaphore>,
     image_reference: Arc<str>,
 }
 
+impl Component {
+    /// Returns the number of invocations of the component currently being handled
+    fn in_flight(&self) -> usize {
+        self.max_instances
+            .get()
+            .min(Semaphore::MAX_PERMITS)
+            .saturating_sub(self.permits.available_permits())
+    }
+}
+
 impl Deref for Component {
     type Target = wasmcloud_runtime::Component<Handler>;
 
@@ -335,6 +362,10 @@ impl wrpc_transport::Serve for WrpcServer {
 #[derive(Debug)]
 struct Provider {
     image_ref: String,
+    /// Path the provider was extracted to, which is kept when purging extracted providers
+    path: PathBuf,
+    /// The manifest digest of the provider, if it was fetched from an OCI registry
+    digest: Option<String>,
     claims_token: Option<jwt::Token<jwt::CapabilityProvider>>,
     xkey: XKey,
     annotations: Annotations,
@@ -366,6 +397,9 @@ pub struct Host {
     /// The Xkey used to encrypt secrets when sending them over NATS
     secrets_xkey: Arc<XKey>,
     labels: RwLock<BTreeMap<String, String>>,
+    /// The current log level, initially taken from the host config and updated by
+    /// [`Host::set_log_level`]
+    log_level: RwLock<LogLevel>,
     ctl_topic_prefix: String,
     /// NATS client to use for control interface subscriptions and jetstream queries
     ctl_nats: async_nats::Client,
@@ -385,6 +419,11 @@ pub struct Host {
     start_at: Instant,
     stop_tx: watch::Sender<Option<Instant>>,
     stop_rx: watch::Receiver<Option<Instant>>,
+    /// Set once the host is draining, see [`Host::drain`]
+    draining: watch::Sender<bool>,
+    /// Whether the secrets backends were reachable when last checked, see
+    /// [`Host::check_secrets_backend`]
+    secrets_ready: watch::Sender<bool>,
     queue: AbortHandle,
     // Component ID -> All Links
     links: RwLock<HashMap<String, Vec<Link>>>,
@@ -392,6 +431,8 @@ pub struct Host {
     provider_claims: Arc<RwLock<HashMap<String, jwt::Claims<jwt::CapabilityProvider>>>>,
     metrics: Arc<HostMetrics>,
     max_execution_time: Duration,
+    /// Limits the number of components and providers downloaded at the same time
+    downloads: Semaphore,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -515,23 +556,41 @@ async fn create_bucket(
     }
 }
 
-/// Given the NATS address, authentication jwt, seed, tls requirement and optional request timeout,
-/// attempt to establish connection.
+/// Given the NATS addresses, connection name, authentication jwt, seed, tls requirement and
+/// optional request timeout, attempt to establish connection. The first reachable server of `urls` is connected
+/// to, and the client fails over among them afterwards.
 ///
 ///
 /// # Errors
 ///
 /// Returns an error if:
 /// - Only one of JWT or seed is specified, as we cannot authenticate with only one of them
+/// - Any of the URLs is not a valid NATS URL
+/// - The TLS configuration cannot be loaded
 /// - Connection fails
+#[allow(clippy::too_many_arguments)]
 async fn connect_nats(
-    addr: impl async_nats::ToServerAddrs,
+    urls: Vec<Url>,
+    name: &str,
     jwt: Option<&String>,
     key: Option<Arc<KeyPair>>,
     require_tls: bool,
+    tls: NatsTls<'_>,
+    reconnect: NatsReconnect,
     request_timeout: Option<Duration>,
 ) -> anyhow::Result<async_nats::Client> {
-    let opts = async_nats::ConnectOptions::new().require_tls(require_tls);
+    let addrs = urls
+        .into_iter()
+        .map(|url| {
+            let addr = url.to_string();
+            async_nats::ServerAddr::from_url(url)
+                .with_context(|| format!("invalid NATS URL `{addr}`"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let opts = async_nats::ConnectOptions::new()
+        .name(name)
+        .require_tls(require_tls)
+        .retain_servers_order();
     let opts = match (jwt, key) {
         (Some(jwt), Some(key)) => opts.jwt(jwt.to_string(), {
             move |nonce| {
@@ -544,21 +603,180 @@ async fn connect_nats(
         }
         _ => opts,
     };
+    let opts = if let Some(tls_config) = tls.client_config()? {
+        opts.tls_client_config(tls_config)
+    } else {
+        opts
+    };
     let opts = if let Some(timeout) = request_timeout {
         opts.request_timeout(Some(timeout))
     } else {
         opts
     };
-    opts.connect(addr)
+    let opts = reconnect.apply(opts);
+    opts.connect(addrs)
         .await
         .context("failed to connect to NATS")
 }
 
+/// Reconnect settings of a NATS connection, the NATS client defaults are used for those unset
+#[derive(Clone, Copy, Debug, Default)]
+struct NatsReconnect {
+    /// Number of operations buffered while disconnected
+    buffer_size: Option<usize>,
+    /// Number of consecutive reconnect attempts before giving up
+    max_reconnects: Option<usize>,
+    /// Delay between reconnect attempts
+    delay: Option<Duration>,
+}
+
+impl NatsReconnect {
+    fn apply(self, opts: async_nats::ConnectOptions) -> async_nats::ConnectOptions {
+        let opts = if let Some(size) = self.buffer_size {
+            opts.client_capacity(size)
+        } else {
+            opts
+        };
+        let opts = if let Some(max) = self.max_reconnects {
+            opts.max_reconnects(max)
+        } else {
+            opts
+        };
+        if let Some(delay) = self.delay {
+            // Reconnect right away after losing the connection, and wait between failed attempts
+            opts.reconnect_delay_callback(
+                move |attempts| {
+                    if attempts <= 1 {
+                        Duration::ZERO
+                    } else {
+                        delay
+                    }
+                },
+            )
+        } else {
+            opts
+        }
+    }
+}
+
+/// TLS settings of a NATS connection
+#[derive(Clone, Copy, Debug, Default)]
+struct NatsTls<'a> {
+    /// CA certificates to trust in addition to the system roots
+    ca_path: Option<&'a Path>,
+    /// Client certificate to present to the server
+    client_cert: Option<&'a Path>,
+    /// Private key of the client certificate
+    client_key: Option<&'a Path>,
+}
+
+impl NatsTls<'_> {
+    /// Builds the TLS client configuration, or `None` if the NATS client defaults should be used
+    fn client_config(&self) -> anyhow::Result<Option<rustls::ClientConfig>> {
+        if self.ca_path.is_none() && self.client_cert.is_none() && self.client_key.is_none() {
+            return Ok(None);
+        }
+        let mut roots = rustls::RootCertStore {
+            roots: tls::DEFAULT_ROOTS.roots.clone(),
+        };
+        if let Some(ca_path) = self.ca_path {
+            let certs = tls::read_certs_from_path(ca_path)?;
+            ensure!(
+                !certs.is_empty(),
+                "no CA certificates found in `{}`",
+                ca_path.display()
+            );
+            roots.add_parsable_certificates(certs);
+        }
+        let config = rustls::ClientConfig::builder().with_root_certificates(roots);
+        let config = match (self.client_cert, self.client_key) {
+            (Some(cert), Some(key)) => {
+                let certs = tls::read_certs_from_path(cert)?;
+                ensure!(
+                    !certs.is_empty(),
+                    "no client certificate found in `{}`",
+                    cert.display()
+                );
+                let key = std::fs::read(key)
+                    .with_context(|| format!("failed to read `{}`", key.display()))
+                    .and_then(|pem| {
+                        rustls_pemfile::private_key(&mut pem.as_slice())
+                            .context("failed to parse private key")?
+                            .context("no private key found")
+                    })
+                    .with_context(|| format!("invalid client key `{}`", key.display()))?;
+                config
+                    .with_client_auth_cert(certs, key)
+                    .context("invalid client certificate or key")?
+            }
+            (None, Some(_)) => bail!("cannot present a client key without a client certificate"),
+            (Some(_), None) => bail!("cannot present a client certificate without its key"),
+            (None, None) => config.with_no_client_auth(),
+        };
+        Ok(Some(config))
+    }
+}
+
 #[derive(Debug, Default)]
 struct SupplementalConfig {
     registry_config: Option<HashMap<String, RegistryConfig>>,
 }
 
+/// Returns the built-in labels of the host, see [`host_config::BUILTIN_LABELS`]
+fn builtin_labels() -> [(String, String); 3] {
+    let [arch, os, osfamily] = host_config::BUILTIN_LABELS;
+    [
+        (arch.into(), ARCH.into()),
+        (os.into(), OS.into()),
+        (osfamily.into(), FAMILY.into()),
+    ]
+}
+
+/// Reads host labels from a file with one `key=value` pair per line. Values may be enclosed in
+/// double quotes, like in files written by the Kubernetes downward API. Empty lines and lines
+/// starting with `#` are ignored
+async fn read_labels_file(path: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read labels file `{}`", path.display()))?;
+    let labels = parse_labels(&content)
+        .with_context(|| format!("failed to parse labels file `{}`", path.display()))?;
+    let errors: Vec<_> = labels
+        .iter()
+        .filter_map(|(key, value)| host_config::validate_label(key, value).err())
+        .map(|err| err.to_string())
+        .collect();
+    ensure!(
+        errors.is_empty(),
+        "invalid labels in labels file `{}`: {}",
+        path.display(),
+        errors.join(", ")
+    );
+    Ok(labels)
+}
+
+fn parse_labels(content: &str) -> anyhow::Result<BTreeMap<String, String>> {
+    let mut labels = BTreeMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .with_context(|| format!("line {} is not a `key=value` pair", i + 1))?;
+        let key = key.trim();
+        ensure!(!key.is_empty(), "line {} has an empty key", i + 1);
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        labels.insert(key.to_string(), value.to_string());
+    }
+    Ok(labels)
+}
+
 #[instrument(level = "debug", skip_all)]
 async fn load_supplemental_config(
     ctl_nats: &async_nats::Client,
@@ -679,6 +897,12 @@ async fn merge_registry_config(
 impl Host {
     const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 
+    /// How often the labels file is checked for changes
+    const LABELS_FILE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// How often [`Host::drain`] checks whether in-flight invocations have finished
+    const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
     const NAME_ADJECTIVES: &'static str = "
     autumn hidden bitter misty silent empty dry dark summer
     icy delicate quiet white cool spring winter patient
@@ -716,6 +940,11 @@ impl Host {
     pub async fn new(
         config: HostConfig,
     ) -> anyhow::Result<(Arc<Self>, impl Future<Output = anyhow::Result<()>>)> {
+        let config = config.for_lattice(&config.lattice);
+        if let Err(errors) = config.validate() {
+            let errors: Vec<_> = errors.iter().map(ToString::to_string).collect();
+            bail!("invalid host configuration: {}", errors.join(", "));
+        }
         let host_key = if let Some(host_key) = &config.host_key {
             ensure!(host_key.key_pair_type() == KeyPairType::Server);
             Arc::clone(host_key)
@@ -723,12 +952,17 @@ impl Host {
             Arc::new(KeyPair::new(KeyPairType::Server))
         };
 
-        let mut labels = BTreeMap::from([
-            ("hostcore.arch".into(), ARCH.into()),
-            ("hostcore.os".into(), OS.into()),
-            ("hostcore.osfamily".into(), FAMILY.into()),
-        ]);
-        labels.extend(config.labels.clone().into_iter());
+        let mut labels: BTreeMap<_, _> = config.labels.clone().into_iter().collect();
+        let mut file_labels = BTreeMap::new();
+        if let Some(path) = &config.labels_file {
+            file_labels = read_labels_file(path).await?;
+        }
+        // Built-in labels cannot be set by users, both the configuration and the labels file are
+        // validated to not contain them
+        let static_labels: BTreeMap<_, _> =
+            labels.clone().into_iter().chain(builtin_labels()).collect();
+        labels.extend(file_labels.clone());
+        labels.extend(builtin_labels());
         let friendly_name =
             Self::generate_friendly_name().context("failed to generate friendly name")?;
 
@@ -754,17 +988,31 @@ impl Host {
             "version": config.version,
         });
 
+        let nats_connection_name = config
+            .nats_connection_name
+            .clone()
+            .unwrap_or_else(|| format!("wasmcloud-host-{}", host_key.public_key()));
+        let nats_reconnect = NatsReconnect {
+            buffer_size: config.nats_reconnect_buffer_size,
+            max_reconnects: config.nats_max_reconnects,
+            delay: config.nats_reconnect_delay,
+        };
         let ((ctl_nats, queue), rpc_nats) = try_join!(
             async {
-                debug!(
-                    ctl_nats_url = config.ctl_nats_url.as_str(),
-                    "connecting to NATS control server"
-                );
+                let ctl_nats_urls = config.ctl_nats_servers();
+                debug!(?ctl_nats_urls, "connecting to NATS control server");
                 let ctl_nats = connect_nats(
-                    config.ctl_nats_url.as_str(),
+                    ctl_nats_urls,
+                    &nats_connection_name,
                     config.ctl_jwt.as_ref(),
                     config.ctl_key.clone(),
                     config.ctl_tls,
+                    NatsTls {
+                        ca_path: config.ctl_tls_ca_path.as_deref(),
+                        client_cert: config.ctl_tls_client_cert.as_deref(),
+                        client_key: config.ctl_tls_client_key.as_deref(),
+                    },
+                    nats_reconnect,
                     None,
                 )
                 .await
@@ -773,6 +1021,7 @@ impl Host {
                     &ctl_nats,
                     &config.ctl_topic_prefix,
                     &config.lattice,
+                    config.ctl_queue_group.as_deref(),
                     &host_key,
                 )
                 .await
@@ -781,31 +1030,53 @@ impl Host {
                 Ok((ctl_nats, queue))
             },
             async {
-                debug!(
-                    rpc_nats_url = config.rpc_nats_url.as_str(),
-                    "connecting to NATS RPC server"
-                );
+                let rpc_nats_urls = config.rpc_nats_servers();
+                debug!(?rpc_nats_urls, "connecting to NATS RPC server");
                 connect_nats(
-                    config.rpc_nats_url.as_str(),
+                    rpc_nats_urls,
+                    &nats_connection_name,
                     config.rpc_jwt.as_ref(),
                     config.rpc_key.clone(),
                     config.rpc_tls,
+                    NatsTls {
+                        ca_path: config.rpc_tls_ca_path.as_deref(),
+                        client_cert: config.rpc_tls_client_cert.as_deref(),
+                        client_key: config.rpc_tls_client_key.as_deref(),
+                    },
+                    nats_reconnect,
                     Some(config.rpc_timeout),
                 )
                 .await
                 .context("failed to establish NATS RPC server connection")
             }
         )?;
+        if let Some(rpc_max_payload) = config.rpc_max_payload {
+            let server_max_payload = rpc_nats.server_info().max_payload;
+            if rpc_max_payload > server_max_payload {
+                warn!(
+                    rpc_max_payload,
+                    server_max_payload,
+                    "`rpc_max_payload` exceeds the maximum payload of the NATS RPC server, which limits RPC calls instead"
+                );
+            }
+        }
 
         let start_at = Instant::now();
 
         let heartbeat_interval = config
             .heartbeat_interval
             .unwrap_or(Self::DEFAULT_HEARTBEAT_INTERVAL);
-        let heartbeat_start_at = start_at
-            .checked_add(heartbeat_interval)
-            .context("failed to compute heartbeat start time")?;
-        let heartbeat = IntervalStream::new(interval_at(heartbeat_start_at, heartbeat_interval));
+        let heartbeat_jitter = config.heartbeat_jitter.unwrap_or_default();
+        let heartbeat = Box::pin(stream::unfold(start_at, move |last| async move {
+            let jitter = if heartbeat_jitter.is_zero() {
+                Duration::ZERO
+            } else {
+                rand::thread_rng().gen_range(Duration::ZERO..=heartbeat_jitter)
+            };
+            let next = last + heartbeat_interval + jitter;
+            sleep_until(next).await;
+            Some(((), next))
+        }));
 
         let (stop_tx, stop_rx) = watch::channel(None);
 
@@ -832,6 +1103,9 @@ impl Host {
         let (queue_abort, queue_abort_reg) = AbortHandle::new_pair();
         let (heartbeat_abort, heartbeat_abort_reg) = AbortHandle::new_pair();
         let (data_watch_abort, data_watch_abort_reg) = AbortHandle::new_pair();
+        let (labels_watch_abort, labels_watch_abort_reg) = AbortHandle::new_pair();
+        let (trust_refresh_abort, trust_refresh_abort_reg) = AbortHandle::new_pair();
+        let (secrets_health_abort, secrets_health_abort_reg) = AbortHandle::new_pair();
 
         let supplemental_config = if config.config_service_enabled {
             load_supplemental_config(&ctl_nats, &config.lattice, &labels).await?
@@ -842,6 +1116,21 @@ impl Host {
         let registry_config = RwLock::new(supplemental_config.registry_config.unwrap_or_default());
         merge_registry_config(&registry_config, config.oci_opts.clone()).await;
 
+        let meter = global::meter_with_version(
+            "wasmcloud-host",
+            Some(config.version.clone()),
+            None::<&str>,
+            Some(vec![
+                KeyValue::new("host.id", host_key.public_key()),
+                KeyValue::new("host.version", config.version.clone()),
+            ]),
+        );
+        let metrics = Arc::new(HostMetrics::new(
+            &meter,
+            host_key.public_key(),
+            config.lattice.to_string(),
+        ));
+
         let policy_manager = PolicyManager::new(
             ctl_nats.clone(),
             PolicyHostInfo {
@@ -851,41 +1140,41 @@ impl Host {
             },
             config.policy_service_config.policy_topic.clone(),
             config.policy_service_config.policy_timeout_ms,
+            config.policy_service_config.policy_retries,
+            config.policy_service_config.policy_retry_backoff,
             config.policy_service_config.policy_changes_topic.clone(),
+            config.policy_service_config.policy_batch_topic.clone(),
+            config.policy_service_config.policy_decision_ttl,
+            config.policy_service_config.policy_default_decision,
+            Arc::clone(&metrics),
         )
         .await?;
 
         // If provided, secrets topic must be non-empty
         // TODO(#2411): Validate secrets topic prefix as a valid NATS subject
         ensure!(
-            config.secrets_topic_prefix.is_none()
-                || config
-                    .secrets_topic_prefix
-                    .as_ref()
-                    .is_some_and(|topic| !topic.is_empty()),
+            config
+                .secrets_topic_prefix
+                .prefixes()
+                .all(|topic| !topic.is_empty()),
             "secrets topic prefix must be non-empty"
         );
 
         let secrets_manager = Arc::new(SecretsManager::new(
             &config_data,
-            config.secrets_topic_prefix.as_ref(),
+            &config.secrets_topic_prefix,
             &ctl_nats,
+            config.secrets_cache_ttl,
+            config.secrets_cache_max_entries,
+            config.secrets_timeout,
         ));
 
-        let meter = global::meter_with_version(
-            "wasmcloud-host",
-            Some(config.version.clone()),
-            None::<&str>,
-            Some(vec![
-                KeyValue::new("host.id", host_key.public_key()),
-                KeyValue::new("host.version", config.version.clone()),
-            ]),
-        );
-        let metrics = HostMetrics::new(&meter, host_key.public_key(), config.lattice.to_string());
-
         let config_generator = BundleGenerator::new(config_data.clone());
 
         let max_execution_time_ms = config.max_execution_time;
+        let max_concurrent_downloads = config
+            .max_concurrent_downloads
+            .clamp(1, Semaphore::MAX_PERMITS);
 
         let host = Host {
             components: RwLock::default(),
@@ -897,6 +1186,7 @@ impl Host {
             host_token,
             secrets_xkey: Arc::new(XKey::new()),
             labels: RwLock::new(labels),
+            log_level: RwLock::new(config.log_level.clone()),
             ctl_nats,
             rpc_nats: Arc::new(rpc_nats),
             host_config: config,
@@ -912,12 +1202,15 @@ impl Host {
             start_at,
             stop_rx,
             stop_tx,
+            draining: watch::channel(false).0,
+            secrets_ready: watch::channel(true).0,
             queue: queue_abort.clone(),
             links: RwLock::default(),
             component_claims: Arc::default(),
             provider_claims: Arc::default(),
-            metrics: Arc::new(metrics),
+            metrics,
             max_execution_time: max_execution_time_ms,
+            downloads: Semaphore::new(max_concurrent_downloads),
         };
 
         let host = Arc::new(host);
@@ -1020,6 +1313,75 @@ impl Host {
             }
         });
 
+        let labels_watch = spawn({
+            let host = Arc::clone(&host);
+            Abortable::new(
+                async move {
+                    let Some(path) = host.host_config.labels_file.clone() else {
+                        return;
+                    };
+                    let mut poll = tokio::time::interval(Self::LABELS_FILE_POLL_INTERVAL);
+                    poll.reset();
+                    loop {
+                        poll.tick().await;
+                        match read_labels_file(&path).await {
+                            Ok(labels) => {
+                                if let Err(err) = host
+                                    .apply_file_labels(&static_labels, &mut file_labels, labels)
+                                    .await
+                                {
+                                    error!(?err, "failed to apply labels file");
+                                }
+                            }
+                            Err(err) => warn!(?err, "failed to reload labels file"),
+                        }
+                    }
+                },
+                labels_watch_abort_reg,
+            )
+        });
+
+        let trust_refresh = spawn({
+            let host = Arc::clone(&host);
+            Abortable::new(
+                async move {
+                    let Some(interval) = host.host_config.trust_refresh_interval else {
+                        return;
+                    };
+                    let mut refresh = tokio::time::interval(interval);
+                    refresh.reset();
+                    loop {
+                        refresh.tick().await;
+                        if let Err(err) = host.refresh_trust().await {
+                            warn!(?err, "failed to refresh trusted root certificates");
+                        }
+                    }
+                },
+                trust_refresh_abort_reg,
+            )
+        });
+
+        let secrets_health = spawn({
+            let host = Arc::clone(&host);
+            Abortable::new(
+                async move {
+                    if !host.host_config.secrets_topic_prefix.is_configured() {
+                        return;
+                    }
+                    let Some(interval) = host.host_config.secrets_health_check_interval else {
+                        let _ = host.check_secrets_backend().await;
+                        return;
+                    };
+                    let mut check = tokio::time::interval(interval);
+                    loop {
+                        check.tick().await;
+                        let _ = host.check_secrets_backend().await;
+                    }
+                },
+                secrets_health_abort_reg,
+            )
+        });
+
         // Process existing data without emitting events
         data.keys()
             .await
@@ -1050,8 +1412,19 @@ impl Host {
             heartbeat_abort.abort();
             queue_abort.abort();
             data_watch_abort.abort();
+            labels_watch_abort.abort();
+            trust_refresh_abort.abort();
+            secrets_health_abort.abort();
             host.policy_manager.policy_changes.abort();
-            let _ = try_join!(queue, data_watch, heartbeat).context("failed to await tasks")?;
+            let _ = try_join!(
+                queue,
+                data_watch,
+                heartbeat,
+                labels_watch,
+                trust_refresh,
+                secrets_health
+            )
+            .context("failed to await tasks")?;
             host.publish_event(
                 "host_stopped",
                 json!({
@@ -1064,6 +1437,20 @@ impl Host {
             // thought were sent (like the host_stopped event)
             try_join!(host.ctl_nats.flush(), host.rpc_nats.flush(),)
                 .context("failed to flush NATS clients")?;
+            // Reclaim the space taken by extracted providers, keeping any still running
+            let running: Vec<_> = host
+                .providers
+                .read()
+                .await
+                .values()
+                .map(|provider| provider.path.to_string_lossy().into_owned())
+                .collect();
+            if let Err(err) =
+                wasmcloud_core::par::purge_extracted_except(host.host_key.public_key(), running)
+                    .await
+            {
+                warn!(?err, "failed to purge extracted providers");
+            }
             Ok(())
         }))
     }
@@ -1084,6 +1471,177 @@ impl Host {
         Ok(*self.stop_rx.borrow())
     }
 
+    /// Drains the host ahead of a shutdown, e.g. during a rolling update of a host fleet.
+    ///
+    /// The host is labeled [`host_config::UNSCHEDULABLE_LABEL`], rejects commands starting or
+    /// scaling up components and providers, and stops accepting invocations, leaving them to
+    /// other hosts running the same components. Invocations already being handled are given up
+    /// to `timeout` to finish, after which the host is stopped as if by a stop host command.
+    ///
+    /// Returns the number of invocations still running when the timeout elapsed, keyed by
+    /// component ID, which is empty if all invocations finished in time
+    #[instrument(level = "debug", skip(self))]
+    pub async fn drain(&self, timeout: Duration) -> BTreeMap<ComponentId, usize> {
+        info!(?timeout, "draining host");
+        let deadline = Instant::now() + timeout;
+        self.draining.send_replace(true);
+        if let Err(err) = self.publish_unschedulable().await {
+            warn!(?err, "failed to publish unschedulable label");
+        }
+        let running = loop {
+            let running: BTreeMap<_, _> = self
+                .components
+                .read()
+                .await
+                .iter()
+                .filter_map(|(id, component)| match component.in_flight() {
+                    0 => None,
+                    n => Some((id.clone(), n)),
+                })
+                .collect();
+            if running.is_empty() || Instant::now() >= deadline {
+                break running;
+            }
+            sleep(
+                Self::DRAIN_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+            )
+            .await;
+        };
+        if running.is_empty() {
+            info!("all in-flight invocations finished");
+        } else {
+            warn!(?running, "drain timed out with invocations still running");
+        }
+        self.stop(None);
+        running
+    }
+
+    /// Labels the host [`host_config::UNSCHEDULABLE_LABEL`] and publishes the changed labels
+    /// and inventory to the lattice
+    async fn publish_unschedulable(&self) -> anyhow::Result<()> {
+        let labels = {
+            let mut labels = self.labels.write().await;
+            labels.insert(host_config::UNSCHEDULABLE_LABEL.into(), "true".into());
+            labels.clone()
+        };
+        self.publish_event(
+            "labels_changed",
+            event::labels_changed(self.host_key.public_key(), HashMap::from_iter(labels)),
+        )
+        .await
+        .context("failed to publish labels_changed event")?;
+        let heartbeat = self.heartbeat().await?;
+        self.publish_event("host_heartbeat", heartbeat)
+            .await
+            .context("failed to publish heartbeat")
+    }
+
+    /// Stops the background tasks of the host and signals [`Host::stopped`] with `deadline`
+    fn stop(&self, deadline: Option<Instant>) {
+        self.heartbeat.abort();
+        self.data_watch.abort();
+        self.queue.abort();
+        self.policy_manager.policy_changes.abort();
+        self.stop_tx.send_replace(deadline);
+    }
+
+    /// Reloads the trusted root certificates used to fetch OCI artifacts from the system trust
+    /// store and the configured additional CA paths, so that rotated or revoked CAs take effect
+    /// without a restart. Fetches started afterwards use the new roots.
+    ///
+    /// The host builds a registry client for every fetch, so this relies on global state: the
+    /// native root store shared by all registry clients of the process is reloaded, see
+    /// [`tls::refresh_native_roots`], and read by the clients built afterwards. The additional CA
+    /// paths are read again by every fetch, so they are only checked to still load here.
+    ///
+    /// This is done periodically if [`HostConfig::trust_refresh_interval`] is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificates could not be loaded, in which case the previous roots
+    /// stay in use
+    #[instrument(level = "debug", skip(self))]
+    pub async fn refresh_trust(&self) -> anyhow::Result<()> {
+        let ca_paths = self.host_config.oci_opts.additional_ca_paths.clone();
+        spawn_blocking(move || {
+            tls::load_certs_from_paths(&ca_paths)
+                .context("failed to load CA certs from provided paths")?;
+            let roots =
+                tls::refresh_native_roots().context("failed to reload native root store")?;
+            debug!(roots, "reloaded trusted root certificates");
+            Ok(())
+        })
+        .await
+        .context("trust refresh task panicked")?
+    }
+
+    /// Checks that the configured secrets backends are reachable, see
+    /// [`SecretsManager::check_backends`], and records the result. While the secrets backends are
+    /// unreachable, the host does not take part in component and provider auctions, so that
+    /// workloads are not scheduled on a host that may be unable to provision their secrets.
+    ///
+    /// This is done periodically if [`HostConfig::secrets_health_check_interval`] is set, and
+    /// once at startup otherwise. Each check is bounded by [`HostConfig::secrets_timeout`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a secrets backend is unreachable
+    #[instrument(level = "debug", skip(self))]
+    pub async fn check_secrets_backend(&self) -> anyhow::Result<()> {
+        let res = self.secrets_manager.check_backends().await;
+        let ready = res.is_ok();
+        let was_ready = self.secrets_ready.send_replace(ready);
+        match &res {
+            Err(err) if was_ready => warn!(?err, "secrets backend became unreachable"),
+            Err(err) => debug!(?err, "secrets backend still unreachable"),
+            Ok(()) if !was_ready => info!("secrets backend reachable again"),
+            Ok(()) => {}
+        }
+        res
+    }
+
+    /// Returns whether the secrets backends were reachable when last checked. Always `true` if no
+    /// secrets backend is configured
+    #[must_use]
+    pub fn secrets_backend_ready(&self) -> bool {
+        *self.secrets_ready.borrow()
+    }
+
+    /// Changes the log level of the host and of all providers it is currently running. The new
+    /// level takes effect immediately and is also passed to providers started afterwards.
+    ///
+    /// This is safe to call concurrently with itself and with any other host operation. The level
+    /// is held under a write lock until it has been published to all running providers, so
+    /// concurrent calls are applied one after another and providers never end up with a
+    /// different level than the host.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host's logging filter could not be changed or the level could not
+    /// be published to the running providers
+    #[instrument(level = "debug", skip(self))]
+    pub async fn set_log_level(&self, level: LogLevel) -> anyhow::Result<()> {
+        let mut log_level = self.log_level.write().await;
+        wasmcloud_tracing::set_log_level(&level).context("failed to change host log level")?;
+        *log_level = level;
+        let payload =
+            Bytes::from(serde_json::to_vec(&*log_level).context("failed to serialize log level")?);
+        let providers = self.providers.read().await;
+        for provider_id in providers.keys() {
+            self.rpc_nats
+                .publish(
+                    provider_log_level_subject(&self.host_config.lattice, provider_id),
+                    payload.clone(),
+                )
+                .await
+                .with_context(|| {
+                    format!("failed to publish log level to provider `{provider_id}`")
+                })?;
+        }
+        info!(level = ?*log_level, "log level changed");
+        Ok(())
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn inventory(&self) -> HostInventory {
         trace!("generating host inventory");
@@ -1133,12 +1691,16 @@ impl Host {
                         annotations,
                         claims_token,
                         image_ref,
+                        digest,
                         ..
                     },
                 )| {
                     let mut provider_description = ProviderDescription::builder()
                         .id(provider_id)
                         .image_ref(image_ref);
+                    if let Some(digest) = digest {
+                        provider_description = provider_description.digest(digest);
+                    }
                     if let Some(name) = claims_token
                         .as_ref()
                         .and_then(|claims| claims.claims.metadata.as_ref())
@@ -1186,6 +1748,48 @@ impl Host {
         Ok(serde_json::to_value(self.inventory().await)?)
     }
 
+    /// Replaces the labels previously read from the labels file, `applied`, with `labels`,
+    /// reverting labels no longer in the file to their value in `static_labels`. The changed
+    /// labels and inventory are published to the lattice
+    #[instrument(level = "debug", skip_all)]
+    async fn apply_file_labels(
+        &self,
+        static_labels: &BTreeMap<String, String>,
+        applied: &mut BTreeMap<String, String>,
+        labels: BTreeMap<String, String>,
+    ) -> anyhow::Result<()> {
+        if *applied == labels {
+            return Ok(());
+        }
+        let changed = {
+            let mut host_labels = self.labels.write().await;
+            for key in applied.keys() {
+                if labels.contains_key(key) {
+                    continue;
+                }
+                if let Some(value) = static_labels.get(key) {
+                    host_labels.insert(key.clone(), value.clone());
+                } else {
+                    host_labels.remove(key);
+                }
+            }
+            host_labels.extend(labels.clone());
+            host_labels.clone()
+        };
+        *applied = labels;
+        info!("reloaded labels file");
+        self.publish_event(
+            "labels_changed",
+            event::labels_changed(self.host_key.public_key(), HashMap::from_iter(changed)),
+        )
+        .await
+        .context("failed to publish labels_changed event")?;
+        let heartbeat = self.heartbeat().await?;
+        self.publish_event("host_heartbeat", heartbeat)
+            .await
+            .context("failed to publish heartbeat")
+    }
+
     #[instrument(level = "debug", skip(self))]
     async fn publish_event(&self, name: &str, data: serde_json::Value) -> anyhow::Result<()> {
         event::publish(
@@ -1216,8 +1820,17 @@ impl Host {
             "instantiating component"
         );
 
-        let max_execution_time = self.max_execution_time;
-        component.set_max_execution_time(max_execution_time);
+        let limits = self.host_config.component_limits_for(&id, &image_reference);
+        component
+            .set_max_execution_time(limits.max_execution_time.unwrap_or(self.max_execution_time));
+        if let Some(max_linear_memory) = limits.max_linear_memory {
+            component.set_max_linear_memory(max_linear_memory);
+        }
+        component.set_warm_instances(
+            usize::try_from(self.host_config.warm_instances)
+                .unwrap_or(usize::MAX)
+                .min(max_instances.get()),
+        );
 
         let (events_tx, mut events_rx) = mpsc::channel(
             max_instances
@@ -1251,10 +1864,12 @@ impl Host {
             usize::from(max_instances).min(Semaphore::MAX_PERMITS),
         ));
         let metrics = Arc::clone(&self.metrics);
+        let mut draining = self.draining.subscribe();
         Ok(Arc::new(Component {
             component,
             id,
             handler,
+            permits: Arc::clone(&permits),
             exports: spawn(
                 async move {
                     join!(
@@ -1264,6 +1879,11 @@ impl Host {
                             loop {
                                 let permits = Arc::clone(&permits);
                                 select! {
+                                    // Once draining, stop accepting invocations, leaving them to
+                                    // other hosts, and only finish those already accepted
+                                    () = async {
+                                        let _ = draining.wait_for(|draining| *draining).await;
+                                    } => break,
                                     Some(fut) = exports.next() => {
                                         match fut {
                                             Ok(fut) => {
@@ -1296,6 +1916,12 @@ impl Host {
                                     }
                                 }
                             }
+                            drop(exports);
+                            while let Some(res) = tasks.join_next().await {
+                                if let Err(err) = res {
+                                    error!(?err, "export serving task failed");
+                                }
+                            }
                         },
                         async move {
                             while let Some(evt) = events_rx.recv().await {
@@ -1372,6 +1998,7 @@ impl Host {
             trace_ctx: Arc::default(),
             instance_links: Arc::new(RwLock::new(component_import_links(&component_spec.links))),
             invocation_timeout: Duration::from_secs(10), // TODO: Make this configurable
+            max_payload: self.host_config.rpc_max_payload,
         };
         let component = wasmcloud_runtime::Component::new(&self.runtime, &wasm)?;
         let component = self
@@ -1436,6 +2063,14 @@ impl Host {
             .all(|(k, v)| host_labels.get(k).is_some_and(|hv| hv == v));
         let component_id_running = self.components.read().await.contains_key(component_id);
 
+        if !self.secrets_backend_ready() {
+            debug!(
+                component_id,
+                "secrets backend unreachable, not bidding on auction"
+            );
+            return Ok(None);
+        }
+
         // This host can run the component if all constraints are satisfied and the component is not already running
         if constraints_satisfied && !component_id_running {
             Ok(Some(CtlResponse::ok(
@@ -1475,6 +2110,13 @@ impl Host {
             .all(|(k, v)| host_labels.get(k).is_some_and(|hv| hv == v));
         let providers = self.providers.read().await;
         let provider_running = providers.contains_key(provider_id);
+        if !self.secrets_backend_ready() {
+            debug!(
+                provider_id,
+                "secrets backend unreachable, not bidding on auction"
+            );
+            return Ok(None);
+        }
         if constraints_satisfied && !provider_running {
             Ok(Some(CtlResponse::ok(
                 ProviderAuctionAck::builder()
@@ -1490,14 +2132,67 @@ impl Host {
         }
     }
 
+    /// Waits for a started provider to answer a health check, which it does once initialized
+    #[instrument(level = "debug", skip(self, child))]
+    async fn wait_for_provider_startup(
+        &self,
+        provider_id: &str,
+        child: &mut process::Child,
+    ) -> anyhow::Result<()> {
+        let subject = health_subject(&self.host_config.lattice, provider_id);
+        loop {
+            select! {
+                status = child.wait() => {
+                    let status = status.context("failed to wait for provider process")?;
+                    bail!("provider exited during startup with `{status}`");
+                }
+                res = self.rpc_nats.request(subject.clone(), Bytes::new()) => {
+                    if res.is_ok() {
+                        return Ok(());
+                    }
+                    trace!(provider_id, "provider not healthy yet");
+                }
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Returns the options for fetching artifacts from OCI registries configured for this host
+    fn fetch_options(&self) -> FetchOptions {
+        FetchOptions {
+            max_component_size: None,
+            max_provider_size: self.host_config.max_provider_size,
+            pull_policy: self.host_config.oci_opts.pull_policy,
+            default_registry: self.host_config.oci_opts.default_registry.clone(),
+        }
+    }
+
     #[instrument(level = "trace", skip_all)]
-    async fn fetch_component(&self, component_ref: &str) -> anyhow::Result<Vec<u8>> {
+    async fn fetch_component(
+        &self,
+        component_id: &str,
+        component_ref: &str,
+    ) -> anyhow::Result<(Vec<u8>, Option<jwt::Token<jwt::Component>>)> {
+        let max_component_size = self
+            .host_config
+            .component_limits_for(component_id, component_ref)
+            .max_component_size
+            .unwrap_or(self.host_config.max_component_size);
+        let _permit = self
+            .downloads
+            .acquire()
+            .await
+            .context("failed to acquire download permit")?;
         let registry_config = self.registry_config.read().await;
-        fetch_component(
+        fetch_component_signed(
             component_ref,
             self.host_config.allow_file_load,
             &self.host_config.oci_opts.additional_ca_paths,
             &registry_config,
+            &FetchOptions {
+                max_component_size: Some(max_component_size),
+                ..self.fetch_options()
+            },
         )
         .await
         .context("failed to fetch component")
@@ -1547,13 +2242,9 @@ impl Host {
 
         info!(?timeout, "handling stop host");
 
-        self.heartbeat.abort();
-        self.data_watch.abort();
-        self.queue.abort();
-        self.policy_manager.policy_changes.abort();
         let deadline =
             timeout.and_then(|timeout| Instant::now().checked_add(Duration::from_millis(timeout)));
-        self.stop_tx.send_replace(deadline);
+        self.stop(deadline);
         Ok(CtlResponse::<()>::success(
             "successfully handled stop host".into(),
         ))
@@ -1579,6 +2270,23 @@ impl Host {
             max_instances, component_id, "handling scale component"
         );
 
+        if max_instances > 0 && *self.draining.borrow() {
+            return Ok(CtlResponse::error(
+                "host is draining and does not accept new components",
+            ));
+        }
+        if let Some(limit) = self
+            .host_config
+            .component_limits_for(component_id, component_ref)
+            .max_instances
+        {
+            if max_instances > limit {
+                return Ok(CtlResponse::error(&format!(
+                    "cannot scale component `{component_id}` to {max_instances} instances, the host allows at most {limit}"
+                )));
+            }
+        }
+
         let host_id = host_id.to_string();
         let annotations: Annotations = annotations
             .cloned()
@@ -1624,22 +2332,18 @@ impl Host {
 
         let component_id = Arc::from(component_id);
         let component_ref = Arc::from(component_ref);
-        // Spawn a task to perform the scaling and possibly an update of the component afterwards
+        // Spawn a task to perform the scaling and possibly an update of the component afterwards.
+        // The task is traced under the request span, so that fetching the component is as well
         spawn(async move {
             // Fetch the component from the reference
-            let component_and_claims =
-                self.fetch_component(&component_ref)
-                    .await
-                    .map(|component_bytes| {
-                        // Pull the claims token from the component, this returns an error only if claims are embedded
-                        // and they are invalid (expired, tampered with, etc)
-                        let claims_token =
-                            wasmcloud_runtime::component::claims_token(&component_bytes);
-                        (component_bytes, claims_token)
-                    });
-            let (wasm, claims_token) = match component_and_claims {
-                Ok((wasm, Ok(claims_token))) => (wasm, claims_token),
-                Err(e) | Ok((_, Err(e))) => {
+            // Claims are extracted along with the fetch, this returns an error if claims are
+            // embedded and they are invalid (expired, tampered with, etc)
+            let (wasm, claims_token) = match self
+                .fetch_component(&component_id, &component_ref)
+                .await
+            {
+                Ok(component_and_claims) => component_and_claims,
+                Err(e) => {
                     if let Err(e) = self
                         .publish_event(
                             "component_scale_failed",
@@ -1708,7 +2412,7 @@ impl Host {
                     error!(%component_ref, %component_id, err = ?e, "failed to update component after scale");
                 }
             }
-        });
+        }.in_current_span());
 
         Ok(CtlResponse::<()>::success(message))
     }
@@ -1945,7 +2649,7 @@ impl Host {
             {
                 error!(%new_component_ref, %component_id, err = ?e, "failed to update component");
             }
-        });
+        }.in_current_span());
 
         Ok(CtlResponse::<()>::success(message))
     }
@@ -1972,7 +2676,9 @@ impl Host {
                 return Ok(());
             }
 
-            let new_component = self.fetch_component(&new_component_ref).await?;
+            let (new_component, _) = self
+                .fetch_component(&component_id, &new_component_ref)
+                .await?;
             let new_component = wasmcloud_runtime::Component::new(&self.runtime, &new_component)
                 .context("failed to initialize component")?;
             let new_claims = new_component.claims().cloned();
@@ -2052,6 +2758,11 @@ impl Host {
                 "provider with that ID is already running",
             ));
         }
+        if *self.draining.borrow() {
+            return Ok(CtlResponse::error(
+                "host is draining and does not accept new providers",
+            ));
+        }
 
         // NOTE: We log at info since starting providers can take a while
         info!(
@@ -2061,34 +2772,37 @@ impl Host {
         );
 
         let host_id = host_id.to_string();
-        spawn(async move {
-            let config = cmd.config();
-            let provider_id = cmd.provider_id();
-            let provider_ref = cmd.provider_ref();
-            let annotations = cmd.annotations();
+        spawn(
+            async move {
+                let config = cmd.config();
+                let provider_id = cmd.provider_id();
+                let provider_ref = cmd.provider_ref();
+                let annotations = cmd.annotations();
 
-            if let Err(err) = self
-                .handle_start_provider_task(
-                    config,
-                    provider_id,
-                    provider_ref,
-                    annotations.cloned().unwrap_or_default(),
-                    &host_id,
-                )
-                .await
-            {
-                error!(provider_ref, provider_id, ?err, "failed to start provider");
                 if let Err(err) = self
-                    .publish_event(
-                        "provider_start_failed",
-                        event::provider_start_failed(provider_ref, provider_id, &err),
+                    .handle_start_provider_task(
+                        config,
+                        provider_id,
+                        provider_ref,
+                        annotations.cloned().unwrap_or_default(),
+                        &host_id,
                     )
                     .await
                 {
-                    error!(?err, "failed to publish provider_start_failed event");
+                    error!(provider_ref, provider_id, ?err, "failed to start provider");
+                    if let Err(err) = self
+                        .publish_event(
+                            "provider_start_failed",
+                            event::provider_start_failed(provider_ref, provider_id, &err),
+                        )
+                        .await
+                    {
+                        error!(?err, "failed to publish provider_start_failed event");
+                    }
                 }
             }
-        });
+            .in_current_span(),
+        );
         Ok(CtlResponse::<()>::success(
             "successfully started provider".into(),
         ))
@@ -2105,15 +2819,26 @@ impl Host {
     ) -> anyhow::Result<()> {
         trace!(provider_ref, provider_id, "start provider task");
 
+        let permit = self
+            .downloads
+            .acquire()
+            .await
+            .context("failed to acquire download permit")?;
         let registry_config = self.registry_config.read().await;
-        let (path, claims_token) = crate::fetch_provider(
+        let FetchedProvider {
+            path,
+            claims: claims_token,
+            digest,
+        } = crate::fetch_provider(
             provider_ref,
             host_id,
             self.host_config.allow_file_load,
             &registry_config,
+            &self.fetch_options(),
         )
         .await
         .context("failed to fetch provider")?;
+        drop(permit);
         let claims = claims_token.as_ref().map(|t| t.claims.clone());
 
         if let Some(claims) = claims.clone() {
@@ -2181,13 +2906,23 @@ impl Host {
                 protocol: self.host_config.otel_config.protocol,
                 additional_ca_paths: self.host_config.otel_config.additional_ca_paths.clone(),
                 trace_level: self.host_config.otel_config.trace_level.clone(),
+                trace_sampling_ratio: self.host_config.otel_config.trace_sampling_ratio,
+                // Providers report the ID of the host running them, like the host itself does
+                resource_attributes: self
+                    .host_config
+                    .otel_config
+                    .resource_attributes
+                    .clone()
+                    .into_iter()
+                    .chain([("service.instance.id".into(), self.host_key.public_key())])
+                    .collect(),
             };
 
             let provider_xkey = XKey::new();
             // The provider itself needs to know its private key
             let provider_xkey_private_key = if let Ok(seed) = provider_xkey.seed() {
                 seed
-            } else if self.host_config.secrets_topic_prefix.is_none() {
+            } else if !self.host_config.secrets_topic_prefix.is_configured() {
                 "".to_string()
             } else {
                 // This should never happen since this returns an error when an Xkey is
@@ -2273,7 +3008,7 @@ impl Host {
                 host_xkey_public_key: self.secrets_xkey.public_key(),
                 cluster_issuers: vec![],
                 default_rpc_timeout_ms,
-                log_level: Some(self.host_config.log_level.clone()),
+                log_level: Some(self.log_level.read().await.clone()),
                 structured_logging: self.host_config.enable_structured_logging,
                 otel_config,
             };
@@ -2317,23 +3052,50 @@ impl Host {
                 .context("failed to write newline")?;
             stdin.shutdown().await.context("failed to close stdin")?;
 
+            if let Some(timeout) = self.host_config.provider_startup_timeout {
+                // The child process is killed on drop if the provider fails to start
+                let Ok(started) = tokio::time::timeout(
+                    timeout,
+                    self.wait_for_provider_startup(provider_id, &mut child),
+                )
+                .await
+                else {
+                    warn!(
+                        provider_ref,
+                        provider_id,
+                        ?timeout,
+                        "provider startup timed out"
+                    );
+                    self.publish_event(
+                        "provider_start_timed_out",
+                        event::provider_start_timed_out(provider_ref, provider_id, timeout),
+                    )
+                    .await?;
+                    bail!("provider did not become healthy within {timeout:?}");
+                };
+                started?;
+            }
+
             // Create a channel for watching for child process exit
             let (exit_tx, exit_rx) = broadcast::channel::<()>(1);
-            spawn(async move {
-                match child.wait().await {
-                    Ok(status) => {
-                        debug!("provider @ [{}] exited with `{status:?}`", path.display());
+            spawn({
+                let path = path.clone();
+                async move {
+                    match child.wait().await {
+                        Ok(status) => {
+                            debug!("provider @ [{}] exited with `{status:?}`", path.display());
+                        }
+                        Err(e) => {
+                            error!(
+                                "failed to wait for provider @ [{}] to execute: {e}",
+                                path.display()
+                            );
+                        }
                     }
-                    Err(e) => {
-                        error!(
-                            "failed to wait for provider @ [{}] to execute: {e}",
-                            path.display()
-                        );
+                    if let Err(err) = exit_tx.send(()) {
+                        warn!(%err, "failed to send exit tx");
                     }
                 }
-                if let Err(err) = exit_tx.send(()) {
-                    warn!(%err, "failed to send exit tx");
-                }
             });
             let mut exit_health_rx = exit_rx.resubscribe();
 
@@ -2505,6 +3267,8 @@ impl Host {
                 annotations,
                 claims_token,
                 image_ref: provider_ref.to_string(),
+                path,
+                digest,
                 xkey,
                 config,
             });
@@ -2637,6 +3401,9 @@ impl Host {
             .context("failed to deserialize put label request")?;
         let key = host_label.key();
         let value = host_label.value();
+        if let Err(err) = host_config::validate_label(key, value) {
+            return Ok(CtlResponse::error(&err.to_string()));
+        }
         let mut labels = self.labels.write().await;
         match labels.entry(key.into()) {
             BTreeMapEntry::Occupied(mut entry) => {
@@ -2668,6 +3435,11 @@ impl Host {
         let label = serde_json::from_slice::<HostLabel>(payload.as_ref())
             .context("failed to deserialize delete label request")?;
         let key = label.key();
+        if host_config::BUILTIN_LABELS.contains(&key) {
+            return Ok(CtlResponse::error(&format!(
+                "built-in label `{key}` cannot be removed"
+            )));
+        }
         let mut labels = self.labels.write().await;
         let value = labels.remove(key);
 
@@ -2865,6 +3637,34 @@ impl Host {
         ))
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_secrets_invalidate(
+        &self,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        #[derive(Default, Deserialize)]
+        struct InvalidateSecretsCommand {
+            /// The backend whose secrets to invalidate, or all secrets if unset
+            backend: Option<String>,
+        }
+
+        let payload = payload.as_ref();
+        let InvalidateSecretsCommand { backend } = if payload.is_empty() {
+            InvalidateSecretsCommand::default()
+        } else {
+            serde_json::from_slice(payload)
+                .context("failed to deserialize secrets invalidate command")?
+        };
+        let invalidated = self
+            .secrets_manager
+            .invalidate_secrets(backend.as_deref())
+            .await;
+        info!(?backend, invalidated, "invalidated cached secrets");
+        Ok(CtlResponse::<()>::success(format!(
+            "invalidated {invalidated} cached secrets"
+        )))
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_registries_put(
         &self,
@@ -3077,6 +3877,12 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            // Secrets commands
+            (Some("secrets"), Some("invalidate"), None, None) => self
+                .handle_secrets_invalidate(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Config commands
             (Some("config"), Some("get"), Some(config_name), None) => self
                 .handle_config_get(config_name)
@@ -3987,6 +4793,52 @@ fn injector_to_headers(injector: &TraceContextInjector) -> async_nats::header::H
 
 #[cfg(test)]
 mod test {
+    #[test]
+    fn parse_labels() -> anyhow::Result<()> {
+        let labels = super::parse_labels(
+            r#"
+# set by the node agent
+zone="eu-west-1a"
+schedulable = true
+empty=
+"#,
+        )?;
+        assert_eq!(
+            labels,
+            std::collections::BTreeMap::from([
+                ("empty".to_string(), String::new()),
+                ("schedulable".to_string(), "true".to_string()),
+                ("zone".to_string(), "eu-west-1a".to_string()),
+            ])
+        );
+        assert!(super::parse_labels("zone").is_err());
+        assert!(super::parse_labels("=value").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn nats_tls_config() -> anyhow::Result<()> {
+        use super::NatsTls;
+
+        assert!(NatsTls::default().client_config()?.is_none());
+        let dir = tempfile::tempdir()?;
+        let empty = dir.path().join("empty.pem");
+        std::fs::write(&empty, "")?;
+        assert!(NatsTls {
+            ca_path: Some(&empty),
+            ..Default::default()
+        }
+        .client_config()
+        .is_err());
+        assert!(NatsTls {
+            client_key: Some(&empty),
+            ..Default::default()
+        }
+        .client_config()
+        .is_err());
+        Ok(())
+    }
+
     // Ensure that the helper function to translate a list of links into a map of imports works as expected
     #[test]
     fn can_compute_component_links() {
@@ -21,12 +21,13 @@ use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 use tokio::task::{spawn_blocking, JoinSet};
 use tokio::{select, spawn, try_join};
 use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
+use wasmcloud_core::logging::Level;
 use wasmcloud_core::nats::convert_header_map_to_hashmap;
 use wasmcloud_core::rpc::{health_subject, link_del_subject, link_put_subject, shutdown_subject};
 use wasmcloud_core::secrets::SecretValue;
 use wasmcloud_core::{
-    provider_config_update_subject, HealthCheckRequest, HealthCheckResponse, HostData,
-    InterfaceLinkDefinition, LatticeTarget,
+    provider_config_update_subject, provider_log_level_subject, HealthCheckRequest,
+    HealthCheckResponse, HostData, InterfaceLinkDefinition, LatticeTarget,
 };
 
 #[cfg(feature = "otel")]
@@ -369,6 +370,39 @@ async fn subscribe_config_update(
     Ok(config_update_rx)
 }
 
+/// Subscribe to log level changes that are passed by the host.
+///
+/// Log level changes are applied directly to the logging filter of the provider, they are not
+/// forwarded to the [`Provider`] implementation.
+async fn subscribe_log_level(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    lattice: &str,
+    provider_key: &str,
+) -> ProviderInitResult<()> {
+    let mut sub = nats
+        .subscribe(provider_log_level_subject(lattice, provider_key).to_subject())
+        .await?;
+    spawn({
+        async move {
+            process_until_quit!(sub, quit, msg, {
+                match serde_json::from_slice::<Level>(&msg.payload) {
+                    Ok(level) => {
+                        if let Err(err) = wasmcloud_tracing::set_log_level(&level) {
+                            error!(%err, "failed to change log level");
+                        }
+                    }
+                    Err(err) => {
+                        error!(%err, "received invalid log level on message");
+                    }
+                }
+            });
+        }
+        .instrument(tracing::debug_span!("subscribe_log_level"))
+    });
+    Ok(())
+}
+
 pub(crate) struct ProviderCommandReceivers {
     pub health: mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>,
     pub shutdown: mpsc::Receiver<oneshot::Sender<()>>,
@@ -488,7 +522,7 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
     let nats = Arc::new(nats);
 
     // Listen and process various provider events/functionality
-    let (health, shutdown, link_put, link_del, config_update) = try_join!(
+    let (health, shutdown, link_put, link_del, config_update, ()) = try_join!(
         subscribe_health(
             Arc::clone(&nats),
             quit_tx.subscribe(),
@@ -520,6 +554,12 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
             lattice_rpc_prefix,
             provider_key,
         ),
+        subscribe_log_level(
+            Arc::clone(&nats),
+            quit_tx.subscribe(),
+            lattice_rpc_prefix,
+            provider_key,
+        ),
     )?;
     Ok(ProviderInitState {
         nats,
@@ -1,4 +1,4 @@
-use super::{new_store, Ctx, Handler, Instance, ReplacedInstanceTarget, WrpcServeEvent};
+use super::{Ctx, Handler, Instance, ReplacedInstanceTarget, WrpcServeEvent};
 
 use crate::capability::http::types;
 
@@ -134,14 +134,13 @@ where
         let scheme = wrpc_interface_http::bindings::wrpc::http::types::Scheme::from(scheme).into();
 
         let (tx, rx) = oneshot::channel();
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
-        let pre = incoming_http_bindings::IncomingHttpPre::new(self.pre.clone())
-            .context("failed to pre-instantiate `wasi:http/incoming-handler`")?;
         trace!("instantiating `wasi:http/incoming-handler`");
-        let bindings = pre
-            .instantiate_async(&mut store)
+        let (mut store, instance) = self
+            .instantiate()
             .await
             .context("failed to instantiate `wasi:http/incoming-handler`")?;
+        let bindings = incoming_http_bindings::IncomingHttp::new(&mut store, &instance)
+            .context("failed to instantiate `wasi:http/incoming-handler`")?;
         let data = store.data_mut();
 
         // The below is adapted from `WasiHttpView::new_incoming_request`, which is unusable for
@@ -1,4 +1,4 @@
-use super::{new_store, Ctx, Handler, Instance, WrpcServeEvent};
+use super::{Ctx, Handler, Instance, WrpcServeEvent};
 
 use crate::capability::messaging::{consumer, types};
 use crate::capability::wrpc;
@@ -102,10 +102,9 @@ where
             reply_to,
         }: wrpc_handler_bindings::wasmcloud::messaging::types::BrokerMessage,
     ) -> anyhow::Result<Result<(), String>> {
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
-        let pre = wasmtime_handler_bindings::MessagingHandlerPre::new(self.pre.clone())
-            .context("failed to pre-instantiate `wasmcloud:messaging/handler`")?;
-        let bindings = pre.instantiate_async(&mut store).await?;
+        let (mut store, instance) = self.instantiate().await?;
+        let bindings = wasmtime_handler_bindings::MessagingHandler::new(&mut store, &instance)
+            .context("failed to instantiate `wasmcloud:messaging/handler`")?;
         let res = bindings
             .wasmcloud_messaging_handler()
             .call_handle_message(
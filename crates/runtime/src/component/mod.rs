@@ -6,10 +6,12 @@ use core::future::Future;
 use core::pin::Pin;
 use core::time::Duration;
 
+use std::sync::Arc;
+
 use anyhow::{ensure, Context as _};
 use futures::{Stream, TryStreamExt as _};
 use tokio::io::{AsyncRead, AsyncReadExt as _};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Semaphore};
 use tracing::{debug, instrument, warn, Instrument as _, Span};
 use wascap::jwt;
 use wascap::wasm::extract_claims;
@@ -37,8 +39,11 @@ mod http;
 mod keyvalue;
 mod logging;
 mod messaging;
+mod pool;
 mod secrets;
 
+use pool::WarmPool;
+
 /// Instance target, which is replaced in wRPC
 ///
 /// This enum represents the original instance import invoked by the component
@@ -269,6 +274,9 @@ where
     claims: Option<jwt::Claims<jwt::Component>>,
     instance_pre: wasmtime::component::InstancePre<Ctx<H>>,
     max_execution_time: Duration,
+    max_memory_size: Option<usize>,
+    warm_instances: usize,
+    warm_budget: Arc<Semaphore>,
 }
 
 impl<H> Debug for Component<H>
@@ -280,6 +288,8 @@ where
             .field("claims", &self.claims)
             .field("runtime", &"wasmtime")
             .field("max_execution_time", &self.max_execution_time)
+            .field("max_memory_size", &self.max_memory_size)
+            .field("warm_instances", &self.warm_instances)
             .finish_non_exhaustive()
     }
 }
@@ -288,6 +298,7 @@ fn new_store<H: Handler>(
     engine: &wasmtime::Engine,
     handler: H,
     max_execution_time: Duration,
+    max_memory_size: Option<usize>,
 ) -> wasmtime::Store<Ctx<H>> {
     let table = ResourceTable::new();
     let wasi = WasiCtxBuilder::new()
@@ -304,9 +315,15 @@ fn new_store<H: Handler>(
             table,
             shared_resources: SharedResourceTable::default(),
             timeout: max_execution_time,
+            limits: max_memory_size
+                .map_or_else(wasmtime::StoreLimitsBuilder::new, |size| {
+                    wasmtime::StoreLimitsBuilder::new().memory_size(size)
+                })
+                .build(),
         },
     );
     store.set_epoch_deadline(max_execution_time.as_secs());
+    store.limiter(|ctx| &mut ctx.limits);
     store
 }
 
@@ -433,6 +450,9 @@ where
             claims,
             instance_pre,
             max_execution_time: rt.max_execution_time,
+            max_memory_size: None,
+            warm_instances: 0,
+            warm_budget: Arc::clone(&rt.warm_budget),
         })
     }
 
@@ -444,6 +464,29 @@ where
         self
     }
 
+    /// Sets the maximum size in bytes of each linear memory of instances of this component,
+    /// lowering the limit configured on the [Runtime]. Memory growth beyond it fails.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_max_linear_memory(&mut self, max_linear_memory: u64) -> &mut Self {
+        self.max_memory_size = Some(usize::try_from(max_linear_memory).unwrap_or(usize::MAX));
+        self
+    }
+
+    /// Sets the number of instances of this component kept pre-instantiated while it is served
+    /// by [Component::serve_wrpc], so that `wasi:http/incoming-handler` and
+    /// `wasmcloud:messaging/handler` invocations do not pay for instantiation. Defaults to 0.
+    ///
+    /// Other exports are served by instantiating the component for every invocation, so no
+    /// instances are kept warm for components that export neither of these interfaces.
+    ///
+    /// Warm instances take up instance slots and linear memory like running ones, so their total
+    /// across all components is limited to half of the runtime's maximum number of components.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_warm_instances(&mut self, warm_instances: usize) -> &mut Self {
+        self.warm_instances = warm_instances;
+        self
+    }
+
     /// Reads the WebAssembly binary asynchronously and calls [Component::new].
     ///
     /// # Errors
@@ -476,6 +519,20 @@ where
         self.claims.as_ref()
     }
 
+    /// Whether this [Component] exports an interface whose invocations take instances from the
+    /// warm pool, see [Component::set_warm_instances]
+    fn has_pooled_exports(&self) -> bool {
+        self.instance_pre
+            .component()
+            .component_type()
+            .exports(&self.engine)
+            .any(|(name, ty)| {
+                matches!(ty, types::ComponentItem::ComponentInstance(..))
+                    && (name.starts_with("wasi:http/incoming-handler@0.2")
+                        || name == "wasmcloud:messaging/handler@0.2.0")
+            })
+    }
+
     /// Serve all exports of this [Component] using supplied [`wrpc_transport::Serve`]
     ///
     /// The returned [Vec] contains an [InvocationStream] per each function exported by the component.
@@ -494,13 +551,27 @@ where
     {
         let span = Span::current();
         let max_execution_time = self.max_execution_time;
+        let max_memory_size = self.max_memory_size;
         let mut invocations = vec![];
+        let pool = (self.warm_instances > 0 && self.has_pooled_exports()).then(|| {
+            WarmPool::new(
+                self.engine.clone(),
+                self.instance_pre.clone(),
+                handler.clone(),
+                self.max_execution_time,
+                self.max_memory_size,
+                self.warm_instances,
+                Arc::clone(&self.warm_budget),
+            )
+        });
         let instance = Instance {
             engine: self.engine.clone(),
             pre: self.instance_pre.clone(),
             handler: handler.clone(),
             max_execution_time: self.max_execution_time,
+            max_memory_size: self.max_memory_size,
             events: events.clone(),
+            pool,
         };
         for (name, ty) in self
             .instance_pre
@@ -541,7 +612,14 @@ where
                     debug!(?name, "serving root function");
                     let func = srv
                         .serve_function(
-                            move || new_store(&engine, handler.clone(), max_execution_time),
+                            move || {
+                                new_store(
+                                    &engine,
+                                    handler.clone(),
+                                    max_execution_time,
+                                    max_memory_size,
+                                )
+                            },
                             pre,
                             ty,
                             "",
@@ -595,7 +673,12 @@ where
                                 let func = srv
                                     .serve_function(
                                         move || {
-                                            new_store(&engine, handler.clone(), max_execution_time)
+                                            new_store(
+                                                &engine,
+                                                handler.clone(),
+                                                max_execution_time,
+                                                max_memory_size,
+                                            )
                                         },
                                         pre,
                                         ty,
@@ -684,7 +767,9 @@ where
     pre: wasmtime::component::InstancePre<Ctx<H>>,
     handler: H,
     max_execution_time: Duration,
+    max_memory_size: Option<usize>,
     events: mpsc::Sender<WrpcServeEvent<C>>,
+    pool: Option<Arc<WarmPool<H>>>,
 }
 
 impl<H, C> Clone for Instance<H, C>
@@ -697,8 +782,32 @@ where
             pre: self.pre.clone(),
             handler: self.handler.clone(),
             max_execution_time: self.max_execution_time,
+            max_memory_size: self.max_memory_size,
             events: self.events.clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl<H, C> Instance<H, C>
+where
+    H: Handler,
+{
+    /// Returns a store with an instance of the component, taken from the warm pool if possible
+    async fn instantiate(
+        &self,
+    ) -> anyhow::Result<(wasmtime::Store<Ctx<H>>, wasmtime::component::Instance)> {
+        if let Some(warm) = self.pool.as_ref().and_then(WarmPool::take) {
+            return Ok(warm);
         }
+        let mut store = new_store(
+            &self.engine,
+            self.handler.clone(),
+            self.max_execution_time,
+            self.max_memory_size,
+        );
+        let instance = self.pre.instantiate_async(&mut store).await?;
+        Ok((store, instance))
     }
 }
 
@@ -714,6 +823,7 @@ where
     table: ResourceTable,
     shared_resources: SharedResourceTable,
     timeout: Duration,
+    limits: wasmtime::StoreLimits,
 }
 
 impl<H: Handler> WasiView for Ctx<H> {
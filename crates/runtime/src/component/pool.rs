@@ -0,0 +1,154 @@
+use super::{new_store, Ctx, Handler};
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+
+use tokio::spawn;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, trace};
+
+/// A pre-instantiated instance of a component, along with its store
+struct WarmInstance<H>
+where
+    H: Handler,
+{
+    store: wasmtime::Store<Ctx<H>>,
+    instance: wasmtime::component::Instance,
+    /// Permit of the runtime-wide budget of warm instances, held while the instance is pooled
+    _permit: OwnedSemaphorePermit,
+}
+
+/// Pool of pre-instantiated instances of a component, so that invocations do not pay for
+/// instantiation. Taking an instance refills the pool in the background.
+///
+/// The pool is dropped along with its instances once the component stops being served
+pub(crate) struct WarmPool<H>
+where
+    H: Handler,
+{
+    engine: wasmtime::Engine,
+    pre: wasmtime::component::InstancePre<Ctx<H>>,
+    handler: H,
+    max_execution_time: Duration,
+    max_memory_size: Option<usize>,
+    /// Number of instances to keep warm
+    size: usize,
+    /// Runtime-wide budget of warm instances, shared by the pools of all components
+    budget: Arc<Semaphore>,
+    instances: Mutex<Vec<WarmInstance<H>>>,
+    /// Whether a task refilling the pool is running
+    filling: AtomicBool,
+}
+
+impl<H> WarmPool<H>
+where
+    H: Handler,
+{
+    /// Returns a new pool keeping `size` instances warm, and starts filling it
+    pub(crate) fn new(
+        engine: wasmtime::Engine,
+        pre: wasmtime::component::InstancePre<Ctx<H>>,
+        handler: H,
+        max_execution_time: Duration,
+        max_memory_size: Option<usize>,
+        size: usize,
+        budget: Arc<Semaphore>,
+    ) -> Arc<Self> {
+        let pool = Arc::new(Self {
+            engine,
+            pre,
+            handler,
+            max_execution_time,
+            max_memory_size,
+            size,
+            budget,
+            instances: Mutex::new(Vec::with_capacity(size)),
+            filling: AtomicBool::new(false),
+        });
+        pool.fill();
+        pool
+    }
+
+    /// Takes a warm instance from the pool, if any, and refills the pool
+    pub(crate) fn take(
+        self: &Arc<Self>,
+    ) -> Option<(wasmtime::Store<Ctx<H>>, wasmtime::component::Instance)> {
+        let warm = self
+            .instances
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        self.fill();
+        let WarmInstance {
+            mut store,
+            instance,
+            ..
+        } = warm?;
+        trace!("took warm instance");
+        // The deadline is relative to the epoch at which it is set, which for warm instances is
+        // when they were instantiated
+        store.set_epoch_deadline(self.max_execution_time.as_secs());
+        Some((store, instance))
+    }
+
+    fn len(&self) -> usize {
+        self.instances
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Instantiates the component until the pool is full, unless already doing so. Filling stops
+    /// early if the runtime-wide budget of warm instances is exhausted, or the pool is dropped
+    fn fill(self: &Arc<Self>) {
+        if self.len() >= self.size || self.filling.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let pool = Arc::downgrade(self);
+        spawn(async move {
+            loop {
+                let Some(pool) = Weak::upgrade(&pool) else {
+                    return;
+                };
+                if pool.len() >= pool.size {
+                    pool.filling.store(false, Ordering::Release);
+                    // An instance may have been taken before the flag was cleared
+                    if pool.len() >= pool.size || pool.filling.swap(true, Ordering::AcqRel) {
+                        return;
+                    }
+                    continue;
+                }
+                let Ok(permit) = Arc::clone(&pool.budget).try_acquire_owned() else {
+                    debug!("budget of warm instances exhausted, not filling pool");
+                    pool.filling.store(false, Ordering::Release);
+                    return;
+                };
+                let mut store = new_store(
+                    &pool.engine,
+                    pool.handler.clone(),
+                    pool.max_execution_time,
+                    pool.max_memory_size,
+                );
+                match pool.pre.instantiate_async(&mut store).await {
+                    Ok(instance) => {
+                        pool.instances
+                            .lock()
+                            .unwrap_or_else(PoisonError::into_inner)
+                            .push(WarmInstance {
+                                store,
+                                instance,
+                                _permit: permit,
+                            });
+                    }
+                    Err(err) => {
+                        debug!(?err, "failed to instantiate warm instance");
+                        pool.filling.store(false, Ordering::Release);
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
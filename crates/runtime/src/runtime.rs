@@ -4,9 +4,11 @@ use core::fmt;
 use core::fmt::Debug;
 use core::time::Duration;
 
+use std::sync::Arc;
 use std::thread;
 
 use anyhow::Context;
+use tokio::sync::Semaphore;
 use wasmtime::{InstanceAllocationStrategy, PoolingAllocationConfig};
 
 /// Default max linear memory for a component (256 MiB)
@@ -181,6 +183,12 @@ impl RuntimeBuilder {
                 engine,
                 component_config: self.component_config,
                 max_execution_time: self.max_execution_time,
+                // Keep at least half of the instance slots available for running instances
+                warm_budget: Arc::new(Semaphore::new(
+                    usize::try_from(self.max_components / 2)
+                        .unwrap_or_default()
+                        .min(Semaphore::MAX_PERMITS),
+                )),
             },
             epoch,
         ))
@@ -201,6 +209,9 @@ pub struct Runtime {
     pub(crate) engine: wasmtime::Engine,
     pub(crate) component_config: ComponentConfig,
     pub(crate) max_execution_time: Duration,
+    /// Budget of pre-instantiated instances kept warm across all components, see
+    /// [`crate::Component::set_warm_instances`]
+    pub(crate) warm_budget: Arc<Semaphore>,
 }
 
 impl Debug for Runtime {
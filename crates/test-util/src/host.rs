@@ -100,7 +100,7 @@ impl WasmCloudTestHost {
             host_key: Some(Arc::clone(&host_key)),
             provider_shutdown_delay: Some(Duration::from_millis(300)),
             allow_file_load: true,
-            secrets_topic_prefix,
+            secrets_topic_prefix: secrets_topic_prefix.into(),
             ..Default::default()
         };
         if let Some(psc) = policy_service_config {
@@ -12,12 +12,15 @@ use wasmcloud_core::logging::Level;
 #[cfg(feature = "otel")]
 use wasmcloud_core::tls;
 use wasmcloud_core::OtelConfig;
+#[cfg(feature = "otel")]
+use wasmcloud_core::OtelProtocol;
 
 #[cfg(feature = "otel")]
 pub mod context;
 
 mod traces;
 
+pub use traces::set_log_level;
 #[cfg(feature = "otel")]
 pub use traces::FlushGuard;
 
@@ -68,6 +71,31 @@ pub fn configure_observability(
     )
 }
 
+/// Returns the OpenTelemetry resource of all signals, made of the configured resource attributes
+/// and the service name
+#[cfg(feature = "otel")]
+pub(crate) fn get_resource(
+    service_name: impl Into<opentelemetry::Value>,
+    otel_config: &OtelConfig,
+) -> opentelemetry_sdk::Resource {
+    opentelemetry_sdk::Resource::new(
+        otel_config
+            .resource_attributes
+            .iter()
+            .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+            .chain([KeyValue::new("service.name", service_name)]),
+    )
+}
+
+/// Returns the encoding used by HTTP exporters for the configured protocol
+#[cfg(feature = "otel")]
+pub(crate) fn get_http_protocol(otel_config: &OtelConfig) -> opentelemetry_otlp::Protocol {
+    match otel_config.protocol {
+        OtelProtocol::HttpJson => opentelemetry_otlp::Protocol::HttpJson,
+        OtelProtocol::Http | OtelProtocol::Grpc => opentelemetry_otlp::Protocol::HttpBinary,
+    }
+}
+
 // This method builds a custom reqwest 0.11 Client, because the HttpClient trait
 // defined in the `opentelemetry-http` crate is defined against reqwest 0.11 types:
 // * https://github.com/open-telemetry/opentelemetry-rust/blob/opentelemetry-otlp-0.16.0/opentelemetry-http/src/lib.rs#L50-L65
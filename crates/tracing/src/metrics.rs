@@ -12,12 +12,12 @@ pub fn configure_metrics(
     use wasmcloud_core::OtelProtocol;
 
     let builder: MetricsExporterBuilder = match otel_config.protocol {
-        OtelProtocol::Http => {
+        OtelProtocol::Http | OtelProtocol::HttpJson => {
             let client = crate::get_http_client(otel_config)
                 .context("failed to get an http client for otel metrics exporter")?;
             opentelemetry_otlp::new_exporter()
                 .http()
-                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                .with_protocol(crate::get_http_protocol(otel_config))
                 .with_http_client(client)
                 .with_endpoint(otel_config.metrics_endpoint())
                 .into()
@@ -34,9 +34,7 @@ pub fn configure_metrics(
     opentelemetry_otlp::new_pipeline()
         .metrics(opentelemetry_sdk::runtime::Tokio)
         .with_exporter(builder)
-        .with_resource(opentelemetry_sdk::Resource::new(vec![
-            opentelemetry::KeyValue::new("service.name", service_name.to_string()),
-        ]))
+        .with_resource(crate::get_resource(service_name.to_string(), otel_config))
         .with_aggregation_selector(ExponentialHistogramAggregationSelector::new())
         .with_temporality_selector(
             opentelemetry_sdk::metrics::reader::DefaultTemporalitySelector::new(),
@@ -4,6 +4,7 @@ use std::io::{BufWriter, IsTerminal};
 use std::path::Path;
 #[cfg(feature = "otel")]
 use std::sync::Arc;
+use std::sync::{Mutex, PoisonError};
 
 #[cfg(feature = "otel")]
 use anyhow::Context as _;
@@ -17,14 +18,21 @@ use tracing_subscriber::fmt::time::SystemTime;
 use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::EnvFilter;
 #[cfg(feature = "otel")]
 use tracing_subscriber::Layer;
+use tracing_subscriber::{reload, EnvFilter};
 use wasmcloud_core::logging::Level;
 use wasmcloud_core::OtelConfig;
 #[cfg(feature = "otel")]
 use wasmcloud_core::OtelProtocol;
 
+/// Reloads a log level filter of the configured subscriber
+type LogLevelReloader = Box<dyn Fn(&Level) -> anyhow::Result<()> + Send + Sync>;
+
+/// Reloaders of the log level filters of the most recently configured subscriber, used by
+/// [`set_log_level`]
+static LOG_LEVEL_RELOADERS: Mutex<Vec<LogLevelReloader>> = Mutex::new(Vec::new());
+
 #[cfg(feature = "otel")]
 static LOG_PROVIDER: once_cell::sync::OnceCell<opentelemetry_sdk::logs::LoggerProvider> =
     once_cell::sync::OnceCell::new();
@@ -57,6 +65,40 @@ where
     }
 }
 
+/// Returns a reloadable log level filter, along with the function that reloads it
+fn reloadable_log_level_filter<S: 'static>(
+    log_level_override: Option<&Level>,
+) -> (reload::Layer<EnvFilter, S>, LogLevelReloader) {
+    let (filter, handle) = reload::Layer::new(get_log_level_filter(log_level_override));
+    (
+        filter,
+        Box::new(move |level| {
+            handle
+                .reload(get_log_level_filter(Some(level)))
+                .map_err(anyhow::Error::from)
+        }),
+    )
+}
+
+/// Changes the log level of the subscriber configured by [`configure_tracing`], taking effect
+/// immediately for new log events. Directives from `RUST_LOG` keep overriding the level.
+///
+/// This may be called from any thread at any time, concurrent calls are applied one after
+/// another, so the last one wins
+///
+/// # Errors
+///
+/// Returns an error if the configured subscriber was dropped
+pub fn set_log_level(level: &Level) -> anyhow::Result<()> {
+    let reloaders = LOG_LEVEL_RELOADERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for reload in reloaders.iter() {
+        reload(level)?;
+    }
+    Ok(())
+}
+
 /// This guard prevents early `drop()`ing of the tracing related internal data structures
 pub struct FlushGuard {
     _stderr: tracing_appender::non_blocking::WorkerGuard,
@@ -81,8 +123,9 @@ pub fn configure_tracing(
 ) -> anyhow::Result<(tracing::Dispatch, FlushGuard)> {
     let flame = flame_graph.map(FlameLayer::with_file).transpose()?;
     let (flame, flame_guard) = flame.map(|(l, g)| (Some(l), Some(g))).unwrap_or_default();
+    let (log_level_filter, reload_log_level) = reloadable_log_level_filter(log_level_override);
     let reg = tracing_subscriber::Registry::default()
-        .with(get_log_level_filter(log_level_override))
+        .with(log_level_filter)
         .with(flame);
     let stderr = std::io::stderr();
     let ansi = stderr.is_terminal();
@@ -104,6 +147,9 @@ pub fn configure_tracing(
         )
         .into()
     };
+    *LOG_LEVEL_RELOADERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = vec![reload_log_level];
 
     Ok((
         dispatch,
@@ -134,7 +180,9 @@ pub fn configure_tracing(
 ) -> anyhow::Result<(tracing::Dispatch, FlushGuard)> {
     let service_name = Arc::from(service_name);
 
-    let log_level_filter = get_log_level_filter(log_level_override);
+    let mut reloaders = Vec::with_capacity(3);
+    let (log_level_filter, reload_log_level) = reloadable_log_level_filter(log_level_override);
+    reloaders.push(reload_log_level);
     let traces = otel_config
         .traces_enabled()
         .then(|| {
@@ -148,7 +196,11 @@ pub fn configure_tracing(
     let logs = otel_config
         .logs_enabled()
         .then(|| get_otel_logging_layer(Arc::clone(&service_name), otel_config, log_level_override))
-        .transpose()?;
+        .transpose()?
+        .map(|(logs, reload_log_level)| {
+            reloaders.push(reload_log_level);
+            logs
+        });
     let flame = flame_graph.map(FlameLayer::with_file).transpose()?;
     let (flame, flame_guard) = flame
         .map(|(l, g)| {
@@ -158,8 +210,10 @@ pub fn configure_tracing(
             )
         })
         .unwrap_or_default();
+    let (base_log_level_filter, reload_log_level) = reloadable_log_level_filter(log_level_override);
+    reloaders.push(reload_log_level);
     let registry = tracing_subscriber::Registry::default()
-        .with(get_log_level_filter(log_level_override))
+        .with(base_log_level_filter)
         .with(traces)
         .with(logs)
         .with(flame);
@@ -187,6 +241,9 @@ pub fn configure_tracing(
             )
             .into()
     };
+    *LOG_LEVEL_RELOADERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = reloaders;
 
     Ok((
         dispatch,
@@ -210,14 +267,14 @@ where
     use tracing_opentelemetry::OpenTelemetryLayer;
 
     let builder: SpanExporterBuilder = match otel_config.protocol {
-        OtelProtocol::Http => {
+        OtelProtocol::Http | OtelProtocol::HttpJson => {
             let client = crate::get_http_client(otel_config)
                 .context("failed to get an http client for otel tracing exporter")?;
             opentelemetry_otlp::new_exporter()
                 .http()
                 .with_endpoint(otel_config.traces_endpoint())
                 .with_http_client(client)
-                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                .with_protocol(crate::get_http_protocol(otel_config))
                 .into()
         }
         OtelProtocol::Grpc => {
@@ -234,14 +291,14 @@ where
         .with_exporter(builder)
         .with_trace_config(
             opentelemetry_sdk::trace::config()
-                .with_sampler(opentelemetry_sdk::trace::Sampler::AlwaysOn)
+                .with_sampler(opentelemetry_sdk::trace::Sampler::TraceIdRatioBased(
+                    otel_config.trace_sampling_ratio,
+                ))
                 .with_id_generator(opentelemetry_sdk::trace::RandomIdGenerator::default())
                 .with_max_events_per_span(64)
                 .with_max_attributes_per_span(16)
                 .with_max_events_per_span(16)
-                .with_resource(opentelemetry_sdk::Resource::new(vec![
-                    opentelemetry::KeyValue::new("service.name", service_name),
-                ])),
+                .with_resource(crate::get_resource(service_name, otel_config)),
         )
         .install_batch(opentelemetry_sdk::runtime::Tokio)
         .context("failed to create OTEL tracer")?;
@@ -254,20 +311,20 @@ fn get_otel_logging_layer<S>(
     service_name: Arc<str>,
     otel_config: &OtelConfig,
     log_level_override: Option<&Level>,
-) -> anyhow::Result<impl tracing_subscriber::Layer<S>>
+) -> anyhow::Result<(impl tracing_subscriber::Layer<S>, LogLevelReloader)>
 where
     S: Subscriber,
     S: for<'a> tracing_subscriber::registry::LookupSpan<'a>,
 {
     let builder: LogExporterBuilder = match otel_config.protocol {
-        OtelProtocol::Http => {
+        OtelProtocol::Http | OtelProtocol::HttpJson => {
             let client = crate::get_http_client(otel_config)
                 .context("failed to get an http client for otel logging exporter")?;
             opentelemetry_otlp::new_exporter()
                 .http()
                 .with_endpoint(otel_config.logs_endpoint())
                 .with_http_client(client)
-                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                .with_protocol(crate::get_http_protocol(otel_config))
                 .into()
         }
         OtelProtocol::Grpc => {
@@ -281,12 +338,10 @@ where
 
     let log_provider = opentelemetry_otlp::new_pipeline()
         .logging()
-        .with_log_config(opentelemetry_sdk::logs::Config::default().with_resource(
-            opentelemetry_sdk::Resource::new(vec![opentelemetry::KeyValue::new(
-                "service.name",
-                service_name,
-            )]),
-        ))
+        .with_log_config(
+            opentelemetry_sdk::logs::Config::default()
+                .with_resource(crate::get_resource(service_name, otel_config)),
+        )
         .with_exporter(builder)
         .install_batch(opentelemetry_sdk::runtime::Tokio)
         .context("failed to create OTEL logger provider")?;
@@ -296,12 +351,13 @@ where
         .set(log_provider)
         .map_err(|_| anyhow::anyhow!("Logger provider already initialized"))?;
 
+    let (log_level_filter, reload_log_level) = reloadable_log_level_filter(log_level_override);
     let log_layer = opentelemetry_appender_tracing::layer::OpenTelemetryTracingBridge::new(
         LOG_PROVIDER.get().unwrap(),
     )
-    .with_filter(get_log_level_filter(log_level_override));
+    .with_filter(log_level_filter);
 
-    Ok(log_layer)
+    Ok((log_layer, reload_log_level))
 }
 
 #[cfg(feature = "otel")]
@@ -6,18 +6,21 @@ use std::time::Duration;
 
 use anyhow::{bail, Context};
 use clap::Parser;
-use nkeys::KeyPair;
+use nkeys::{KeyPair, KeyPairType};
 use regex::Regex;
 use tokio::time::{timeout, timeout_at};
 use tokio::{select, signal};
 use tracing::{warn, Level as TracingLogLevel};
 use tracing_subscriber::util::SubscriberInitExt as _;
 use wasmcloud_core::logging::Level as WasmcloudLogLevel;
-use wasmcloud_core::{OtelConfig, OtelProtocol};
+use wasmcloud_core::{
+    is_valid_trace_sampling_ratio, parse_resource_attribute, parse_resource_attributes_env,
+    EnvResourceAttributes, OtelConfig, OtelProtocol, PullPolicy,
+};
 use wasmcloud_host::oci::Config as OciConfig;
 use wasmcloud_host::url::Url;
 use wasmcloud_host::wasmbus::host_config::PolicyService as PolicyServiceConfig;
-use wasmcloud_host::WasmbusHostConfig;
+use wasmcloud_host::{PolicyDecision, SecretsTopicPrefix, WasmbusHostConfig};
 use wasmcloud_tracing::configure_observability;
 
 #[derive(Debug, Parser)]
@@ -77,6 +80,9 @@ struct Args {
     /// Delay, in milliseconds, between requesting a provider shut down and forcibly terminating its process
     #[clap(long = "provider-shutdown-delay-ms", alias = "provider-shutdown-delay", default_value = "300", env = "WASMCLOUD_PROV_SHUTDOWN_DELAY_MS", value_parser = parse_duration_millis)]
     provider_shutdown_delay: Duration,
+    /// Time, in milliseconds, to wait for a started provider to become healthy before terminating it and reporting it as failed
+    #[clap(long = "provider-startup-timeout-ms", default_value = "60000", env = "WASMCLOUD_PROV_STARTUP_TIMEOUT_MS", value_parser = parse_nonzero_duration_millis)]
+    provider_startup_timeout: Duration,
     /// Determines whether OCI images tagged latest are allowed to be pulled from OCI registries and started
     #[clap(long = "allow-latest", env = "WASMCLOUD_OCI_ALLOW_LATEST")]
     allow_latest: bool,
@@ -87,6 +93,12 @@ struct Args {
         value_delimiter = ','
     )]
     allowed_insecure: Vec<String>,
+    /// When to contact OCI registries for artifacts that may already be cached: 'revalidate' (default) pulls only if the reference resolves to a new digest, 'always' always pulls, 'if-not-present' pulls only uncached artifacts and 'never' only uses cached artifacts
+    #[clap(long = "oci-pull-policy", env = "WASMCLOUD_OCI_PULL_POLICY")]
+    oci_pull_policy: Option<PullPolicy>,
+    /// Registry to fetch OCI references that do not name one from, e.g. `myteam/mycomponent:1.0`, instead of Docker Hub
+    #[clap(long = "oci-default-registry", env = "WASMCLOUD_OCI_DEFAULT_REGISTRY")]
+    oci_default_registry: Option<String>,
     /// NATS Jetstream domain name
     #[clap(
         long = "js-domain",
@@ -94,6 +106,30 @@ struct Args {
         env = "WASMCLOUD_JS_DOMAIN"
     )]
     js_domain: Option<String>,
+    /// Name of the host's NATS connections, as shown by the NATS server. Defaults to `wasmcloud-host-<host public key>`
+    #[clap(
+        long = "nats-connection-name",
+        env = "WASMCLOUD_NATS_CONNECTION_NAME",
+        hide = true
+    )]
+    nats_connection_name: Option<String>,
+    /// Number of operations each NATS client buffers while disconnected. Buffered messages are kept in memory, so a large buffer may use up to this many times the maximum NATS payload per client
+    #[clap(
+        long = "nats-reconnect-buffer-size",
+        env = "WASMCLOUD_NATS_RECONNECT_BUFFER_SIZE",
+        hide = true
+    )]
+    nats_reconnect_buffer_size: Option<usize>,
+    /// Number of consecutive attempts to reconnect to NATS before giving up, unlimited by default
+    #[clap(
+        long = "nats-max-reconnects",
+        env = "WASMCLOUD_NATS_MAX_RECONNECTS",
+        hide = true
+    )]
+    nats_max_reconnects: Option<usize>,
+    /// Delay in milliseconds between attempts to reconnect to NATS, defaults to an exponential backoff capped at 4 seconds
+    #[clap(long = "nats-reconnect-delay-ms", env = "WASMCLOUD_NATS_RECONNECT_DELAY_MS", value_parser = parse_duration_millis, hide = true)]
+    nats_reconnect_delay: Option<Duration>,
     /// Denotes if a wasmCloud host should issue requests to a config service on startup
     #[clap(long = "config-service-enabled", env = "WASMCLOUD_CONFIG_SERVICE")]
     config_service_enabled: bool,
@@ -113,6 +149,9 @@ struct Args {
     /// Start the host with a set of labels, can be specified multiple times. This can alternatively be specified via environment variables prefixed with `WASMCLOUD_LABEL_`, e.g. `WASMCLOUD_LABEL_foo=bar`
     #[clap(short = 'l', long = "label")]
     label: Option<Vec<String>>,
+    /// File to read additional labels from, with one `key=value` pair per line. The file is watched for changes and labels in it take precedence over labels set with `--label`
+    #[clap(long = "labels-file", env = "WASMCLOUD_LABELS_FILE")]
+    labels_file: Option<PathBuf>,
 
     /// An IP address or DNS name to use to connect to NATS for Control Interface (CTL) messages, defaults to the value supplied to --nats-host if not supplied
     #[clap(long = "ctl-host", env = "WASMCLOUD_CTL_HOST", hide = true)]
@@ -144,6 +183,29 @@ struct Args {
     /// Optional flag to require host communication over TLS with a NATS server for CTL messages
     #[clap(long = "ctl-tls", env = "WASMCLOUD_CTL_TLS", hide = true)]
     ctl_tls: bool,
+    /// CA certificates to trust for CTL connections to NATS, in addition to the system roots
+    #[clap(
+        long = "ctl-tls-ca-path",
+        env = "WASMCLOUD_CTL_TLS_CA_PATH",
+        hide = true
+    )]
+    ctl_tls_ca_path: Option<PathBuf>,
+    /// A client certificate to present to NATS for CTL connections
+    #[clap(
+        long = "ctl-tls-client-cert",
+        env = "WASMCLOUD_CTL_TLS_CLIENT_CERT",
+        requires = "ctl_tls_client_key",
+        hide = true
+    )]
+    ctl_tls_client_cert: Option<PathBuf>,
+    /// The private key of the client certificate supplied to --ctl-tls-client-cert
+    #[clap(
+        long = "ctl-tls-client-key",
+        env = "WASMCLOUD_CTL_TLS_CLIENT_KEY",
+        requires = "ctl_tls_client_cert",
+        hide = true
+    )]
+    ctl_tls_client_key: Option<PathBuf>,
     /// Advanced: A prefix to use for all CTL topics
     #[clap(
         long = "ctl-topic-prefix",
@@ -152,6 +214,13 @@ struct Args {
         hide = true
     )]
     ctl_topic_prefix: String,
+    /// Advanced: The NATS queue group of the lattice-wide CTL subscriptions (links, claims and config). Hosts in the same group share these requests, hosts in different groups each handle every request
+    #[clap(
+        long = "ctl-queue-group",
+        env = "WASMCLOUD_CTL_QUEUE_GROUP",
+        hide = true
+    )]
+    ctl_queue_group: Option<String>,
 
     /// An IP address or DNS name to use to connect to NATS for RPC messages, defaults to the value supplied to --nats-host if not supplied
     #[clap(long = "rpc-host", env = "WASMCLOUD_RPC_HOST", hide = true)]
@@ -183,9 +252,39 @@ struct Args {
     /// Timeout in milliseconds for all RPC calls
     #[clap(long = "rpc-timeout-ms", default_value = "2000", env = "WASMCLOUD_RPC_TIMEOUT_MS", value_parser = parse_duration_millis, hide = true)]
     rpc_timeout_ms: Duration,
+    /// Maximum size in bytes of the parameters of an RPC call made by a component, defaults to the maximum payload of the NATS server
+    #[clap(
+        long = "rpc-max-payload",
+        env = "WASMCLOUD_RPC_MAX_PAYLOAD",
+        hide = true
+    )]
+    rpc_max_payload: Option<usize>,
     /// Optional flag to require host communication over TLS with a NATS server for RPC messages
     #[clap(long = "rpc-tls", env = "WASMCLOUD_RPC_TLS", hide = true)]
     rpc_tls: bool,
+    /// CA certificates to trust for RPC connections to NATS, in addition to the system roots
+    #[clap(
+        long = "rpc-tls-ca-path",
+        env = "WASMCLOUD_RPC_TLS_CA_PATH",
+        hide = true
+    )]
+    rpc_tls_ca_path: Option<PathBuf>,
+    /// A client certificate to present to NATS for RPC connections
+    #[clap(
+        long = "rpc-tls-client-cert",
+        env = "WASMCLOUD_RPC_TLS_CLIENT_CERT",
+        requires = "rpc_tls_client_key",
+        hide = true
+    )]
+    rpc_tls_client_cert: Option<PathBuf>,
+    /// The private key of the client certificate supplied to --rpc-tls-client-cert
+    #[clap(
+        long = "rpc-tls-client-key",
+        env = "WASMCLOUD_RPC_TLS_CLIENT_KEY",
+        requires = "rpc_tls_client_cert",
+        hide = true
+    )]
+    rpc_tls_client_key: Option<PathBuf>,
 
     /// If provided, enables policy checks on start actions and component invocations
     #[clap(long = "policy-topic", env = "WASMCLOUD_POLICY_TOPIC")]
@@ -197,6 +296,13 @@ struct Args {
         requires = "policy_topic"
     )]
     policy_changes_topic: Option<String>,
+    /// If provided, policy decisions requested at about the same time are batched into a single request on this topic. Requires `policy_topic` to be set.
+    #[clap(
+        long = "policy-batch-topic",
+        env = "WASMCLOUD_POLICY_BATCH_TOPIC",
+        requires = "policy_topic"
+    )]
+    policy_batch_topic: Option<String>,
     /// If provided, allows to set a custom Max Execution time for the Host in ms.
     #[clap(long = "max-execution-time-ms", default_value = "600000", env = "WASMCLOUD_MAX_EXECUTION_TIME_MS", value_parser = parse_duration_millis)]
     max_execution_time: Duration,
@@ -206,6 +312,9 @@ struct Args {
     /// The maximum byte size of a component binary that can be loaded (default 50 MiB)
     #[clap(long = "max-component-size-bytes", default_value_t = 50 * 1024 * 1024, env = "WASMCLOUD_MAX_COMPONENT_SIZE")]
     max_component_size: u64,
+    /// The maximum byte size of a provider archive that can be downloaded (default unlimited)
+    #[clap(long = "max-provider-size-bytes", env = "WASMCLOUD_MAX_PROVIDER_SIZE")]
+    max_provider_size: Option<u64>,
     /// The maximum number of components that can be run simultaneously
     #[clap(
         long = "max-components",
@@ -213,6 +322,20 @@ struct Args {
         env = "WASMCLOUD_MAX_COMPONENTS"
     )]
     max_components: u32,
+    /// The number of pre-instantiated instances kept warm for each component, so that `wasi:http/incoming-handler` and `wasmcloud:messaging/handler` invocations after a scale-up do not wait for instantiation
+    #[clap(
+        long = "warm-instances",
+        default_value_t = 0,
+        env = "WASMCLOUD_WARM_INSTANCES"
+    )]
+    warm_instances: u32,
+    /// The maximum number of components and providers that are downloaded at the same time
+    #[clap(
+        long = "max-concurrent-downloads",
+        default_value_t = 4,
+        env = "WASMCLOUD_MAX_CONCURRENT_DOWNLOADS"
+    )]
+    max_concurrent_downloads: usize,
     /// If provided, allows setting a custom timeout for requesting policy decisions. Defaults to one second. Requires `policy_topic` to be set.
     #[clap(
         long = "policy-timeout-ms",
@@ -221,10 +344,78 @@ struct Args {
         value_parser = parse_duration_millis,
     )]
     policy_timeout_ms: Option<Duration>,
+    /// If provided, sets how many times policy requests that time out or find no responders are retried before applying the default decision. Defaults to 2. Requires `policy_topic` to be set.
+    #[clap(
+        long = "policy-retries",
+        env = "WASMCLOUD_POLICY_RETRIES",
+        requires = "policy_topic"
+    )]
+    policy_retries: Option<u32>,
+    /// If provided, sets the delay before the first retry of a policy request, doubled for every subsequent retry. Defaults to 100 milliseconds. Requires `policy_topic` to be set.
+    #[clap(
+        long = "policy-retry-backoff-ms",
+        env = "WASMCLOUD_POLICY_RETRY_BACKOFF_MS",
+        requires = "policy_topic",
+        value_parser = parse_duration_millis,
+    )]
+    policy_retry_backoff: Option<Duration>,
+    /// If provided, policy decisions are cached for this long instead of until they are overridden on `policy_changes_topic`. Requires `policy_topic` to be set.
+    #[clap(
+        long = "policy-decision-ttl-ms",
+        env = "WASMCLOUD_POLICY_DECISION_TTL_MS",
+        requires = "policy_topic",
+        value_parser = parse_duration_millis,
+    )]
+    policy_decision_ttl: Option<Duration>,
+    /// The decision to apply when the policy service does not respond in time or is unavailable, either `allow` or `deny`. Defaults to `deny`. Requires `policy_topic` to be set.
+    #[clap(
+        long = "policy-default-decision",
+        env = "WASMCLOUD_POLICY_DEFAULT_DECISION",
+        requires = "policy_topic"
+    )]
+    policy_default_decision: Option<PolicyDecision>,
 
     /// If provided, enables interfacing with a secrets backend for secret retrieval over the given topic prefix. Must not be empty.
     #[clap(long = "secrets-topic", env = "WASMCLOUD_SECRETS_TOPIC")]
     secrets_topic_prefix: Option<String>,
+    /// Topic prefixes of additional secrets backends as `scheme=prefix` pairs, e.g. `vault=wasmcloud.secrets.vault`. Secret references whose backend has the given scheme are fetched over that prefix instead of `secrets_topic`. Can be specified multiple times
+    #[clap(
+        long = "secrets-backend-topic",
+        env = "WASMCLOUD_SECRETS_BACKEND_TOPICS",
+        value_delimiter = ',',
+        value_parser = parse_secrets_backend_topic,
+    )]
+    secrets_backend_topics: Vec<(String, String)>,
+    /// If provided, resolved secrets are cached for this long instead of being fetched from the secrets backend every time they are requested
+    #[clap(
+        long = "secrets-cache-ttl-ms",
+        env = "WASMCLOUD_SECRETS_CACHE_TTL_MS",
+        value_parser = parse_duration_millis,
+    )]
+    secrets_cache_ttl: Option<Duration>,
+    /// The maximum number of resolved secrets to cache
+    #[clap(
+        long = "secrets-cache-max-entries",
+        default_value_t = 1024,
+        env = "WASMCLOUD_SECRETS_CACHE_MAX_ENTRIES"
+    )]
+    secrets_cache_max_entries: usize,
+    /// How long in milliseconds a request to a secrets backend may take
+    #[clap(
+        long = "secrets-timeout-ms",
+        default_value = "10000",
+        env = "WASMCLOUD_SECRETS_TIMEOUT_MS",
+        value_parser = parse_duration_millis,
+    )]
+    secrets_timeout: Duration,
+    /// Interval in seconds at which the secrets backends are checked to be reachable, `0` disables periodic checks
+    #[clap(
+        long = "secrets-health-check-interval-seconds",
+        default_value = "30",
+        env = "WASMCLOUD_SECRETS_HEALTH_CHECK_INTERVAL",
+        value_parser = parse_duration_secs,
+    )]
+    secrets_health_check_interval: Duration,
 
     /// Used in tandem with `oci_user` and `oci_password` to override credentials for a specific OCI registry.
     #[clap(
@@ -306,7 +497,7 @@ struct Args {
     )]
     logs_endpoint: Option<String>,
 
-    /// Configures whether grpc or http will be used for exporting the enabled telemetry. This defaults to 'http'.
+    /// Configures whether grpc, http (protobuf) or http/json will be used for exporting the enabled telemetry. This defaults to 'http'.
     #[clap(
         long = "observability-protocol",
         env = "WASMCLOUD_OBSERVABILITY_PROTOCOL",
@@ -314,6 +505,23 @@ struct Args {
     )]
     observability_protocol: Option<OtelProtocol>,
 
+    /// The ratio of traces to sample and export, from 0.0 (none) to 1.0 (all). This defaults to 1.0.
+    #[clap(
+        long = "trace-sampling-ratio",
+        env = "WASMCLOUD_TRACE_SAMPLING_RATIO",
+        default_value_t = 1.0,
+        value_parser = parse_trace_sampling_ratio
+    )]
+    trace_sampling_ratio: f64,
+
+    /// Additional attributes of the OpenTelemetry resource of all signals, as a repeatable or comma-separated set of `key=value` pairs. These are merged with and take precedence over the attributes set in `OTEL_RESOURCE_ATTRIBUTES`
+    #[clap(
+        long = "otel-resource-attribute",
+        value_delimiter = ',',
+        value_parser = parse_resource_attribute
+    )]
+    otel_resource_attributes: Vec<(String, String)>,
+
     /// Path to generate flame graph at
     #[clap(long = "flame-graph", env = "WASMCLOUD_FLAME_GRAPH")]
     flame_graph: Option<String>,
@@ -325,6 +533,14 @@ struct Args {
     /// If provided, overrides the default heartbeat interval of every 30 seconds. Provided value is interpreted as seconds.
     #[arg(long = "heartbeat-interval-seconds", env = "WASMCLOUD_HEARTBEAT_INTERVAL", value_parser = parse_duration_secs, hide = true)]
     heartbeat_interval: Option<Duration>,
+
+    /// If provided, delays the first heartbeat and every heartbeat interval by a random amount of up to this many milliseconds, to spread heartbeats of hosts started together
+    #[arg(long = "heartbeat-jitter-ms", env = "WASMCLOUD_HEARTBEAT_JITTER_MS", value_parser = parse_duration_millis, hide = true)]
+    heartbeat_jitter: Option<Duration>,
+
+    /// If provided, reloads the trusted root certificates used to fetch OCI artifacts from the system trust store and `--tls-ca-path` at this interval, in seconds
+    #[arg(long = "trust-refresh-interval-seconds", env = "WASMCLOUD_TRUST_REFRESH_INTERVAL", value_parser = parse_nonzero_duration_secs)]
+    trust_refresh_interval: Option<Duration>,
 }
 
 const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
@@ -338,6 +554,27 @@ async fn main() -> anyhow::Result<()> {
         ensure_certs_for_paths(tls_ca_paths)?;
     }
 
+    let host_key = args
+        .host_seed
+        .as_deref()
+        .map(KeyPair::from_seed)
+        .transpose()
+        .context("failed to construct host key pair from seed")?
+        .unwrap_or_else(|| KeyPair::new(KeyPairType::Server));
+    let host_key = Arc::new(host_key);
+
+    // Reserved attributes set for the whole deployment are dropped rather than failing startup,
+    // and reported once logging is configured
+    let EnvResourceAttributes {
+        attributes: mut resource_attributes,
+        dropped: dropped_resource_attributes,
+    } = match env::var("OTEL_RESOURCE_ATTRIBUTES") {
+        Ok(attributes) => parse_resource_attributes_env(&attributes)
+            .context("failed to parse `OTEL_RESOURCE_ATTRIBUTES`")?,
+        Err(_) => EnvResourceAttributes::default(),
+    };
+    resource_attributes.extend(args.otel_resource_attributes);
+
     let trace_level = WasmcloudLogLevel::from(args.trace_level);
     let otel_config = OtelConfig {
         enable_observability: args.enable_observability,
@@ -351,12 +588,18 @@ async fn main() -> anyhow::Result<()> {
         protocol: args.observability_protocol.unwrap_or_default(),
         additional_ca_paths: args.tls_ca_paths.clone().unwrap_or_default(),
         trace_level,
+        trace_sampling_ratio: args.trace_sampling_ratio,
+        resource_attributes: resource_attributes.into_iter().collect(),
     };
+    let mut host_otel_config = otel_config.clone();
+    host_otel_config
+        .resource_attributes
+        .insert("service.instance.id".into(), host_key.public_key());
     let log_level = WasmcloudLogLevel::from(args.log_level);
 
     let _guard = match configure_observability(
         "wasmcloud-host",
-        &otel_config,
+        &host_otel_config,
         args.enable_structured_logging,
         args.flame_graph,
         Some(&log_level),
@@ -366,6 +609,15 @@ async fn main() -> anyhow::Result<()> {
             dispatch
                 .try_init()
                 .context("failed to init observability for host")?;
+            for mismatch in otel_config.protocol_mismatches() {
+                warn!("{mismatch}");
+            }
+            for key in dropped_resource_attributes {
+                warn!(
+                    %key,
+                    "ignoring reserved resource attribute set in `OTEL_RESOURCE_ATTRIBUTES`"
+                );
+            }
             Some(guard)
         }
         Err(e) => {
@@ -387,13 +639,6 @@ async fn main() -> anyhow::Result<()> {
     ))
     .context("failed to construct a valid `rpc_nats_url` using `rpc-host` and `rpc-port`")?;
 
-    let host_key = args
-        .host_seed
-        .as_deref()
-        .map(KeyPair::from_seed)
-        .transpose()
-        .context("failed to construct host key pair from seed")?
-        .map(Arc::new);
     let (nats_jwt, nats_key) =
         parse_nats_credentials(args.nats_creds, args.nats_jwt, args.nats_seed)
             .await
@@ -411,6 +656,8 @@ async fn main() -> anyhow::Result<()> {
         oci_registry: args.oci_registry,
         oci_user: args.oci_user,
         oci_password: args.oci_password,
+        pull_policy: args.oci_pull_policy.unwrap_or_default(),
+        default_registry: args.oci_default_registry,
     };
     if let Some(policy_topic) = args.policy_topic.as_deref() {
         anyhow::ensure!(
@@ -421,7 +668,12 @@ async fn main() -> anyhow::Result<()> {
     let policy_service_config = PolicyServiceConfig {
         policy_topic: args.policy_topic,
         policy_changes_topic: args.policy_changes_topic,
+        policy_batch_topic: args.policy_batch_topic,
         policy_timeout_ms: args.policy_timeout_ms,
+        policy_retries: args.policy_retries,
+        policy_retry_backoff: args.policy_retry_backoff,
+        policy_decision_ttl: args.policy_decision_ttl,
+        policy_default_decision: args.policy_default_decision.unwrap_or_default(),
     };
     let mut labels = args
         .label
@@ -446,7 +698,11 @@ async fn main() -> anyhow::Result<()> {
         }
         Some((key, value))
     }));
-    if let Some(secrets_topic) = args.secrets_topic_prefix.as_deref() {
+    let secrets_topic_prefix = args.secrets_backend_topics.into_iter().fold(
+        SecretsTopicPrefix::from(args.secrets_topic_prefix),
+        |prefix, (scheme, topic)| prefix.with_scheme(scheme, topic),
+    );
+    for secrets_topic in secrets_topic_prefix.prefixes() {
         anyhow::ensure!(
             validate_nats_subject(secrets_topic).is_ok(),
             "Invalid secrets topic"
@@ -454,34 +710,62 @@ async fn main() -> anyhow::Result<()> {
     }
     let (host, shutdown) = Box::pin(wasmcloud_host::wasmbus::Host::new(WasmbusHostConfig {
         ctl_nats_url,
+        ctl_nats_urls: Vec::new(),
         lattice: Arc::from(args.lattice),
-        host_key,
+        host_key: Some(host_key),
         config_service_enabled: args.config_service_enabled,
         js_domain: args.js_domain,
+        nats_connection_name: args.nats_connection_name,
+        nats_reconnect_buffer_size: args.nats_reconnect_buffer_size,
+        nats_max_reconnects: args.nats_max_reconnects,
+        nats_reconnect_delay: args.nats_reconnect_delay,
         labels,
+        labels_file: args.labels_file,
         provider_shutdown_delay: Some(args.provider_shutdown_delay),
+        provider_startup_timeout: Some(args.provider_startup_timeout),
         oci_opts,
         ctl_jwt: ctl_jwt.or_else(|| nats_jwt.clone()),
         ctl_key: ctl_key.or_else(|| nats_key.clone()),
         ctl_tls: args.ctl_tls,
+        ctl_tls_ca_path: args.ctl_tls_ca_path,
+        ctl_tls_client_cert: args.ctl_tls_client_cert,
+        ctl_tls_client_key: args.ctl_tls_client_key,
         ctl_topic_prefix: args.ctl_topic_prefix,
+        ctl_queue_group: args.ctl_queue_group,
         rpc_nats_url,
+        rpc_nats_urls: Vec::new(),
         rpc_timeout: args.rpc_timeout_ms,
+        rpc_max_payload: args.rpc_max_payload,
         rpc_jwt: rpc_jwt.or_else(|| nats_jwt.clone()),
         rpc_key: rpc_key.or_else(|| nats_key.clone()),
         rpc_tls: args.rpc_tls,
+        rpc_tls_ca_path: args.rpc_tls_ca_path,
+        rpc_tls_client_cert: args.rpc_tls_client_cert,
+        rpc_tls_client_key: args.rpc_tls_client_key,
         allow_file_load: args.allow_file_load,
         log_level,
         enable_structured_logging: args.enable_structured_logging,
         otel_config,
         policy_service_config,
-        secrets_topic_prefix: args.secrets_topic_prefix,
+        secrets_topic_prefix,
+        secrets_cache_ttl: args.secrets_cache_ttl,
+        secrets_cache_max_entries: args.secrets_cache_max_entries,
+        secrets_timeout: args.secrets_timeout,
+        secrets_health_check_interval: (!args.secrets_health_check_interval.is_zero())
+            .then_some(args.secrets_health_check_interval),
         version: env!("CARGO_PKG_VERSION").to_string(),
         max_execution_time: args.max_execution_time,
         max_linear_memory: args.max_linear_memory,
         max_component_size: args.max_component_size,
+        max_provider_size: args.max_provider_size,
         max_components: args.max_components,
+        warm_instances: args.warm_instances,
+        component_limits: HashMap::new(),
+        max_concurrent_downloads: args.max_concurrent_downloads,
         heartbeat_interval: args.heartbeat_interval,
+        heartbeat_jitter: args.heartbeat_jitter,
+        trust_refresh_interval: args.trust_refresh_interval,
+        lattice_overrides: HashMap::new(),
     }))
     .await
     .context("failed to initialize host")?;
@@ -543,6 +827,34 @@ fn parse_duration_secs(arg: &str) -> anyhow::Result<Duration> {
         .map_err(|e| anyhow::anyhow!(e))
 }
 
+fn parse_nonzero_duration_millis(arg: &str) -> anyhow::Result<Duration> {
+    let duration = parse_duration_millis(arg)?;
+    anyhow::ensure!(!duration.is_zero(), "duration must be greater than zero");
+    Ok(duration)
+}
+
+fn parse_nonzero_duration_secs(arg: &str) -> anyhow::Result<Duration> {
+    let duration = parse_duration_secs(arg)?;
+    anyhow::ensure!(!duration.is_zero(), "duration must be greater than zero");
+    Ok(duration)
+}
+
+fn parse_trace_sampling_ratio(arg: &str) -> anyhow::Result<f64> {
+    let ratio = arg.parse()?;
+    anyhow::ensure!(
+        is_valid_trace_sampling_ratio(ratio),
+        "trace sampling ratio must be within 0.0 and 1.0"
+    );
+    Ok(ratio)
+}
+
+fn parse_secrets_backend_topic(pair: &str) -> anyhow::Result<(String, String)> {
+    match pair.split_once('=') {
+        Some((scheme, topic)) if !scheme.is_empty() => Ok((scheme.into(), topic.into())),
+        _ => bail!("invalid secrets backend topic format `{pair}`. Expected `scheme=prefix`"),
+    }
+}
+
 fn parse_label(labelpair: &str) -> anyhow::Result<(String, String)> {
     match labelpair.split('=').collect::<Vec<&str>>()[..] {
         [k, v] => Ok((k.to_string(), v.to_string())),
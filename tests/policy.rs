@@ -46,6 +46,7 @@ async fn policy_always_deny() -> anyhow::Result<()> {
             policy_topic: Some("test-policy".into()),
             policy_changes_topic: Some("test-policy-changes".into()),
             policy_timeout_ms: Some(Duration::from_millis(100)),
+            ..Default::default()
         }),
         None,
     )